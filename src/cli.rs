@@ -1,10 +1,15 @@
 //! CLI argument definitions and top-level command dispatch.
 
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Parser, Subcommand};
+use std::num::NonZeroUsize;
 use std::process::ExitCode;
 
-use crate::commands;
+use crate::commands::{self, PrOptions};
 use crate::env;
+use crate::github::MergeMethod;
+use crate::stack::{MoveTarget, Navigation};
+use crate::sync_state::{Journal, Outcome};
+use crate::util;
 
 #[derive(Debug, Parser)]
 #[command(
@@ -23,15 +28,112 @@ enum Commands {
     New {
         /// Name of the branch to create.
         branch: String,
+        /// Only create and record the branch locally; skip pushing and PR creation.
+        #[arg(long, conflicts_with_all = ["draft", "no_draft", "title", "body_file", "reviewers", "assignees", "labels"])]
+        local: bool,
+        #[command(flatten)]
+        pr: PrOptions,
+    },
+    /// Insert a new branch between the current branch and its children.
+    Insert {
+        /// Name of the branch to create.
+        #[arg(required_unless_present_any = ["continue_edit", "reset_edit"])]
+        branch: Option<String>,
+        /// Continue a previously interrupted insert.
+        #[arg(long = "continue", conflicts_with_all = ["branch", "reset_edit"])]
+        continue_edit: bool,
+        /// Discard saved insert state.
+        #[arg(long = "reset", conflicts_with = "branch")]
+        reset_edit: bool,
+    },
+    /// Move a branch elsewhere in the stack; its children close the gap.
+    #[command(group(ArgGroup::new("target").args(["onto", "before"])))]
+    Move {
+        /// Branch to move.
+        #[arg(
+            required_unless_present_any = ["continue_edit", "reset_edit"],
+            requires = "target"
+        )]
+        branch: Option<String>,
+        /// Stack the branch directly on this branch (or the default branch).
+        #[arg(long, value_name = "BRANCH", requires = "branch")]
+        onto: Option<String>,
+        /// Put the branch between this branch and its parent.
+        #[arg(long, value_name = "BRANCH", requires = "branch")]
+        before: Option<String>,
+        /// Continue a previously interrupted move.
+        #[arg(long = "continue", conflicts_with_all = ["branch", "reset_edit"])]
+        continue_edit: bool,
+        /// Discard saved move state.
+        #[arg(long = "reset", conflicts_with = "branch")]
+        reset_edit: bool,
+    },
+    /// Fold the current branch into its parent and close its PR.
+    Fold {
+        /// Continue a previously interrupted fold.
+        #[arg(long = "continue", conflicts_with = "reset_edit")]
+        continue_edit: bool,
+        /// Discard saved fold state.
+        #[arg(long = "reset")]
+        reset_edit: bool,
+    },
+    /// Drop a branch from its stack, moving its children onto its parent.
+    Remove {
+        /// Branch to remove (defaults to the current branch).
+        #[arg(conflicts_with = "continue_edit")]
+        branch: Option<String>,
+        /// Also delete the branch locally and on the remote.
+        #[arg(long, conflicts_with = "continue_edit")]
+        delete: bool,
+        /// Continue a previously interrupted remove.
+        #[arg(long = "continue", conflicts_with = "reset_edit")]
+        continue_edit: bool,
+        /// Discard saved remove state.
+        #[arg(long = "reset", conflicts_with_all = ["branch", "delete"])]
+        reset_edit: bool,
+    },
+    /// Rename the current branch, recreating its PR and retargeting its children.
+    Rename {
+        /// New name of the branch.
+        #[arg(required_unless_present_any = ["continue_edit", "reset_edit"])]
+        new_name: Option<String>,
+        /// Continue a previously interrupted rename.
+        #[arg(long = "continue", conflicts_with_all = ["new_name", "reset_edit"])]
+        continue_edit: bool,
+        /// Discard saved rename state.
+        #[arg(long = "reset", conflicts_with = "new_name")]
+        reset_edit: bool,
+    },
+    /// Split the current branch into stacked branches at commit boundaries.
+    Split {
+        /// New branch and the first commit it takes over (repeatable; prompts
+        /// for split points when omitted).
+        #[arg(value_name = "BRANCH=COMMIT", conflicts_with = "continue_edit")]
+        points: Vec<String>,
+        /// Continue a previously interrupted split.
+        #[arg(long = "continue", conflicts_with = "reset_edit")]
+        continue_edit: bool,
+        /// Discard saved split state.
+        #[arg(long = "reset", conflicts_with = "points")]
+        reset_edit: bool,
     },
     /// Create a PR for the current branch if missing.
     Submit {
         /// Base branch for the PR (auto-detects the stack parent when omitted).
-        #[arg(long)]
+        #[arg(long, conflicts_with = "stack")]
         base: Option<String>,
+        /// Push and open PRs for every branch in the stack that lacks one, root to tip.
+        #[arg(long, conflicts_with_all = ["title", "body_file"])]
+        stack: bool,
+        #[command(flatten)]
+        pr: PrOptions,
     },
     /// Show detected stack and PR state.
-    Status,
+    Status {
+        /// Print a versioned JSON document instead of human-readable text.
+        #[arg(long)]
+        json: bool,
+    },
     /// Restack/rebase the local stack.
     Sync {
         /// Continue a previously interrupted sync run.
@@ -40,16 +142,130 @@ enum Commands {
         /// Discard saved sync state and recompute sync from scratch.
         #[arg(long = "reset", conflicts_with = "continue_sync")]
         reset_sync: bool,
+        /// Print the rebase plan without rebasing or saving any state.
+        #[arg(long, conflicts_with_all = ["continue_sync", "reset_sync"])]
+        dry_run: bool,
+    },
+    /// Rebase local branches onto their recorded parents without contacting GitHub.
+    Restack {
+        /// Continue a previously interrupted restack run.
+        #[arg(long = "continue", conflicts_with = "reset_restack")]
+        continue_restack: bool,
+        /// Discard saved restack state and recompute from scratch.
+        #[arg(long = "reset", conflicts_with = "continue_restack")]
+        reset_restack: bool,
+    },
+    /// Restore the branches rewritten by the last sync to their previous heads.
+    Undo,
+    /// Show the journal of recent stck operations.
+    Log {
+        /// Number of operations to show, newest first.
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Print the journal entries as JSON.
+        #[arg(long)]
+        json: bool,
     },
     /// Push rewritten branches and update PR base targets.
-    Push,
+    Push {
+        /// Print the planned pushes and PR retargets without running them.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Rewrite the stack navigation block in every open PR description of the stack.
+    Nav,
+    /// Merge the bottom open PR and restack the branches above it.
+    Land {
+        /// Merge strategy to use (defaults to the first one the repository allows).
+        #[arg(long, value_enum)]
+        method: Option<MergeMethod>,
+    },
+    /// Check out the child branch above the current one.
+    Up {
+        /// Number of branches to move.
+        #[arg(long, default_value = "1")]
+        steps: NonZeroUsize,
+    },
+    /// Check out the parent branch below the current one.
+    Down {
+        /// Number of branches to move.
+        #[arg(long, default_value = "1")]
+        steps: NonZeroUsize,
+    },
+    /// Check out the tip of the stack.
+    Top,
+    /// Check out the lowest open branch of the stack.
+    Bottom,
+    /// Read or change `stck` settings.
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigAction {
+    /// Print the effective value of a setting.
+    Get {
+        /// Setting name.
+        key: String,
+    },
+    /// Store a setting in the repository's git config.
+    Set {
+        /// Setting name.
+        key: String,
+        /// New value.
+        value: String,
+        /// Write to the user config file instead of the repository.
+        #[arg(long)]
+        user: bool,
+    },
+    /// List every setting with its effective value and where it comes from.
+    List,
 }
 
 /// Parse CLI arguments, run preflight checks, and dispatch to a command handler.
 pub fn run() -> ExitCode {
     let cli = Cli::parse();
 
-    let preflight = match env::run_preflight() {
+    // `config` only touches settings, so it runs without any preflight.
+    if let Commands::Config { action } = cli.command {
+        return match action {
+            ConfigAction::Get { key } => commands::run_config_get(&key),
+            ConfigAction::Set { key, value, user } => commands::run_config_set(&key, &value, user),
+            ConfigAction::List => commands::run_config_list(),
+        };
+    }
+
+    // `log` only reads the journal.
+    if let Commands::Log { limit, json } = cli.command {
+        return commands::run_log(limit, json);
+    }
+
+    // `undo` must also run mid-rebase, on a detached HEAD with conflicts.
+    if let Commands::Undo = cli.command {
+        return journaled(|_| commands::run_undo());
+    }
+
+    // `restack` works from local metadata alone, so it skips the GitHub checks.
+    if let Commands::Restack {
+        continue_restack,
+        reset_restack,
+    } = cli.command
+    {
+        return match env::run_local_preflight() {
+            Ok(mut context) => journaled(|journal| {
+                context.journal = journal.clone();
+                commands::run_restack(&context, continue_restack, reset_restack)
+            }),
+            Err(message) => {
+                eprintln!("error: {message}");
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    let mut preflight = match env::run_preflight() {
         Ok(preflight) => preflight,
         Err(message) => {
             eprintln!("error: {message}");
@@ -57,25 +273,163 @@ pub fn run() -> ExitCode {
         }
     };
 
-    match cli.command {
-        Commands::Status => commands::run_status(&preflight),
-        Commands::New { branch } => commands::run_new(&preflight, &branch),
-        Commands::Submit { base } => commands::run_submit(&preflight, base.as_deref()),
+    if changes_the_stack(&cli.command) {
+        journaled(|journal| {
+            preflight.journal = journal.clone();
+            dispatch(&preflight, cli.command)
+        })
+    } else {
+        dispatch(&preflight, cli.command)
+    }
+}
+
+/// Whether `command` can change branches or PRs and is therefore journaled.
+fn changes_the_stack(command: &Commands) -> bool {
+    match command {
+        Commands::Sync { dry_run, .. } | Commands::Push { dry_run } => !dry_run,
+        Commands::New { .. }
+        | Commands::Insert { .. }
+        | Commands::Move { .. }
+        | Commands::Fold { .. }
+        | Commands::Remove { .. }
+        | Commands::Rename { .. }
+        | Commands::Split { .. }
+        | Commands::Submit { .. }
+        | Commands::Restack { .. }
+        | Commands::Undo
+        | Commands::Nav
+        | Commands::Land { .. } => true,
+        Commands::Status { .. }
+        | Commands::Up { .. }
+        | Commands::Down { .. }
+        | Commands::Top
+        | Commands::Bottom
+        | Commands::Config { .. }
+        | Commands::Log { .. } => false,
+    }
+}
+
+/// Run `command` with a journal handle, appending its branch and PR changes
+/// to the journal when it exits.
+fn journaled(command: impl FnOnce(&Journal) -> ExitCode) -> ExitCode {
+    let arguments = std::env::args()
+        .skip(1)
+        .map(|argument| util::shell_word(&argument))
+        .collect::<Vec<_>>();
+    let journal = Journal::begin(arguments.join(" "));
+    let code = command(&journal);
+    journal.finish(if code == ExitCode::SUCCESS {
+        Outcome::Succeeded
+    } else {
+        Outcome::Failed
+    });
+    code
+}
+
+/// Dispatch a command that needs the full preflight context.
+fn dispatch(preflight: &env::PreflightContext, command: Commands) -> ExitCode {
+    match command {
+        Commands::Status { json } => commands::run_status(preflight, json),
+        Commands::New { branch, local, pr } => commands::run_new(preflight, &branch, local, &pr),
+        Commands::Insert {
+            branch,
+            continue_edit,
+            reset_edit,
+        } => commands::run_insert(preflight, branch.as_deref(), continue_edit, reset_edit),
+        Commands::Move {
+            branch,
+            onto,
+            before,
+            continue_edit,
+            reset_edit,
+        } => {
+            let target = match (onto, before) {
+                (Some(onto), _) => Some(MoveTarget::Onto(onto)),
+                (None, Some(before)) => Some(MoveTarget::Before(before)),
+                (None, None) => None,
+            };
+            let request = branch.as_deref().zip(target.as_ref());
+            commands::run_move(preflight, request, continue_edit, reset_edit)
+        }
+        Commands::Fold {
+            continue_edit,
+            reset_edit,
+        } => commands::run_fold(preflight, continue_edit, reset_edit),
+        Commands::Remove {
+            branch,
+            delete,
+            continue_edit,
+            reset_edit,
+        } => commands::run_remove(
+            preflight,
+            branch.as_deref(),
+            delete,
+            continue_edit,
+            reset_edit,
+        ),
+        Commands::Rename {
+            new_name,
+            continue_edit,
+            reset_edit,
+        } => commands::run_rename(preflight, new_name.as_deref(), continue_edit, reset_edit),
+        Commands::Split {
+            points,
+            continue_edit,
+            reset_edit,
+        } => commands::run_split(preflight, &points, continue_edit, reset_edit),
+        Commands::Submit { base, stack, pr } => {
+            commands::run_submit(preflight, base.as_deref(), stack, &pr)
+        }
+        Commands::Sync { dry_run: true, .. } => commands::run_sync_dry_run(preflight),
         Commands::Sync {
             continue_sync,
             reset_sync,
-        } => commands::run_sync(&preflight, continue_sync, reset_sync),
-        Commands::Push => commands::run_push(&preflight),
+            ..
+        } => commands::run_sync(preflight, continue_sync, reset_sync),
+        Commands::Restack { .. } => unreachable!("restack is dispatched before preflight"),
+        Commands::Config { .. } => unreachable!("config is dispatched before preflight"),
+        Commands::Undo => unreachable!("undo is dispatched before preflight"),
+        Commands::Log { .. } => unreachable!("log is dispatched before preflight"),
+        Commands::Push { dry_run: true } => commands::run_push_dry_run(preflight),
+        Commands::Push { dry_run: false } => commands::run_push(preflight),
+        Commands::Nav => commands::run_nav(preflight),
+        Commands::Land { method } => commands::run_land(preflight, method),
+        Commands::Up { steps } => commands::run_navigate(preflight, Navigation::Up(steps.get())),
+        Commands::Down { steps } => {
+            commands::run_navigate(preflight, Navigation::Down(steps.get()))
+        }
+        Commands::Top => commands::run_navigate(preflight, Navigation::Top),
+        Commands::Bottom => commands::run_navigate(preflight, Navigation::Bottom),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Cli;
-    use clap::CommandFactory;
+    use clap::{error::ErrorKind, CommandFactory, Parser};
 
     #[test]
     fn clap_definition_debug_asserts() {
         Cli::command().debug_assert();
     }
+
+    #[test]
+    fn edit_reset_cannot_start_a_new_plan() {
+        for args in [
+            &["stck", "insert", "feature-x", "--reset"][..],
+            &["stck", "move", "feature-a", "--onto", "main", "--reset"],
+            &["stck", "remove", "feature-a", "--reset"],
+            &["stck", "rename", "feature-x", "--reset"],
+            &["stck", "split", "feature-x=HEAD~1", "--reset"],
+        ] {
+            let error = Cli::try_parse_from(args).expect_err("reset with a plan should fail");
+            assert_eq!(error.kind(), ErrorKind::ArgumentConflict, "{args:?}");
+        }
+        for args in [
+            &["stck", "split", "--reset"][..],
+            &["stck", "fold", "--reset"],
+        ] {
+            Cli::try_parse_from(args).expect("bare reset should parse");
+        }
+    }
 }
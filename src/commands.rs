@@ -1,18 +1,39 @@
 //! Command implementations behind the clap definitions in `cli`.
 
+use std::fs;
+use std::path::PathBuf;
 use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::config;
 use crate::env;
-use crate::github;
-use crate::gitops;
+use crate::forge::{ForgeOperation, NewPullRequest};
+use crate::github::{self, MergeMethod, PrState};
+use crate::gitops::{self, Remotes};
 use crate::stack;
 use crate::sync_state::{
-    self, LastSyncPlan, PushState, RemoteBranchLease, SyncPlanScope, SyncState,
+    self, EditAction, EditState, Journal, LandState, LastSyncPlan, PrMutation, PushState,
+    RemoteBranchLease, SyncPlanScope, SyncState,
 };
+use crate::util;
 
 /// Print the detected stack, its PR state, and any local follow-up actions.
-pub(crate) fn run_status(preflight: &env::PreflightContext) -> ExitCode {
+///
+/// With `json`, a versioned [`stack::StatusDocument`] is printed instead of
+/// the human-readable view.
+pub(crate) fn run_status(preflight: &env::PreflightContext, json: bool) -> ExitCode {
     if preflight.current_branch == preflight.default_branch {
+        if json {
+            let report = stack::StatusReport {
+                lines: Vec::new(),
+                summary: stack::StatusSummary {
+                    needs_sync: 0,
+                    needs_push: 0,
+                    base_mismatch: 0,
+                },
+            };
+            return print_status_json(preflight, &report);
+        }
         println!(
             "On default branch ({}). Run `stck new <branch>` to start a new stack.",
             preflight.default_branch
@@ -20,26 +41,29 @@ pub(crate) fn run_status(preflight: &env::PreflightContext) -> ExitCode {
         return ExitCode::SUCCESS;
     }
 
-    if let Err(message) = gitops::fetch_origin() {
+    if let Err(message) = auto_fetch(preflight) {
         eprintln!("error: {message}");
         return ExitCode::from(1);
     }
 
-    let stack =
-        match github::discover_linear_stack(&preflight.current_branch, &preflight.default_branch) {
-            Ok(stack) => stack,
-            Err(message) => {
-                eprintln!("error: {message}");
-                return ExitCode::from(1);
-            }
-        };
+    let stack = match github::discover_stack_tree(
+        preflight.forge.as_ref(),
+        &preflight.remotes,
+        &preflight.current_branch,
+        &preflight.default_branch,
+    ) {
+        Ok(stack) => stack,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+    };
     let mut report = stack::build_status_report(&stack, &preflight.default_branch);
-    if let Some(first_open) =
-        stack::first_open_branch_rooted_on_default(&stack, &preflight.default_branch)
-    {
+    for root in stack::open_branches_rooted_on_default(&stack, &preflight.default_branch) {
         let needs_sync = match gitops::branch_needs_sync_with_default(
+            &preflight.remotes,
             &preflight.default_branch,
-            &first_open.head_ref_name,
+            &root.head_ref_name,
         ) {
             Ok(needs_sync) => needs_sync,
             Err(message) => {
@@ -52,7 +76,7 @@ pub(crate) fn run_status(preflight: &env::PreflightContext) -> ExitCode {
             if let Some(line) = report
                 .lines
                 .iter_mut()
-                .find(|line| line.branch == first_open.head_ref_name)
+                .find(|line| line.branch == root.head_ref_name)
             {
                 if !line.flags.contains(&"needs_sync") {
                     line.flags.push("needs_sync");
@@ -62,11 +86,12 @@ pub(crate) fn run_status(preflight: &env::PreflightContext) -> ExitCode {
         }
     }
     for line in &mut report.lines {
-        if line.state == github::PrState::Merged {
+        // Merged branches are done and local-only branches are not published yet.
+        if matches!(line.state, github::PrState::Merged | github::PrState::Local) {
             continue;
         }
 
-        let needs_push = match gitops::branch_needs_push(&line.branch) {
+        let needs_push = match gitops::branch_needs_push(&preflight.remotes, &line.branch) {
             Ok(needs_push) => needs_push,
             Err(message) => {
                 eprintln!("error: {message}");
@@ -80,13 +105,24 @@ pub(crate) fn run_status(preflight: &env::PreflightContext) -> ExitCode {
         }
     }
 
-    let branch_chain = stack
-        .iter()
-        .map(|pr| pr.head_ref_name.as_str())
-        .collect::<Vec<_>>()
-        .join(" <- ");
+    if json {
+        return print_status_json(preflight, &report);
+    }
 
-    println!("Stack: {} <- {}", preflight.default_branch, branch_chain);
+    // Linear stacks keep the compact chain header; trees indent each branch
+    // under its parent instead.
+    let is_linear = stack.is_linear();
+    if is_linear {
+        let branch_chain = stack
+            .prs()
+            .iter()
+            .map(|pr| pr.head_ref_name.as_str())
+            .collect::<Vec<_>>()
+            .join(" <- ");
+        println!("Stack: {} <- {}", preflight.default_branch, branch_chain);
+    } else {
+        println!("Stack: {} (tree)", preflight.default_branch);
+    }
 
     for line in report.lines {
         let marker = if line.branch == preflight.current_branch {
@@ -94,14 +130,22 @@ pub(crate) fn run_status(preflight: &env::PreflightContext) -> ExitCode {
         } else {
             "  "
         };
+        let indent = if is_linear {
+            String::new()
+        } else {
+            "  ".repeat(line.depth)
+        };
         let flags = if line.flags.is_empty() {
             String::new()
         } else {
             format!(" [{}]", line.flags.join(", "))
         };
+        let number = line
+            .number
+            .map_or_else(String::new, |number| format!(" PR #{number}"));
         println!(
-            "{}{} PR #{} {} base={}{}",
-            marker, line.branch, line.number, line.state, line.base, flags
+            "{}{}{}{} {} base={}{}",
+            marker, indent, line.branch, number, line.state, line.base, flags
         );
     }
 
@@ -113,55 +157,215 @@ pub(crate) fn run_status(preflight: &env::PreflightContext) -> ExitCode {
     ExitCode::SUCCESS
 }
 
-/// Create the next branch in the stack and bootstrap the current branch PR when needed.
-pub(crate) fn run_new(preflight: &env::PreflightContext, new_branch: &str) -> ExitCode {
-    let current_branch = &preflight.current_branch;
-    let starting_from_default = current_branch == &preflight.default_branch;
-    let pr_base_branch = if starting_from_default {
-        preflight.default_branch.as_str()
-    } else {
-        current_branch.as_str()
-    };
-
-    match gitops::is_valid_branch_name(new_branch) {
-        Ok(true) => {}
-        Ok(false) => {
-            eprintln!(
-                "error: `{new_branch}` is not a valid branch name; use only alphanumeric characters, hyphens, underscores, and slashes"
-            );
-            return ExitCode::from(1);
+fn print_status_json(preflight: &env::PreflightContext, report: &stack::StatusReport) -> ExitCode {
+    let document = stack::StatusDocument::new(
+        report,
+        &preflight.repository,
+        &preflight.default_branch,
+        &preflight.current_branch,
+    );
+    match serde_json::to_string_pretty(&document) {
+        Ok(raw) => {
+            println!("{raw}");
+            ExitCode::SUCCESS
+        }
+        Err(_) => {
+            eprintln!("error: failed to serialize status output");
+            ExitCode::from(1)
         }
+    }
+}
+
+/// Check out the stack branch reached by `navigation` from the current branch.
+pub(crate) fn run_navigate(
+    preflight: &env::PreflightContext,
+    navigation: stack::Navigation,
+) -> ExitCode {
+    if preflight.current_branch == preflight.default_branch {
+        eprintln!(
+            "error: on default branch {}; checkout a branch in the stack to navigate it",
+            preflight.default_branch
+        );
+        return ExitCode::from(1);
+    }
+
+    let stack = match github::discover_stack_tree(
+        preflight.forge.as_ref(),
+        &preflight.remotes,
+        &preflight.current_branch,
+        &preflight.default_branch,
+    ) {
+        Ok(stack) => stack,
         Err(message) => {
             eprintln!("error: {message}");
             return ExitCode::from(1);
         }
-    }
-
-    let local_exists = match gitops::local_branch_exists(new_branch) {
-        Ok(exists) => exists,
+    };
+    let target = match stack::navigation_target(&stack, &preflight.current_branch, navigation) {
+        Ok(target) => target,
         Err(message) => {
             eprintln!("error: {message}");
             return ExitCode::from(1);
         }
     };
-    if local_exists {
-        eprintln!("error: branch {new_branch} already exists locally; choose a different name");
+
+    if target == preflight.current_branch {
+        println!("Already on {target}.");
+        return ExitCode::SUCCESS;
+    }
+
+    println!("$ git checkout {}", target);
+    if let Err(message) = gitops::checkout_branch(&target) {
+        eprintln!("error: {message}");
         return ExitCode::from(1);
     }
+    ExitCode::SUCCESS
+}
 
-    let remote_exists = match gitops::remote_branch_exists(new_branch) {
-        Ok(exists) => exists,
-        Err(message) => {
-            eprintln!("error: {message}");
-            return ExitCode::from(1);
+/// PR creation options shared by `stck new` and `stck submit`.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub(crate) struct PrOptions {
+    /// Open the PR as a draft.
+    #[arg(long)]
+    pub(crate) draft: bool,
+    /// Open the PR ready for review even when the `draft` setting is on.
+    #[arg(long, conflicts_with = "draft")]
+    pub(crate) no_draft: bool,
+    /// PR title (defaults to the branch's first commit subject).
+    #[arg(long)]
+    pub(crate) title: Option<String>,
+    /// Read the PR description from a file; the stack context is appended to it.
+    #[arg(long, value_name = "PATH")]
+    pub(crate) body_file: Option<PathBuf>,
+    /// Request a review from a user (repeatable).
+    #[arg(long = "reviewer", value_name = "USER")]
+    pub(crate) reviewers: Vec<String>,
+    /// Assign the PR to a user (repeatable).
+    #[arg(long = "assignee", value_name = "USER")]
+    pub(crate) assignees: Vec<String>,
+    /// Add a label to the PR (repeatable).
+    #[arg(long = "label", value_name = "NAME")]
+    pub(crate) labels: Vec<String>,
+}
+
+/// Open a PR for `head` targeting `base`, echoing the forge operation first.
+///
+/// The title defaults to the first commit subject on `head`, falling back to
+/// the branch name.
+fn open_pr(
+    preflight: &env::PreflightContext,
+    base: &str,
+    head: &str,
+    options: &PrOptions,
+) -> Result<(), String> {
+    let title = match &options.title {
+        Some(title) => title.clone(),
+        None => gitops::first_commit_subject(&preflight.remotes, base, head)?
+            .unwrap_or_else(|| head.to_string()),
+    };
+    let pr = new_pull_request(preflight, base, head, title, options)?;
+    println!("{}", preflight.forge.describe(ForgeOperation::Create(&pr)));
+    preflight.forge.create_pr(&pr)?;
+    preflight.journal.record_pr_mutation(PrMutation::Create {
+        head: pr.head,
+        base: pr.base,
+    });
+    Ok(())
+}
+
+/// Describe a PR for `head` targeting `base` titled `title`.
+///
+/// The body comes from `--body-file` or the `body-template` setting and
+/// always carries the stack context block.
+fn new_pull_request(
+    preflight: &env::PreflightContext,
+    base: &str,
+    head: &str,
+    title: String,
+    options: &PrOptions,
+) -> Result<NewPullRequest, String> {
+    let context = github::stack_pr_body(base, &preflight.default_branch);
+    let body = match options
+        .body_file
+        .as_ref()
+        .or(preflight.config.body_template.as_ref())
+    {
+        Some(path) => {
+            let text = fs::read_to_string(path)
+                .map_err(|err| format!("failed to read PR body file {}: {err}", path.display()))?;
+            github::replace_stack_block(&text, &context)
         }
+        None => context,
+    };
+
+    Ok(NewPullRequest {
+        base: base.to_string(),
+        head: head.to_string(),
+        title,
+        body,
+        draft: options.draft || (preflight.config.draft && !options.no_draft),
+        reviewers: options.reviewers.clone(),
+        assignees: options.assignees.clone(),
+        labels: options.labels.clone(),
+    })
+}
+
+/// Prepend the `branch-prefix` setting to `branch` unless it already starts with it.
+fn apply_branch_prefix(preflight: &env::PreflightContext, branch: &str) -> String {
+    let prefix = &preflight.config.branch_prefix;
+    if prefix.is_empty() || branch.starts_with(prefix.as_str()) {
+        branch.to_string()
+    } else {
+        format!("{prefix}{branch}")
+    }
+}
+
+/// Check that `branch` is a valid name that exists neither locally nor on the push remote.
+fn check_new_branch(remotes: &Remotes, branch: &str) -> Result<(), String> {
+    if !gitops::is_valid_branch_name(branch)? {
+        return Err(format!(
+            "`{branch}` is not a valid branch name; use only alphanumeric characters, hyphens, underscores, and slashes"
+        ));
+    }
+    if gitops::local_branch_exists(branch)? {
+        return Err(format!(
+            "branch {branch} already exists locally; choose a different name"
+        ));
+    }
+    if gitops::remote_branch_exists(remotes, branch)? {
+        return Err(format!(
+            "branch {branch} already exists on {}; choose a different name",
+            remotes.push
+        ));
+    }
+    Ok(())
+}
+
+/// Create the next branch in the stack and bootstrap the current branch PR when needed.
+///
+/// `options` apply to the new branch's PR; a bootstrapped PR for the current
+/// branch uses the defaults.
+pub(crate) fn run_new(
+    preflight: &env::PreflightContext,
+    new_branch: &str,
+    local_only: bool,
+    options: &PrOptions,
+) -> ExitCode {
+    let new_branch = &apply_branch_prefix(preflight, new_branch);
+    let current_branch = &preflight.current_branch;
+    let starting_from_default = current_branch == &preflight.default_branch;
+    let pr_base_branch = if starting_from_default {
+        preflight.default_branch.as_str()
+    } else {
+        current_branch.as_str()
     };
-    if remote_exists {
-        eprintln!("error: branch {new_branch} already exists on origin; choose a different name");
+
+    if let Err(message) = check_new_branch(&preflight.remotes, new_branch) {
+        eprintln!("error: {message}");
         return ExitCode::from(1);
     }
 
-    if !starting_from_default {
+    if !starting_from_default && !local_only {
         let has_upstream = match gitops::branch_has_upstream(current_branch) {
             Ok(has_upstream) => has_upstream,
             Err(message) => {
@@ -171,13 +375,16 @@ pub(crate) fn run_new(preflight: &env::PreflightContext, new_branch: &str) -> Ex
         };
 
         if !has_upstream {
-            println!("$ git push -u origin {}", current_branch);
-            if let Err(message) = gitops::push_set_upstream(current_branch) {
+            println!(
+                "$ git push -u {} {}",
+                preflight.remotes.push, current_branch
+            );
+            if let Err(message) = gitops::push_set_upstream(&preflight.remotes, current_branch) {
                 eprintln!("error: {message}");
                 return ExitCode::from(1);
             }
         } else {
-            let needs_push = match gitops::branch_needs_push(current_branch) {
+            let needs_push = match gitops::branch_needs_push(&preflight.remotes, current_branch) {
                 Ok(needs_push) => needs_push,
                 Err(message) => {
                     eprintln!("error: {message}");
@@ -185,15 +392,15 @@ pub(crate) fn run_new(preflight: &env::PreflightContext, new_branch: &str) -> Ex
                 }
             };
             if needs_push {
-                println!("$ git push origin {}", current_branch);
-                if let Err(message) = gitops::push_branch(current_branch) {
+                println!("$ git push {} {}", preflight.remotes.push, current_branch);
+                if let Err(message) = gitops::push_branch(&preflight.remotes, current_branch) {
                     eprintln!("error: {message}");
                     return ExitCode::from(1);
                 }
             }
         }
 
-        let current_has_pr = match github::pr_exists_for_head(current_branch) {
+        let current_has_pr = match preflight.forge.open_pr_exists(current_branch) {
             Ok(exists) => exists,
             Err(message) => {
                 eprintln!("error: {message}");
@@ -202,21 +409,19 @@ pub(crate) fn run_new(preflight: &env::PreflightContext, new_branch: &str) -> Ex
         };
 
         if !current_has_pr {
-            let bootstrap_base = match discover_parent_base(current_branch) {
+            let bootstrap_base = match discover_parent_base(preflight, current_branch) {
                 Ok(base) => base.unwrap_or_else(|| preflight.default_branch.clone()),
                 Err(message) => {
                     eprintln!("error: {message}");
                     return ExitCode::from(1);
                 }
             };
-            println!(
-                "$ gh pr create --base {} --head {} --title {} --body \"<stack context>\"",
-                bootstrap_base, current_branch, current_branch
-            );
-            let body = github::stack_pr_body(&bootstrap_base, &preflight.default_branch);
-            if let Err(message) =
-                github::create_pr(&bootstrap_base, current_branch, current_branch, &body)
-            {
+            if let Err(message) = open_pr(
+                preflight,
+                &bootstrap_base,
+                current_branch,
+                &PrOptions::default(),
+            ) {
                 eprintln!("error: {message}");
                 return ExitCode::from(1);
             }
@@ -229,8 +434,28 @@ pub(crate) fn run_new(preflight: &env::PreflightContext, new_branch: &str) -> Ex
         return ExitCode::from(1);
     }
 
-    println!("$ git push -u origin {}", new_branch);
-    if let Err(message) = gitops::push_set_upstream(new_branch) {
+    let fork_point = match gitops::resolve_ref(&format!("refs/heads/{new_branch}")) {
+        Ok(sha) => sha,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+    };
+    if let Err(message) = gitops::record_local_parent(new_branch, pr_base_branch, &fork_point) {
+        eprintln!("error: {message}");
+        return ExitCode::from(1);
+    }
+
+    if local_only {
+        println!(
+            "Created local branch {} on top of {}. Run `stck submit` when it is ready to publish.",
+            new_branch, pr_base_branch
+        );
+        return ExitCode::SUCCESS;
+    }
+
+    println!("$ git push -u {} {}", preflight.remotes.push, new_branch);
+    if let Err(message) = gitops::push_set_upstream(&preflight.remotes, new_branch) {
         eprintln!("error: {message}");
         return ExitCode::from(1);
     }
@@ -250,12 +475,7 @@ pub(crate) fn run_new(preflight: &env::PreflightContext, new_branch: &str) -> Ex
         return ExitCode::SUCCESS;
     }
 
-    println!(
-        "$ gh pr create --base {} --head {} --title {} --body \"<stack context>\"",
-        pr_base_branch, new_branch, new_branch
-    );
-    let body = github::stack_pr_body(pr_base_branch, &preflight.default_branch);
-    if let Err(message) = github::create_pr(pr_base_branch, new_branch, new_branch, &body) {
+    if let Err(message) = open_pr(preflight, pr_base_branch, new_branch, options) {
         eprintln!("error: {message}");
         return ExitCode::from(1);
     }
@@ -267,6 +487,15 @@ pub(crate) fn run_new(preflight: &env::PreflightContext, new_branch: &str) -> Ex
     ExitCode::SUCCESS
 }
 
+/// Fetch the configured remote unless the `auto-fetch` setting is off.
+fn auto_fetch(preflight: &env::PreflightContext) -> Result<(), String> {
+    if preflight.config.auto_fetch {
+        gitops::fetch_remotes(&preflight.remotes)
+    } else {
+        Ok(())
+    }
+}
+
 fn parent_discovery_error(branch: &str, message: &str) -> String {
     format!(
         "could not auto-detect stack parent for {branch}: {message}; retry or pass `--base <branch>` explicitly"
@@ -275,12 +504,33 @@ fn parent_discovery_error(branch: &str, message: &str) -> String {
 
 /// Discover the intended PR parent from open PR metadata and Git ancestry.
 ///
-/// `Ok(None)` means discovery completed and no parent is an ancestor. Any
-/// GitHub, ref-resolution, or ancestry-check failure is returned separately so
-/// callers cannot silently create a PR against the default branch.
-fn discover_parent_base(branch: &str) -> Result<Option<String>, String> {
-    gitops::fetch_origin().map_err(|message| parent_discovery_error(branch, &message))?;
-    let candidate_branches = gitops::list_origin_branches()
+/// A parent recorded locally by `stck new` wins over discovery. `Ok(None)`
+/// means discovery completed and no parent is an ancestor. Any GitHub,
+/// ref-resolution, or ancestry-check failure is returned separately so callers
+/// cannot silently create a PR against the default branch.
+fn discover_parent_base(
+    preflight: &env::PreflightContext,
+    branch: &str,
+) -> Result<Option<String>, String> {
+    let forge = preflight.forge.as_ref();
+    if let Some(local) =
+        gitops::local_parent(branch).map_err(|message| parent_discovery_error(branch, &message))?
+    {
+        // Ignore a recorded parent that was deleted or no longer sits below
+        // the branch, e.g. after it was rewritten without restacking.
+        if let Ok(parent_ref) =
+            gitops::resolve_branch_ref_remote_first(&preflight.remotes, &local.parent)
+        {
+            if gitops::is_ancestor(&parent_ref, &format!("refs/heads/{branch}"))
+                .map_err(|message| parent_discovery_error(branch, &message))?
+            {
+                return Ok(Some(local.parent));
+            }
+        }
+    }
+
+    auto_fetch(preflight).map_err(|message| parent_discovery_error(branch, &message))?;
+    let candidate_branches = gitops::list_remote_branches(&preflight.remotes)
         .map_err(|message| parent_discovery_error(branch, &message))?;
 
     let branch_ref = format!("refs/heads/{branch}");
@@ -291,13 +541,14 @@ fn discover_parent_base(branch: &str) -> Result<Option<String>, String> {
             continue;
         }
 
-        let candidate_ref = gitops::resolve_branch_ref_remote_first(&candidate)
+        let candidate_ref = gitops::resolve_branch_ref_remote_first(&preflight.remotes, &candidate)
             .map_err(|message| parent_discovery_error(branch, &message))?;
         let is_ancestor = gitops::is_ancestor(&candidate_ref, &branch_ref)
             .map_err(|message| parent_discovery_error(branch, &message))?;
 
         if is_ancestor
-            && github::pr_exists_for_head(&candidate)
+            && forge
+                .open_pr_exists(&candidate)
                 .map_err(|message| parent_discovery_error(branch, &message))?
         {
             if let Some((_, current_best_ref)) = &best {
@@ -316,9 +567,14 @@ fn discover_parent_base(branch: &str) -> Result<Option<String>, String> {
 }
 
 /// Create a pull request for the current branch if one does not already exist.
+///
+/// With `stack`, every branch of the current stack that has no PR yet is
+/// pushed and submitted in order from the root to the tip.
 pub(crate) fn run_submit(
     preflight: &env::PreflightContext,
     base_override: Option<&str>,
+    stack: bool,
+    options: &PrOptions,
 ) -> ExitCode {
     let current_branch = &preflight.current_branch;
     if current_branch == &preflight.default_branch {
@@ -329,101 +585,125 @@ pub(crate) fn run_submit(
         return ExitCode::from(1);
     }
 
-    let has_upstream = match gitops::branch_has_upstream(current_branch) {
-        Ok(has_upstream) => has_upstream,
+    if stack {
+        return run_submit_stack(preflight, options);
+    }
+
+    match submit_branch(preflight, current_branch, base_override, options) {
+        Ok(_) => ExitCode::SUCCESS,
         Err(message) => {
             eprintln!("error: {message}");
-            return ExitCode::from(1);
-        }
-    };
-    if !has_upstream {
-        println!("$ git push -u origin {}", current_branch);
-        if let Err(message) = gitops::push_set_upstream(current_branch) {
-            eprintln!("error: {message}");
-            return ExitCode::from(1);
-        }
-    } else {
-        let needs_push = match gitops::branch_needs_push(current_branch) {
-            Ok(needs_push) => needs_push,
-            Err(message) => {
-                eprintln!("error: {message}");
-                return ExitCode::from(1);
-            }
-        };
-        if needs_push {
-            println!("$ git push origin {}", current_branch);
-            if let Err(message) = gitops::push_branch(current_branch) {
-                eprintln!("error: {message}");
-                return ExitCode::from(1);
-            }
+            ExitCode::from(1)
         }
     }
+}
 
-    let current_has_pr = match github::pr_exists_for_head(current_branch) {
-        Ok(exists) => exists,
+fn run_submit_stack(preflight: &env::PreflightContext, options: &PrOptions) -> ExitCode {
+    let stack = match github::discover_stack_tree(
+        preflight.forge.as_ref(),
+        &preflight.remotes,
+        &preflight.current_branch,
+        &preflight.default_branch,
+    ) {
+        Ok(stack) => stack,
         Err(message) => {
             eprintln!("error: {message}");
             return ExitCode::from(1);
         }
     };
-    if current_has_pr {
-        println!("Branch {} already has an open PR.", current_branch);
+
+    let missing = stack
+        .prs()
+        .iter()
+        .filter(|pr| pr.state == PrState::Local)
+        .map(|pr| pr.head_ref_name.clone())
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        println!("Every branch in the stack already has a PR.");
         return ExitCode::SUCCESS;
     }
 
-    let discovered_base;
-    let base = if let Some(explicit) = base_override {
-        explicit
-    } else {
-        discovered_base = match discover_parent_base(current_branch) {
-            Ok(base) => base.unwrap_or_else(|| preflight.default_branch.clone()),
+    let mut created = 0;
+    for branch in &missing {
+        println!("Submitting {branch}.");
+        match submit_branch(preflight, branch, None, options) {
+            Ok(true) => created += 1,
+            Ok(false) => {}
             Err(message) => {
                 eprintln!("error: {message}");
+                eprintln!(
+                    "Created {created} PR(s) before the failure; fix the error and rerun `stck submit --stack`."
+                );
                 return ExitCode::from(1);
             }
-        };
-        if discovered_base == preflight.default_branch {
-            println!(
-                "No --base provided. Defaulting PR base to {}.",
-                preflight.default_branch
-            );
-        } else {
-            println!(
-                "No --base provided. Detected stack parent: {}.",
-                discovered_base
-            );
         }
-        &discovered_base
-    };
-
-    println!(
-        "$ gh pr create --base {} --head {} --title {} --body \"<stack context>\"",
-        base, current_branch, current_branch
-    );
-    let body = github::stack_pr_body(base, &preflight.default_branch);
-    if let Err(message) = github::create_pr(base, current_branch, current_branch, &body) {
-        eprintln!("error: {message}");
-        return ExitCode::from(1);
     }
 
-    println!("Created PR for {} targeting {}.", current_branch, base);
+    println!("Created {created} PR(s) for the stack.");
     ExitCode::SUCCESS
 }
 
-fn capture_remote_branch_leases(branches: &[String]) -> Result<Vec<RemoteBranchLease>, String> {
+/// Publish `branch` and open its PR, returning whether a PR was created.
+///
+/// The base is `base_override` or the discovered stack parent, falling back
+/// to the default branch.
+fn submit_branch(
+    preflight: &env::PreflightContext,
+    branch: &str,
+    base_override: Option<&str>,
+    options: &PrOptions,
+) -> Result<bool, String> {
+    if !gitops::branch_has_upstream(branch)? {
+        println!("$ git push -u {} {}", preflight.remotes.push, branch);
+        gitops::push_set_upstream(&preflight.remotes, branch)?;
+    } else if gitops::branch_needs_push(&preflight.remotes, branch)? {
+        println!("$ git push {} {}", preflight.remotes.push, branch);
+        gitops::push_branch(&preflight.remotes, branch)?;
+    }
+
+    if preflight.forge.open_pr_exists(branch)? {
+        println!("Branch {} already has an open PR.", branch);
+        return Ok(false);
+    }
+
+    let base = match base_override {
+        Some(explicit) => explicit.to_string(),
+        None => {
+            let discovered = discover_parent_base(preflight, branch)?
+                .unwrap_or_else(|| preflight.default_branch.clone());
+            if discovered == preflight.default_branch {
+                println!(
+                    "No --base provided. Defaulting PR base to {}.",
+                    preflight.default_branch
+                );
+            } else {
+                println!("No --base provided. Detected stack parent: {}.", discovered);
+            }
+            discovered
+        }
+    };
+
+    open_pr(preflight, &base, branch, options)?;
+
+    println!("Created PR for {} targeting {}.", branch, base);
+    Ok(true)
+}
+
+fn capture_remote_branch_leases(
+    remotes: &Remotes,
+    branches: &[String],
+) -> Result<Vec<RemoteBranchLease>, String> {
     branches
         .iter()
         .map(|branch| {
-            let expected_remote_head = gitops::remote_branch_head(branch)?;
+            let expected_remote_head = gitops::remote_branch_head(remotes, branch)?;
             if expected_remote_head.is_some()
-                && !gitops::is_ancestor(
-                    &format!("refs/remotes/origin/{branch}"),
-                    &format!("refs/heads/{branch}"),
-                )?
+                && !gitops::is_ancestor(&remotes.push_ref(branch), &format!("refs/heads/{branch}"))?
             {
                 return Err(format!(
-                    "remote branch `origin/{branch}` has commits not in local `{branch}`; \
-                     pull or rebase to integrate remote changes before syncing"
+                    "remote branch `{}/{branch}` has commits not in local `{branch}`; \
+                     pull or rebase to integrate remote changes before syncing",
+                    remotes.push
                 ));
             }
 
@@ -454,6 +734,13 @@ pub(crate) fn run_sync(
         }
     };
 
+    if existing_state.as_ref().is_some_and(|state| state.offline) && !reset_sync {
+        eprintln!(
+            "error: a `stck restack` is in progress; finish it with `stck restack --continue` or discard it with `stck restack --reset`"
+        );
+        return ExitCode::from(1);
+    }
+
     if reset_sync {
         if existing_state.is_some() {
             if let Err(message) = sync_state::clear() {
@@ -467,7 +754,7 @@ pub(crate) fn run_sync(
         existing_state = None;
     }
 
-    let mut state = match existing_state {
+    let state = match existing_state {
         Some(state) => {
             if !continue_sync {
                 println!(
@@ -496,43 +783,18 @@ pub(crate) fn run_sync(
                 return ExitCode::from(1);
             }
 
-            if let Err(message) = gitops::fetch_origin() {
+            if let Err(message) = auto_fetch(preflight) {
                 eprintln!("error: {message}");
                 return ExitCode::from(1);
             }
 
-            let stack = match github::discover_linear_stack(
-                &preflight.current_branch,
-                &preflight.default_branch,
-            ) {
-                Ok(stack) => stack,
+            let (stack, steps) = match plan_sync(preflight) {
+                Ok(plan) => plan,
                 Err(message) => {
                     eprintln!("error: {message}");
                     return ExitCode::from(1);
                 }
             };
-            let force_rewrite_first_open = if let Some(first_open) =
-                stack::first_open_branch_rooted_on_default(&stack, &preflight.default_branch)
-            {
-                match gitops::branch_needs_sync_with_default(
-                    &preflight.default_branch,
-                    &first_open.head_ref_name,
-                ) {
-                    Ok(needs_sync) => needs_sync,
-                    Err(message) => {
-                        eprintln!("error: {message}");
-                        return ExitCode::from(1);
-                    }
-                }
-            } else {
-                false
-            };
-
-            let steps = stack::build_sync_plan_with_options(
-                &stack,
-                &preflight.default_branch,
-                force_rewrite_first_open,
-            );
             if steps.is_empty() {
                 if let Err(message) = sync_state::clear_last_sync_plan() {
                     eprintln!("error: {message}");
@@ -546,25 +808,35 @@ pub(crate) fn run_sync(
                 .iter()
                 .map(|step| step.branch.clone())
                 .collect::<Vec<_>>();
-            let push_leases = match capture_remote_branch_leases(&lease_branches) {
-                Ok(push_leases) => push_leases,
-                Err(message) => {
-                    eprintln!("error: {message}");
-                    return ExitCode::from(1);
-                }
-            };
-            let state = SyncState {
+            let push_leases =
+                match capture_remote_branch_leases(&preflight.remotes, &lease_branches) {
+                    Ok(push_leases) => push_leases,
+                    Err(message) => {
+                        eprintln!("error: {message}");
+                        return ExitCode::from(1);
+                    }
+                };
+            let mut state = SyncState {
                 steps,
                 completed_steps: 0,
                 failed_step: None,
                 failed_step_branch_head: None,
                 plan_scope: Some(SyncPlanScope::new(
                     &preflight.repository,
-                    &stack,
+                    stack.prs(),
                     push_leases,
                 )),
+                offline: false,
+                backup_id: None,
             };
-            if let Err(message) = sync_state::save_sync(&state) {
+            match backup_sync_branches(&state.steps) {
+                Ok(id) => state.backup_id = Some(id),
+                Err(message) => {
+                    eprintln!("error: {message}");
+                    return ExitCode::from(1);
+                }
+            }
+            if let Err(message) = sync_state::save_sync(&preflight.journal, &state) {
                 eprintln!("error: {message}");
                 return ExitCode::from(1);
             }
@@ -572,55 +844,562 @@ pub(crate) fn run_sync(
         }
     };
 
-    if let Some(failed_step) = state.failed_step {
-        let rebase_in_progress = match gitops::rebase_in_progress() {
-            Ok(in_progress) => in_progress,
-            Err(message) => {
-                eprintln!("error: {message}");
-                return ExitCode::from(1);
-            }
-        };
-
-        if rebase_in_progress {
-            eprintln!("error: rebase is still in progress; run `git rebase --continue` (or `git rebase --abort`) before rerunning `stck sync`");
-            return ExitCode::from(1);
-        }
+    let already_up_to_date = state.steps.is_empty();
+    let code = execute_sync_steps(
+        &preflight.remotes,
+        &preflight.journal,
+        &preflight.default_branch,
+        &original_branch,
+        state,
+        continue_sync,
+    );
+    if code != ExitCode::SUCCESS {
+        return code;
+    }
+    if already_up_to_date {
+        println!("Stack is already up to date. No sync needed.");
+    } else {
+        println!("Sync succeeded locally. Run `stck push` to update remotes + PR bases.");
+    }
+    ExitCode::SUCCESS
+}
 
-        if continue_sync {
-            let step = &state.steps[failed_step];
-            let branch_ref = format!("refs/heads/{}", step.branch);
-            let current_head = match gitops::resolve_ref(&branch_ref) {
-                Ok(sha) => sha,
-                Err(message) => {
-                    eprintln!("error: {message}");
-                    return ExitCode::from(1);
-                }
-            };
+/// Number of branch backups kept for `stck undo`.
+const KEPT_BACKUPS: usize = 10;
+
+/// Save the heads and recorded parents of the branches `steps` will rewrite
+/// so `stck undo` can restore them, pruning all but the most recent backups.
+///
+/// Returns the new backup id.
+fn backup_sync_branches(steps: &[stack::SyncStep]) -> Result<String, String> {
+    let id = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| "system clock is set before the Unix epoch".to_string())?
+        .as_millis()
+        .to_string();
+    let mut names = steps
+        .iter()
+        .map(|step| step.branch.clone())
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    let mut branches = Vec::new();
+    for branch in names {
+        let local = gitops::local_parent(&branch)?;
+        branches.push(sync_state::BackupBranch {
+            before: gitops::resolve_ref(&format!("refs/heads/{branch}"))?,
+            after: None,
+            parent: local.as_ref().map(|local| local.parent.clone()),
+            fork_point: local.and_then(|local| local.fork_point),
+            branch,
+        });
+    }
+    let backup = sync_state::SyncBackup {
+        id: id.clone(),
+        branches,
+    };
+
+    gitops::create_backup_refs(&id, &backup_heads(&backup))?;
+    sync_state::save_backup(&backup)?;
+    for stale in sync_state::load_backups()?.iter().skip(KEPT_BACKUPS) {
+        gitops::delete_backup_refs(&stale.id, &backup_heads(stale))?;
+        sync_state::delete_backup(&stale.id)?;
+    }
+    Ok(id)
+}
+
+fn backup_heads(backup: &sync_state::SyncBackup) -> Vec<(String, String)> {
+    backup
+        .branches
+        .iter()
+        .map(|saved| (saved.branch.clone(), saved.before.clone()))
+        .collect()
+}
+
+/// Restore every branch rewritten by the latest sync, restack, or land to the
+/// head and recorded parent it had before, aborting a rebase left in progress.
+///
+/// Undo refuses to run when a branch moved after the operation left it, so
+/// later work is never discarded. Branch refs move in a single transaction
+/// that re-checks those heads, and the saved sync and push plans are cleared.
+/// Remote branches are not touched.
+pub(crate) fn run_undo() -> ExitCode {
+    match undo_last_sync() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn undo_last_sync() -> Result<(), String> {
+    util::ensure_command_available("git")?;
+    sync_state::ensure_undoable()?;
+    let backup = sync_state::load_backups()?.into_iter().next().ok_or_else(|| {
+        "no branch backup to restore; `stck undo` only reverts branches rewritten by `stck sync`, `stck restack`, or `stck land`".to_string()
+    })?;
+
+    // A branch mid-rebase keeps its ref until the rebase finishes, so this
+    // holds before the abort below as well.
+    let interrupted = sync_state::load_sync().ok().flatten();
+    let mut restores = Vec::new();
+    for saved in &backup.branches {
+        let expected = saved.expected_head();
+        if !gitops::local_branch_exists(&saved.branch)? {
+            return Err(format!(
+                "branch {} was deleted after the sync; recreate it at {} or delete the backup by hand before undoing",
+                saved.branch,
+                &expected[..7]
+            ));
+        }
+        let current = gitops::resolve_ref(&format!("refs/heads/{}", saved.branch))?;
+        if current != expected {
+            let mut message = format!(
+                "branch {} moved from {} to {} after the sync; undo would discard those commits, so nothing was restored",
+                saved.branch,
+                &expected[..7],
+                &current[..7]
+            );
+            if let Some(state) = interrupted.as_ref().filter(|state| {
+                state
+                    .failed_step
+                    .is_some_and(|failed| state.steps[failed].branch == saved.branch)
+            }) {
+                let command = if state.offline { "restack" } else { "sync" };
+                message.push_str(&format!(
+                    "; if you finished its interrupted rebase yourself, run `stck {command} --continue` first"
+                ));
+            }
+            return Err(message);
+        }
+        restores.push(gitops::BranchRestore {
+            branch: saved.branch.clone(),
+            saved: saved.before.clone(),
+            expected: current,
+        });
+    }
+
+    let taken_at = backup
+        .id
+        .parse::<u64>()
+        .map(|millis| util::format_utc(millis / 1_000))
+        .unwrap_or_else(|_| backup.id.clone());
+    println!("Undoing the sync started at {taken_at}.");
+    if gitops::rebase_in_progress()? {
+        println!("$ git rebase --abort");
+        gitops::abort_rebase()?;
+    }
+    env::ensure_clean_working_tree()?;
+
+    let checked_out = gitops::checked_out_branch()?;
+    gitops::restore_backup(&backup.id, &restores)?;
+    sync_state::delete_backup(&backup.id)?;
+    for saved in &backup.branches {
+        gitops::set_local_parent(
+            &saved.branch,
+            saved.parent.as_deref(),
+            saved.fork_point.as_deref(),
+        )?;
+    }
+    if checked_out
+        .as_ref()
+        .is_some_and(|current| backup.branches.iter().any(|saved| saved.branch == *current))
+    {
+        gitops::reset_hard_to_head()?;
+    }
+    sync_state::clear()?;
+    sync_state::clear_last_sync_plan()?;
+
+    for restore in &restores {
+        if restore.saved == restore.expected {
+            println!(
+                "Branch {} is unchanged at {}.",
+                restore.branch,
+                &restore.saved[..7]
+            );
+        } else {
+            println!(
+                "Restored {} to {} (was {}).",
+                restore.branch,
+                &restore.saved[..7],
+                &restore.expected[..7]
+            );
+        }
+    }
+    println!(
+        "Undo restored {} branch(es) to their heads before the last sync. Remote branches were not changed.",
+        restores.len()
+    );
+    Ok(())
+}
+
+/// Discover the stack and compute the rebase steps `stck sync` would run.
+fn plan_sync(
+    preflight: &env::PreflightContext,
+) -> Result<(stack::StackTree, Vec<stack::SyncStep>), String> {
+    let stack = github::discover_stack_tree(
+        preflight.forge.as_ref(),
+        &preflight.remotes,
+        &preflight.current_branch,
+        &preflight.default_branch,
+    )?;
+    let mut force_rewrite_roots = Vec::new();
+    for root in stack::open_branches_rooted_on_default(&stack, &preflight.default_branch) {
+        if gitops::branch_needs_sync_with_default(
+            &preflight.remotes,
+            &preflight.default_branch,
+            &root.head_ref_name,
+        )? {
+            force_rewrite_roots.push(root.head_ref_name.clone());
+        }
+    }
+
+    let mut steps = stack::build_sync_plan_with_options(
+        &stack,
+        &preflight.default_branch,
+        &force_rewrite_roots,
+    );
+    attach_local_fork_points(&stack, &mut steps)?;
+    Ok((stack, steps))
+}
+
+/// Print the rebase plan `stck sync` would run without changing anything.
+///
+/// Each step shows the resolved `git rebase --onto` command and the commits it
+/// would carry, followed by the remote leases a real sync would capture.
+/// Nothing is written under `.git/stck/` and no branch is checked out; only the
+/// usual auto-fetch updates remote-tracking refs.
+pub(crate) fn run_sync_dry_run(preflight: &env::PreflightContext) -> ExitCode {
+    match print_sync_plan(preflight) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn print_sync_plan(preflight: &env::PreflightContext) -> Result<(), String> {
+    if sync_state::load_sync()?.is_some() {
+        println!(
+            "Note: a previous sync is in progress; this plan ignores it. Use `stck sync --continue` to resume it."
+        );
+    }
+    auto_fetch(preflight)?;
+    let (_, steps) = plan_sync(preflight)?;
+    if steps.is_empty() {
+        println!("Stack is already up to date. No sync needed.");
+        return Ok(());
+    }
+
+    let total_steps = steps.len();
+    for (index, step) in steps.iter().enumerate() {
+        let old_base_sha = match &step.fork_point {
+            Some(fork_point) => fork_point.clone(),
+            None => gitops::resolve_old_base_for_rebase(
+                &preflight.remotes,
+                &step.old_base_ref,
+                &step.branch,
+            )?,
+        };
+        // A parent rebased by an earlier step is used through its local ref,
+        // whose new tip only exists once that step has run.
+        let rebased_by = steps[..index]
+            .iter()
+            .position(|earlier| earlier.branch == step.new_base_ref);
+        let (onto_ref, onto_sha) = match rebased_by {
+            Some(earlier) => (
+                format!("refs/heads/{}", step.new_base_ref),
+                format!("its tip after step {}", earlier + 1),
+            ),
+            None => {
+                let onto_ref = gitops::resolve_onto_ref(&preflight.remotes, &step.new_base_ref)?;
+                let onto_sha = gitops::resolve_ref(&onto_ref)?;
+                (onto_ref, onto_sha)
+            }
+        };
+
+        if step.old_base_ref == step.new_base_ref {
+            println!(
+                "Step {}/{total_steps}: rebase {} onto {} (dropping already-upstream commits)",
+                index + 1,
+                step.branch,
+                step.new_base_ref
+            );
+        } else {
+            println!(
+                "Step {}/{total_steps}: rebase {} onto {} (from {})",
+                index + 1,
+                step.branch,
+                step.new_base_ref,
+                step.old_base_ref
+            );
+        }
+        println!(
+            "$ git rebase --onto {onto_ref} {old_base_sha} {}",
+            step.branch
+        );
+        println!("  onto {onto_ref} = {onto_sha}");
+        let commits = gitops::branch_commits(&old_base_sha, &step.branch)?;
+        println!("  carries {} commit(s):", commits.len());
+        for commit in commits {
+            println!("    {} {}", &commit.sha[..7], commit.subject);
+        }
+    }
+
+    let branches = steps
+        .iter()
+        .map(|step| step.branch.clone())
+        .collect::<Vec<_>>();
+    println!("Push leases:");
+    for lease in capture_remote_branch_leases(&preflight.remotes, &branches)? {
+        match lease.expected_remote_head {
+            Some(head) => println!(
+                "  {}/{} expected at {head}",
+                preflight.remotes.push, lease.branch
+            ),
+            None => println!(
+                "  {}/{} expected to be absent",
+                preflight.remotes.push, lease.branch
+            ),
+        }
+    }
+    println!("Dry run: nothing was changed. Run `stck sync` to apply this plan.");
+    Ok(())
+}
+
+/// Rebase locally recorded stack branches onto their recorded parents.
+///
+/// Unlike `stck sync`, this never fetches or talks to GitHub: the plan comes
+/// from the parents `stck new` records in git config, and every step rebases
+/// onto the local parent branch. Progress uses the resumable sync state.
+pub(crate) fn run_restack(
+    context: &env::LocalContext,
+    continue_restack: bool,
+    reset_restack: bool,
+) -> ExitCode {
+    let mut existing_state = match sync_state::load_sync() {
+        Ok(state) => state,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+    };
+
+    if existing_state.as_ref().is_some_and(|state| !state.offline) {
+        eprintln!(
+            "error: a `stck sync` is in progress; finish it with `stck sync --continue` or discard it with `stck sync --reset`"
+        );
+        return ExitCode::from(1);
+    }
+
+    if reset_restack {
+        if existing_state.is_some() {
+            if let Err(message) = sync_state::clear() {
+                eprintln!("error: {message}");
+                return ExitCode::from(1);
+            }
+            println!("Cleared previous restack state. Recomputing from scratch.");
+        } else {
+            println!("No existing restack state found. Computing restack plan from scratch.");
+        }
+        existing_state = None;
+    }
+
+    let state = match existing_state {
+        Some(state) => {
+            if !continue_restack {
+                println!(
+                    "Resuming previous restack operation from saved state. Use `stck restack --reset` to discard saved state and recompute."
+                );
+            }
+            state
+        }
+        None => {
+            if continue_restack {
+                eprintln!(
+                    "error: no restack state found; run `stck restack` to compute a new plan"
+                );
+                return ExitCode::from(1);
+            }
+
+            let steps = match plan_restack(context) {
+                Ok(steps) => steps,
+                Err(message) => {
+                    eprintln!("error: {message}");
+                    return ExitCode::from(1);
+                }
+            };
+            if steps.is_empty() {
+                println!("Stack is already restacked on its recorded parents. Nothing to do.");
+                return ExitCode::SUCCESS;
+            }
+
+            let mut state = SyncState {
+                steps,
+                completed_steps: 0,
+                failed_step: None,
+                failed_step_branch_head: None,
+                plan_scope: None,
+                offline: true,
+                backup_id: None,
+            };
+            match backup_sync_branches(&state.steps) {
+                Ok(id) => state.backup_id = Some(id),
+                Err(message) => {
+                    eprintln!("error: {message}");
+                    return ExitCode::from(1);
+                }
+            }
+            if let Err(message) = sync_state::save_sync(&context.journal, &state) {
+                eprintln!("error: {message}");
+                return ExitCode::from(1);
+            }
+            state
+        }
+    };
+
+    let code = execute_sync_steps(
+        &context.remotes,
+        &context.journal,
+        &context.default_branch,
+        &context.current_branch,
+        state,
+        continue_restack,
+    );
+    if code != ExitCode::SUCCESS {
+        return code;
+    }
+    println!("Restack succeeded locally.");
+    ExitCode::SUCCESS
+}
+
+/// Plan the rebases that put recorded branches back on their recorded parents.
+///
+/// A branch needs a step when its parent is rebased earlier in the plan or the
+/// parent's tip is no longer an ancestor of the branch.
+fn plan_restack(context: &env::LocalContext) -> Result<Vec<stack::SyncStep>, String> {
+    if gitops::rebase_in_progress()? {
+        return Err(
+            "rebase is already in progress; run `git rebase --continue` or `git rebase --abort` before starting a new `stck restack`"
+                .to_string(),
+        );
+    }
+
+    let local_parents = gitops::list_local_parents()?;
+    let mut steps: Vec<stack::SyncStep> = Vec::new();
+    for local in stack::local_restack_order(
+        &context.current_branch,
+        &context.default_branch,
+        &local_parents,
+    ) {
+        let branch_ref = format!("refs/heads/{}", local.branch);
+        let parent_ref = format!("refs/heads/{}", local.parent);
+        // Stale records for deleted branches (or parents) have nothing to restack.
+        if !gitops::local_branch_exists(&local.branch)?
+            || !gitops::local_branch_exists(&local.parent)?
+        {
+            continue;
+        }
+
+        let parent_moved = steps.iter().any(|step| step.branch == local.parent);
+        if !parent_moved && gitops::is_ancestor(&parent_ref, &branch_ref)? {
+            continue;
+        }
+
+        let mut fork_point = None;
+        if let Some(recorded) = local.fork_point.as_deref() {
+            if matches!(gitops::is_ancestor(recorded, &branch_ref), Ok(true)) {
+                fork_point = Some(recorded.to_string());
+            }
+        }
+        steps.push(stack::SyncStep {
+            branch: local.branch.clone(),
+            old_base_ref: local.parent.clone(),
+            new_base_ref: local.parent.clone(),
+            fork_point,
+        });
+    }
+    Ok(steps)
+}
+
+/// Run the remaining steps of a saved sync plan, then check out `original_branch`.
+///
+/// Progress is persisted after every step so a failed rebase can be resumed
+/// with `stck sync --continue` (or `stck restack --continue` for an offline
+/// plan). On success the saved state is cleared and, for online plans, the
+/// plan's retargets are cached for the next `stck push`.
+fn execute_sync_steps(
+    remotes: &Remotes,
+    journal: &Journal,
+    default_branch: &str,
+    original_branch: &str,
+    mut state: SyncState,
+    continue_sync: bool,
+) -> ExitCode {
+    let command = if state.offline {
+        "stck restack"
+    } else {
+        "stck sync"
+    };
+    if let Some(failed_step) = state.failed_step {
+        let rebase_in_progress = match gitops::rebase_in_progress() {
+            Ok(in_progress) => in_progress,
+            Err(message) => {
+                eprintln!("error: {message}");
+                return ExitCode::from(1);
+            }
+        };
+
+        if rebase_in_progress {
+            eprintln!("error: rebase is still in progress; run `git rebase --continue` (or `git rebase --abort`) before rerunning `{command}`");
+            return ExitCode::from(1);
+        }
+
+        if continue_sync {
+            let step = &state.steps[failed_step];
+            let branch_ref = format!("refs/heads/{}", step.branch);
+            let current_head = match gitops::resolve_ref(&branch_ref) {
+                Ok(sha) => sha,
+                Err(message) => {
+                    eprintln!("error: {message}");
+                    return ExitCode::from(1);
+                }
+            };
 
             let Some(failed_head) = state.failed_step_branch_head.as_deref() else {
-                eprintln!("error: sync state is missing failed-step branch head; rerun `stck sync` to retry");
+                eprintln!("error: sync state is missing failed-step branch head; rerun `{command}` to retry");
                 return ExitCode::from(1);
             };
 
             if current_head == failed_head {
-                eprintln!("error: no completed rebase detected for {}; resolve with `git rebase --continue` (or rerun `stck sync` to retry the step)", step.branch);
+                eprintln!("error: no completed rebase detected for {}; resolve with `git rebase --continue` (or rerun `{command}` to retry the step)", step.branch);
                 return ExitCode::from(1);
             }
 
+            if let Some(backup_id) = &state.backup_id {
+                if let Err(message) =
+                    sync_state::record_backup_head(backup_id, &step.branch, &current_head)
+                {
+                    eprintln!("error: {message}");
+                    return ExitCode::from(1);
+                }
+            }
             if state.completed_steps <= failed_step {
                 state.completed_steps = failed_step + 1;
             }
         } else {
             let step = &state.steps[failed_step];
             eprintln!(
-                "error: sync stopped at failed step for {}; run `stck sync --continue` after completing the rebase, or `stck sync --reset` to discard saved state and recompute",
+                "error: sync stopped at failed step for {}; run `{command} --continue` after completing the rebase, or `{command} --reset` to discard saved state and recompute",
                 step.branch
             );
             return ExitCode::from(1);
         }
         state.failed_step = None;
         state.failed_step_branch_head = None;
-        if let Err(message) = sync_state::save_sync(&state) {
+        if let Err(message) = sync_state::save_sync(journal, &state) {
             eprintln!("error: {message}");
             return ExitCode::from(1);
         }
@@ -641,20 +1420,26 @@ pub(crate) fn run_sync(
                 return ExitCode::from(1);
             }
         };
-        let old_base_sha =
-            match gitops::resolve_old_base_for_rebase(&step.old_base_ref, &step.branch) {
-                Ok(sha) => sha,
-                Err(message) => {
-                    eprintln!("error: {message}");
-                    return ExitCode::from(1);
+        let old_base_sha = match &step.fork_point {
+            Some(fork_point) => fork_point.clone(),
+            None => {
+                match gitops::resolve_old_base_for_rebase(remotes, &step.old_base_ref, &step.branch)
+                {
+                    Ok(sha) => sha,
+                    Err(message) => {
+                        eprintln!("error: {message}");
+                        return ExitCode::from(1);
+                    }
                 }
-            };
-        let onto_ref = if rebased_in_this_sync.contains(&step.new_base_ref) {
+            }
+        };
+        let onto_ref = if state.offline || rebased_in_this_sync.contains(&step.new_base_ref) {
             // Parent was rebased in a prior step of this sync; the local ref
             // is up-to-date but the remote ref is stale (not yet pushed).
+            // Offline restacks never consult remote refs at all.
             format!("refs/heads/{}", step.new_base_ref)
         } else {
-            match gitops::resolve_onto_ref(&step.new_base_ref) {
+            match gitops::resolve_onto_ref(remotes, &step.new_base_ref) {
                 Ok(r) => r,
                 Err(message) => {
                     eprintln!("error: {message}");
@@ -664,7 +1449,15 @@ pub(crate) fn run_sync(
         };
 
         let total_steps = state.steps.len();
-        if step.old_base_ref == step.new_base_ref {
+        if state.offline {
+            println!(
+                "Step {}/{}: restacking {} onto {}",
+                index + 1,
+                total_steps,
+                step.branch,
+                step.new_base_ref
+            );
+        } else if step.old_base_ref == step.new_base_ref {
             println!(
                 "Step {}/{}: rebasing {} onto {} (dropping already-upstream commits)",
                 index + 1,
@@ -689,7 +1482,7 @@ pub(crate) fn run_sync(
         if let Err(message) = gitops::rebase_onto(&onto_ref, &old_base_sha, &step.branch) {
             state.failed_step = Some(index);
             state.failed_step_branch_head = Some(branch_head);
-            if let Err(save_error) = sync_state::save_sync(&state) {
+            if let Err(save_error) = sync_state::save_sync(journal, &state) {
                 eprintln!("error: {save_error}");
                 return ExitCode::from(1);
             }
@@ -697,25 +1490,39 @@ pub(crate) fn run_sync(
             eprintln!();
             eprintln!("To recover:");
             eprintln!("  1. Resolve conflicts and run `git rebase --continue`");
-            eprintln!("     Then run `stck sync --continue` to resume.");
+            eprintln!("     Then run `{command} --continue` to resume.");
             eprintln!(
-                "  2. Or run `git rebase --abort` and then `stck sync --reset` to start over."
+                "  2. Or run `git rebase --abort` and then `{command} --reset` to start over."
             );
             return ExitCode::from(1);
         }
 
+        if let Some(backup_id) = &state.backup_id {
+            let recorded = gitops::resolve_ref(&branch_ref)
+                .and_then(|head| sync_state::record_backup_head(backup_id, &step.branch, &head));
+            if let Err(message) = recorded {
+                eprintln!("error: {message}");
+                return ExitCode::from(1);
+            }
+        }
+
         rebased_in_this_sync.insert(step.branch.clone());
         state.completed_steps = index + 1;
         state.failed_step = None;
         state.failed_step_branch_head = None;
-        if let Err(message) = sync_state::save_sync(&state) {
+        if let Err(message) = sync_state::save_sync(journal, &state) {
             eprintln!("error: {message}");
             return ExitCode::from(1);
         }
     }
 
+    if let Err(message) = refresh_local_parents(remotes, &state.steps, state.offline) {
+        eprintln!("error: {message}");
+        return ExitCode::from(1);
+    }
+
     println!("$ git checkout {}", original_branch);
-    if let Err(message) = gitops::checkout_branch(&original_branch) {
+    if let Err(message) = gitops::checkout_branch(original_branch) {
         if let Err(clear_error) = sync_state::clear() {
             eprintln!("error: {clear_error}");
             return ExitCode::from(1);
@@ -724,9 +1531,12 @@ pub(crate) fn run_sync(
         return ExitCode::from(1);
     }
 
-    if let Some(scope) = state.plan_scope.clone() {
+    if state.offline {
+        // Remote branches are untouched, so any plan cached by an earlier
+        // `stck sync` still describes what `stck push` has left to do.
+    } else if let Some(scope) = state.plan_scope.clone() {
         let last_plan = LastSyncPlan {
-            default_branch: preflight.default_branch.clone(),
+            default_branch: default_branch.to_string(),
             scope: Some(scope),
             retargets: state
                 .steps
@@ -750,23 +1560,135 @@ pub(crate) fn run_sync(
         eprintln!("error: {message}");
         return ExitCode::from(1);
     }
-    if state.steps.is_empty() {
-        println!("Stack is already up to date. No sync needed.");
-    } else {
-        println!("Sync succeeded locally. Run `stck push` to update remotes + PR bases.");
-    }
     ExitCode::SUCCESS
 }
 
-/// Push rewritten stack branches and retarget any affected pull requests.
-pub(crate) fn run_push(preflight: &env::PreflightContext) -> ExitCode {
-    if let Err(message) = gitops::fetch_origin() {
-        eprintln!("error: {message}");
-        return ExitCode::from(1);
+/// Rebase local-only branches from their recorded fork points.
+///
+/// A fork point is only used while it still matches the step's old base and
+/// remains an ancestor of the branch; otherwise the merge-base fallback applies.
+fn attach_local_fork_points(
+    stack: &stack::StackTree,
+    steps: &mut [stack::SyncStep],
+) -> Result<(), String> {
+    let local_parents = gitops::list_local_parents()?;
+    for step in steps {
+        let is_local = stack
+            .find(&step.branch)
+            .is_some_and(|index| stack.prs()[index].state == github::PrState::Local);
+        let Some(fork_point) = local_parents
+            .iter()
+            .find(|local| local.branch == step.branch && local.parent == step.old_base_ref)
+            .and_then(|local| local.fork_point.as_deref())
+        else {
+            continue;
+        };
+        // An unreadable fork point (e.g. pruned after a rewrite) is not fatal;
+        // the merge-base fallback still produces a usable range.
+        if is_local
+            && matches!(
+                gitops::is_ancestor(fork_point, &format!("refs/heads/{}", step.branch)),
+                Ok(true)
+            )
+        {
+            step.fork_point = Some(fork_point.to_string());
+        }
     }
+    Ok(())
+}
 
-    let existing_state = match sync_state::load_push() {
-        Ok(state) => state,
+/// Point recorded parents of restacked branches at their new base commits.
+///
+/// `local_onto` mirrors an offline plan, whose steps always rebase onto the
+/// local parent branch.
+fn refresh_local_parents(
+    remotes: &Remotes,
+    steps: &[stack::SyncStep],
+    local_onto: bool,
+) -> Result<(), String> {
+    let local_parents = gitops::list_local_parents()?;
+    for step in steps {
+        if !local_parents
+            .iter()
+            .any(|local| local.branch == step.branch)
+        {
+            continue;
+        }
+        let onto_ref = if local_onto || steps.iter().any(|other| other.branch == step.new_base_ref)
+        {
+            format!("refs/heads/{}", step.new_base_ref)
+        } else {
+            gitops::resolve_onto_ref(remotes, &step.new_base_ref)?
+        };
+        let onto_sha = gitops::resolve_ref(&onto_ref)?;
+        gitops::record_local_parent(&step.branch, &step.new_base_ref, &onto_sha)?;
+    }
+    Ok(())
+}
+
+/// Compute the branches a fresh `stck push` would push and the PR retargets
+/// it would apply, reusing the remote leases of a matching cached sync plan.
+///
+/// A cached plan that no longer matches the stack is discarded when
+/// `clear_stale_plan` is set.
+fn plan_push(
+    preflight: &env::PreflightContext,
+    clear_stale_plan: bool,
+) -> Result<PushState, String> {
+    let stack = github::discover_stack_tree(
+        preflight.forge.as_ref(),
+        &preflight.remotes,
+        &preflight.current_branch,
+        &preflight.default_branch,
+    )?;
+    let cached_plan = sync_state::load_last_sync_plan()?;
+    let (retargets, sync_push_leases) = match cached_plan {
+        Some(plan)
+            if plan.matches(
+                &preflight.repository,
+                &preflight.default_branch,
+                stack.prs(),
+            ) =>
+        {
+            let push_leases = plan.push_leases().to_vec();
+            (plan.retargets, push_leases)
+        }
+        cached_plan => {
+            if cached_plan.is_some() && clear_stale_plan {
+                sync_state::clear_last_sync_plan()?;
+            }
+            (
+                stack::build_push_retargets(&stack, &preflight.default_branch),
+                Vec::new(),
+            )
+        }
+    };
+    let retargets = stack::filter_pending_retargets(retargets, &stack);
+    let mut push_branches = Vec::new();
+    for branch in stack::build_push_branches(&stack) {
+        if gitops::branch_needs_push(&preflight.remotes, &branch)? {
+            push_branches.push(branch);
+        }
+    }
+
+    Ok(PushState {
+        push_branches,
+        completed_pushes: 0,
+        sync_push_leases,
+        retargets,
+        completed_retargets: 0,
+    })
+}
+
+/// Push rewritten stack branches and retarget any affected pull requests.
+pub(crate) fn run_push(preflight: &env::PreflightContext) -> ExitCode {
+    if let Err(message) = gitops::fetch_remotes(&preflight.remotes) {
+        eprintln!("error: {message}");
+        return ExitCode::from(1);
+    }
+
+    let existing_state = match sync_state::load_push() {
+        Ok(state) => state,
         Err(message) => {
             eprintln!("error: {message}");
             return ExitCode::from(1);
@@ -776,7 +1698,9 @@ pub(crate) fn run_push(preflight: &env::PreflightContext) -> ExitCode {
     let mut state = match existing_state {
         Some(mut state) => {
             if state.completed_retargets < state.retargets.len() {
-                let stack = match github::discover_linear_stack(
+                let stack = match github::discover_stack_tree(
+                    preflight.forge.as_ref(),
+                    &preflight.remotes,
                     &preflight.current_branch,
                     &preflight.default_branch,
                 ) {
@@ -793,7 +1717,7 @@ pub(crate) fn run_push(preflight: &env::PreflightContext) -> ExitCode {
                 );
                 state.completed_retargets = 0;
 
-                if let Err(message) = sync_state::save_push(&state) {
+                if let Err(message) = sync_state::save_push(&preflight.journal, &state) {
                     eprintln!("error: {message}");
                     return ExitCode::from(1);
                 }
@@ -802,67 +1726,14 @@ pub(crate) fn run_push(preflight: &env::PreflightContext) -> ExitCode {
             state
         }
         None => {
-            let stack = match github::discover_linear_stack(
-                &preflight.current_branch,
-                &preflight.default_branch,
-            ) {
-                Ok(stack) => stack,
-                Err(message) => {
-                    eprintln!("error: {message}");
-                    return ExitCode::from(1);
-                }
-            };
-            let cached_plan = match sync_state::load_last_sync_plan() {
-                Ok(plan) => plan,
+            let state = match plan_push(preflight, true) {
+                Ok(state) => state,
                 Err(message) => {
                     eprintln!("error: {message}");
                     return ExitCode::from(1);
                 }
             };
-            let (retargets, sync_push_leases) = if let Some(plan) = cached_plan {
-                if plan.matches(&preflight.repository, &preflight.default_branch, &stack) {
-                    let push_leases = plan.push_leases().to_vec();
-                    (plan.retargets, push_leases)
-                } else {
-                    if let Err(message) = sync_state::clear_last_sync_plan() {
-                        eprintln!("error: {message}");
-                        return ExitCode::from(1);
-                    }
-                    (
-                        stack::build_push_retargets(&stack, &preflight.default_branch),
-                        Vec::new(),
-                    )
-                }
-            } else {
-                (
-                    stack::build_push_retargets(&stack, &preflight.default_branch),
-                    Vec::new(),
-                )
-            };
-            let retargets = stack::filter_pending_retargets(retargets, &stack);
-            let mut push_branches = Vec::new();
-            for branch in stack::build_push_branches(&stack) {
-                let needs_push = match gitops::branch_needs_push(&branch) {
-                    Ok(needs_push) => needs_push,
-                    Err(message) => {
-                        eprintln!("error: {message}");
-                        return ExitCode::from(1);
-                    }
-                };
-
-                if needs_push {
-                    push_branches.push(branch);
-                }
-            }
-
-            let state = PushState {
-                push_branches,
-                completed_pushes: 0,
-                sync_push_leases,
-                retargets,
-                completed_retargets: 0,
-            };
-            if let Err(message) = sync_state::save_push(&state) {
+            if let Err(message) = sync_state::save_push(&preflight.journal, &state) {
                 eprintln!("error: {message}");
                 return ExitCode::from(1);
             }
@@ -874,7 +1745,7 @@ pub(crate) fn run_push(preflight: &env::PreflightContext) -> ExitCode {
 
     for index in state.completed_pushes..state.push_branches.len() {
         let branch = state.push_branches[index].clone();
-        let remote_ref = format!("refs/remotes/origin/{branch}");
+        let remote_ref = preflight.remotes.push_ref(&branch);
         let local_ref = format!("refs/heads/{branch}");
         let local_head = match gitops::resolve_ref(&local_ref) {
             Ok(head) => head,
@@ -883,7 +1754,7 @@ pub(crate) fn run_push(preflight: &env::PreflightContext) -> ExitCode {
                 return ExitCode::from(1);
             }
         };
-        let remote_head = match gitops::remote_branch_head(&branch) {
+        let remote_head = match gitops::remote_branch_head(&preflight.remotes, &branch) {
             Ok(head) => head,
             Err(message) => {
                 eprintln!("error: {message}");
@@ -892,9 +1763,12 @@ pub(crate) fn run_push(preflight: &env::PreflightContext) -> ExitCode {
         };
 
         if remote_head.as_deref() == Some(local_head.as_str()) {
-            println!("Branch {branch} already matches origin; skipping.");
+            println!(
+                "Branch {branch} already matches {}; skipping.",
+                preflight.remotes.push
+            );
             state.completed_pushes = index + 1;
-            if let Err(message) = sync_state::save_push(&state) {
+            if let Err(message) = sync_state::save_push(&preflight.journal, &state) {
                 eprintln!("error: {message}");
                 return ExitCode::from(1);
             }
@@ -920,7 +1794,8 @@ pub(crate) fn run_push(preflight: &env::PreflightContext) -> ExitCode {
                     let expected = expected_remote_head.as_deref().unwrap_or("missing");
                     let found = remote_head.as_deref().unwrap_or("missing");
                     eprintln!(
-                        "error: remote branch `origin/{branch}` changed since sync; expected {expected}, found {found}; integrate the remote changes locally, then rerun `stck sync` before pushing"
+                        "error: remote branch `{}/{branch}` changed since sync; expected {expected}, found {found}; integrate the remote changes locally, then rerun `stck sync` before pushing",
+                        preflight.remotes.push
                     );
                     return ExitCode::from(1);
                 }
@@ -931,13 +1806,16 @@ pub(crate) fn run_push(preflight: &env::PreflightContext) -> ExitCode {
                     match gitops::is_ancestor(&remote_ref, &local_ref) {
                         Ok(true) => {}
                         Ok(false) => {
-                            if let Err(save_error) = sync_state::save_push(&state) {
+                            if let Err(save_error) =
+                                sync_state::save_push(&preflight.journal, &state)
+                            {
                                 eprintln!("error: {save_error}");
                                 return ExitCode::from(1);
                             }
                             eprintln!(
-                                "error: remote branch `origin/{branch}` has commits not in local `{branch}`; \
-                                 pull or rebase to integrate remote changes before pushing"
+                                "error: remote branch `{}/{branch}` has commits not in local `{branch}`; \
+                                 pull or rebase to integrate remote changes before pushing",
+                                preflight.remotes.push
                             );
                             return ExitCode::from(1);
                         }
@@ -961,11 +1839,13 @@ pub(crate) fn run_push(preflight: &env::PreflightContext) -> ExitCode {
             "--force-with-lease=refs/heads/{branch}:{}",
             expected_remote_head.as_deref().unwrap_or_default()
         );
-        println!("$ git push {lease} origin {branch}");
-        if let Err(message) =
-            gitops::push_force_with_lease(&branch, expected_remote_head.as_deref())
-        {
-            if let Err(save_error) = sync_state::save_push(&state) {
+        println!("$ git push {lease} {} {branch}", preflight.remotes.push);
+        if let Err(message) = gitops::push_force_with_lease(
+            &preflight.remotes,
+            &branch,
+            expected_remote_head.as_deref(),
+        ) {
+            if let Err(save_error) = sync_state::save_push(&preflight.journal, &state) {
                 eprintln!("error: {save_error}");
                 return ExitCode::from(1);
             }
@@ -977,7 +1857,7 @@ pub(crate) fn run_push(preflight: &env::PreflightContext) -> ExitCode {
 
         state.completed_pushes = index + 1;
         pushed_this_run += 1;
-        if let Err(message) = sync_state::save_push(&state) {
+        if let Err(message) = sync_state::save_push(&preflight.journal, &state) {
             eprintln!("error: {message}");
             return ExitCode::from(1);
         }
@@ -993,27 +1873,49 @@ pub(crate) fn run_push(preflight: &env::PreflightContext) -> ExitCode {
             retarget.new_base_ref
         );
         println!(
-            "$ gh pr edit {} --base {}",
-            retarget.branch, retarget.new_base_ref
+            "{}",
+            preflight.forge.describe(ForgeOperation::Retarget {
+                branch: &retarget.branch,
+                new_base: &retarget.new_base_ref,
+            })
         );
-        if let Err(message) = github::retarget_pr_base(&retarget.branch, &retarget.new_base_ref) {
-            if let Err(save_error) = sync_state::save_push(&state) {
+        if let Err(message) = preflight
+            .forge
+            .retarget_pr(&retarget.branch, &retarget.new_base_ref)
+        {
+            if let Err(save_error) = sync_state::save_push(&preflight.journal, &state) {
                 eprintln!("error: {save_error}");
                 return ExitCode::from(1);
             }
             eprintln!("error: {message}");
             eprintln!();
-            eprintln!("Fix the GitHub error and rerun `stck push` to resume.");
+            eprintln!("Fix the forge error and rerun `stck push` to resume.");
             return ExitCode::from(1);
         }
+        preflight.journal.record_pr_mutation(PrMutation::Retarget {
+            branch: retarget.branch.clone(),
+            new_base: retarget.new_base_ref.clone(),
+        });
 
         state.completed_retargets = index + 1;
-        if let Err(message) = sync_state::save_push(&state) {
+        if let Err(message) = sync_state::save_push(&preflight.journal, &state) {
             eprintln!("error: {message}");
             return ExitCode::from(1);
         }
     }
 
+    // Runs after the retargets so the tables show the new bases; a failure
+    // leaves the completed push state in place so a rerun only retries this.
+    let updated_descriptions = match refresh_stack_navigation(preflight) {
+        Ok(updated) => updated,
+        Err(message) => {
+            eprintln!("error: {message}");
+            eprintln!();
+            eprintln!("Fix the error and rerun `stck push` to update the stack navigation.");
+            return ExitCode::from(1);
+        }
+    };
+
     if let Err(message) = sync_state::clear() {
         eprintln!("error: {message}");
         return ExitCode::from(1);
@@ -1030,5 +1932,1775 @@ pub(crate) fn run_push(preflight: &env::PreflightContext) -> ExitCode {
         "Push succeeded. Pushed {} branch(es) and applied {} PR base update(s) in this run.",
         pushed_this_run, retargeted_this_run
     );
+    if updated_descriptions > 0 {
+        println!("Updated stack navigation in {updated_descriptions} PR description(s).");
+    }
     ExitCode::SUCCESS
 }
+
+/// Print the pushes and PR retargets `stck push` would run without changing anything.
+///
+/// A saved push operation is previewed from where it stopped. The command
+/// fails when a push would be refused because a remote branch moved since
+/// `stck sync` captured its lease.
+pub(crate) fn run_push_dry_run(preflight: &env::PreflightContext) -> ExitCode {
+    match print_push_plan(preflight) {
+        Ok(0) => ExitCode::SUCCESS,
+        Ok(blocked) => {
+            eprintln!(
+                "error: {blocked} push(es) would be refused; integrate the remote changes locally, then rerun `stck sync` before pushing"
+            );
+            ExitCode::from(1)
+        }
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::from(1)
+        }
+    }
+}
+
+/// Print the push plan and return how many of its pushes would be refused.
+fn print_push_plan(preflight: &env::PreflightContext) -> Result<usize, String> {
+    gitops::fetch_remotes(&preflight.remotes)?;
+    let mut state = match sync_state::load_push()? {
+        Some(mut state) => {
+            println!("Previewing the remaining steps of the interrupted push.");
+            if state.completed_retargets < state.retargets.len() {
+                let stack = github::discover_stack_tree(
+                    preflight.forge.as_ref(),
+                    &preflight.remotes,
+                    &preflight.current_branch,
+                    &preflight.default_branch,
+                )?;
+                state.retargets = stack::filter_pending_retargets(
+                    state.retargets[state.completed_retargets..].to_vec(),
+                    &stack,
+                );
+            } else {
+                state.retargets.clear();
+            }
+            state
+        }
+        None => plan_push(preflight, false)?,
+    };
+    let push_branches = state.push_branches.split_off(state.completed_pushes);
+    if push_branches.is_empty() && state.retargets.is_empty() {
+        println!("Nothing to push or retarget.");
+        return Ok(0);
+    }
+
+    let remote = &preflight.remotes.push;
+    let mut blocked = 0;
+    for (index, branch) in push_branches.iter().enumerate() {
+        let remote_ref = preflight.remotes.push_ref(branch);
+        let local_ref = format!("refs/heads/{branch}");
+        let local_head = gitops::resolve_ref(&local_ref)?;
+        let remote_head = gitops::remote_branch_head(&preflight.remotes, branch)?;
+        let position = format!("Push {}/{}: {branch}", index + 1, push_branches.len());
+
+        if remote_head.as_deref() == Some(local_head.as_str()) {
+            println!("{position} (already matches {remote}; would skip)");
+            continue;
+        }
+
+        let sync_expected_remote_head = state
+            .sync_push_leases
+            .iter()
+            .find(|lease| lease.branch == *branch)
+            .map(|lease| lease.expected_remote_head.clone());
+        if let Some(expected_remote_head) = &sync_expected_remote_head {
+            if *expected_remote_head != remote_head {
+                let expected = expected_remote_head.as_deref().unwrap_or("missing");
+                let found = remote_head.as_deref().unwrap_or("missing");
+                println!(
+                    "{position} (stale lease: sync expected {remote}/{branch} at {expected}, found {found})"
+                );
+                blocked += 1;
+                continue;
+            }
+        }
+
+        let kind = match &remote_head {
+            None => "new upstream".to_string(),
+            Some(head) if gitops::is_ancestor(&remote_ref, &local_ref)? => {
+                format!("fast-forward from {head}")
+            }
+            Some(head) if sync_expected_remote_head.is_some() => {
+                format!("force-with-lease, expected {remote}/{branch} at {head}")
+            }
+            Some(_) => {
+                println!(
+                    "{position} (refused: {remote}/{branch} has commits not in local `{branch}`)"
+                );
+                blocked += 1;
+                continue;
+            }
+        };
+        println!("{position} ({kind})");
+        println!(
+            "$ git push --force-with-lease=refs/heads/{branch}:{} {remote} {branch}",
+            remote_head.as_deref().unwrap_or_default()
+        );
+    }
+
+    for (index, retarget) in state.retargets.iter().enumerate() {
+        println!(
+            "Retarget PR {}/{}: {} -> {}",
+            index + 1,
+            state.retargets.len(),
+            retarget.branch,
+            retarget.new_base_ref
+        );
+        println!(
+            "{}",
+            preflight.forge.describe(ForgeOperation::Retarget {
+                branch: &retarget.branch,
+                new_base: &retarget.new_base_ref,
+            })
+        );
+    }
+
+    if blocked == 0 {
+        println!("Dry run: nothing was changed. Run `stck push` to apply this plan.");
+    }
+    Ok(blocked)
+}
+
+/// Rewrite the stack navigation block in every open PR description of the stack.
+pub(crate) fn run_nav(preflight: &env::PreflightContext) -> ExitCode {
+    match refresh_stack_navigation(preflight) {
+        Ok(0) => {
+            println!("Stack navigation is already up to date.");
+            ExitCode::SUCCESS
+        }
+        Ok(updated) => {
+            println!("Updated stack navigation in {updated} PR description(s).");
+            ExitCode::SUCCESS
+        }
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::from(1)
+        }
+    }
+}
+
+/// Rewrite the navigation block of each open PR in the current stack whose
+/// description is out of date, returning how many descriptions changed.
+fn refresh_stack_navigation(preflight: &env::PreflightContext) -> Result<usize, String> {
+    let forge = preflight.forge.as_ref();
+    let stack = github::discover_stack_tree(
+        forge,
+        &preflight.remotes,
+        &preflight.current_branch,
+        &preflight.default_branch,
+    )?;
+
+    let mut updated = 0;
+    for pr in stack.prs().iter().filter(|pr| pr.state == PrState::Open) {
+        let branch = &pr.head_ref_name;
+        let body = forge.pr_body(branch)?;
+        let block =
+            github::stack_navigation_block(&stack, branch, |number| forge.pr_reference(number));
+        let new_body = github::replace_stack_block(&body, &block);
+        if new_body == body {
+            continue;
+        }
+
+        println!(
+            "Updating stack navigation for {}: {branch}",
+            forge.pr_reference(pr.number)
+        );
+        println!("{}", forge.describe(ForgeOperation::UpdateBody { branch }));
+        forge.update_pr_body(branch, &new_body)?;
+        preflight
+            .journal
+            .record_pr_mutation(PrMutation::UpdateBody {
+                branch: branch.to_string(),
+            });
+        updated += 1;
+    }
+    Ok(updated)
+}
+
+/// Merge the bottom open PR of the stack and restack the branches above it.
+///
+/// Child PRs are retargeted to the default branch before the merge so GitHub
+/// does not close them when the landed branch is deleted. Land progress is
+/// saved via `sync_state`; once the PR is merged the restack runs as a regular
+/// sync followed by a push, resumable through those commands.
+pub(crate) fn run_land(preflight: &env::PreflightContext, method: Option<MergeMethod>) -> ExitCode {
+    if preflight.current_branch == preflight.default_branch {
+        eprintln!(
+            "error: cannot land from default branch {}; checkout a branch in the stack and retry",
+            preflight.default_branch
+        );
+        return ExitCode::from(1);
+    }
+
+    let existing_state = match sync_state::load_land() {
+        Ok(state) => state,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+    };
+
+    let mut state = match existing_state {
+        Some(mut state) => {
+            println!(
+                "Resuming previous land operation for {} from saved state.",
+                state.branch
+            );
+            if !state.merged {
+                // The merge may have gone through even though saving the
+                // progress afterwards did not.
+                match preflight.forge.fetch_pr_for_head(&state.branch) {
+                    Ok(pr) => state.merged = pr.state == github::PrState::Merged,
+                    Err(message) => {
+                        eprintln!("error: {message}");
+                        return ExitCode::from(1);
+                    }
+                }
+            }
+            state
+        }
+        None => {
+            let state = match plan_land(preflight, method) {
+                Ok(state) => state,
+                Err(message) => {
+                    eprintln!("error: {message}");
+                    return ExitCode::from(1);
+                }
+            };
+            if let Err(message) = sync_state::save_land(&preflight.journal, &state) {
+                eprintln!("error: {message}");
+                return ExitCode::from(1);
+            }
+            state
+        }
+    };
+
+    for index in state.completed_retargets..state.retargets.len() {
+        let retarget = &state.retargets[index];
+        println!(
+            "Retargeting PR {}/{}: {} -> {}",
+            index + 1,
+            state.retargets.len(),
+            retarget.branch,
+            retarget.new_base_ref
+        );
+        println!(
+            "{}",
+            preflight.forge.describe(ForgeOperation::Retarget {
+                branch: &retarget.branch,
+                new_base: &retarget.new_base_ref,
+            })
+        );
+        if let Err(message) = preflight
+            .forge
+            .retarget_pr(&retarget.branch, &retarget.new_base_ref)
+        {
+            eprintln!("error: {message}");
+            eprintln!();
+            eprintln!("Fix the forge error and rerun `stck land` to resume.");
+            return ExitCode::from(1);
+        }
+        preflight.journal.record_pr_mutation(PrMutation::Retarget {
+            branch: retarget.branch.clone(),
+            new_base: retarget.new_base_ref.clone(),
+        });
+
+        state.completed_retargets = index + 1;
+        if let Err(message) = sync_state::save_land(&preflight.journal, &state) {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+    }
+
+    if !state.merged {
+        println!(
+            "{}",
+            preflight.forge.describe(ForgeOperation::Merge {
+                branch: &state.branch,
+                method: state.merge_method,
+                head_sha: &state.head_sha,
+            })
+        );
+        if let Err(message) =
+            preflight
+                .forge
+                .merge_pr(&state.branch, state.merge_method, &state.head_sha)
+        {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+        preflight.journal.record_pr_mutation(PrMutation::Merge {
+            branch: state.branch.clone(),
+            method: state.merge_method,
+        });
+
+        state.merged = true;
+        if let Err(message) = sync_state::save_land(&preflight.journal, &state) {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+    }
+    println!("Landed {} into {}.", state.branch, preflight.default_branch);
+
+    if let Err(message) = gitops::fetch_remotes(&preflight.remotes) {
+        eprintln!("error: {message}");
+        return ExitCode::from(1);
+    }
+
+    if state.steps.is_empty() {
+        if let Err(message) = sync_state::clear() {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+        println!("No stacked branches left to restack.");
+        return ExitCode::SUCCESS;
+    }
+
+    let stack = match github::discover_stack_tree(
+        preflight.forge.as_ref(),
+        &preflight.remotes,
+        &state.return_branch,
+        &preflight.default_branch,
+    ) {
+        Ok(stack) => stack,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+    };
+    let lease_branches = state
+        .steps
+        .iter()
+        .map(|step| step.branch.clone())
+        .collect::<Vec<_>>();
+    let push_leases = match capture_remote_branch_leases(&preflight.remotes, &lease_branches) {
+        Ok(push_leases) => push_leases,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+    };
+    let mut sync = SyncState {
+        steps: state.steps,
+        completed_steps: 0,
+        failed_step: None,
+        failed_step_branch_head: None,
+        plan_scope: Some(SyncPlanScope::new(
+            &preflight.repository,
+            stack.prs(),
+            push_leases,
+        )),
+        offline: false,
+        backup_id: None,
+    };
+    match backup_sync_branches(&sync.steps) {
+        Ok(id) => sync.backup_id = Some(id),
+        Err(message) => {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+    }
+    if let Err(message) = sync_state::save_sync(&preflight.journal, &sync) {
+        eprintln!("error: {message}");
+        return ExitCode::from(1);
+    }
+
+    // The rest of the stack now hangs off the return branch; sync and push
+    // operate on it as if the user had run them from there.
+    let restack = env::PreflightContext {
+        current_branch: state.return_branch.clone(),
+        ..preflight.clone()
+    };
+    let code = execute_sync_steps(
+        &restack.remotes,
+        &restack.journal,
+        &restack.default_branch,
+        &state.return_branch,
+        sync,
+        false,
+    );
+    if code != ExitCode::SUCCESS {
+        return code;
+    }
+    run_push(&restack)
+}
+
+fn plan_land(
+    preflight: &env::PreflightContext,
+    method: Option<MergeMethod>,
+) -> Result<LandState, String> {
+    if gitops::rebase_in_progress()? {
+        return Err(
+            "rebase is in progress; run `git rebase --continue` or `git rebase --abort` before landing"
+                .to_string(),
+        );
+    }
+    gitops::fetch_remotes(&preflight.remotes)?;
+
+    let stack = github::discover_stack_tree(
+        preflight.forge.as_ref(),
+        &preflight.remotes,
+        &preflight.current_branch,
+        &preflight.default_branch,
+    )?;
+    let landed =
+        stack::land_candidate(&stack, &preflight.default_branch, &preflight.current_branch)
+            .ok_or_else(|| {
+                format!(
+                    "no open PR in this stack targets {}; run `stck sync` and `stck push` first",
+                    preflight.default_branch
+                )
+            })?;
+    let branch = landed.head_ref_name.clone();
+    if landed.state == github::PrState::Local {
+        return Err(format!(
+            "bottom branch {branch} has no PR yet; run `stck submit` on it before landing"
+        ));
+    }
+
+    let children = stack::open_children(&stack, &branch);
+    if children.len() > 1 {
+        let names = children
+            .iter()
+            .map(|pr| pr.head_ref_name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(format!(
+            "cannot land {branch}: it has several open child PRs ({names}); merge it on GitHub, then run `stck sync` and `stck push` from each child"
+        ));
+    }
+
+    if gitops::branch_needs_push(&preflight.remotes, &branch)? {
+        return Err(format!(
+            "local branch {branch} differs from {}; run `stck push` before landing",
+            preflight.remotes.push
+        ));
+    }
+    let head_sha = gitops::remote_branch_head(&preflight.remotes, &branch)?.ok_or_else(|| {
+        format!(
+            "branch {branch} is missing on {}; push it before landing",
+            preflight.remotes.push
+        )
+    })?;
+
+    let allowed = preflight.forge.allowed_merge_methods()?;
+    let merge_method = match method {
+        Some(method) if allowed.contains(&method) => method,
+        Some(method) => {
+            return Err(format!(
+                "merge method `{method}` is not allowed in {}; allowed: {}",
+                preflight.repository,
+                allowed
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        }
+        None => *allowed.first().ok_or_else(|| {
+            format!(
+                "{} does not allow any merge method; enable one in the repository settings",
+                preflight.repository
+            )
+        })?,
+    };
+
+    let return_branch = if preflight.current_branch == branch {
+        children
+            .first()
+            .map_or_else(|| branch.clone(), |child| child.head_ref_name.clone())
+    } else {
+        preflight.current_branch.clone()
+    };
+    let retargets = children
+        .iter()
+        .filter(|child| child.state != github::PrState::Local)
+        .map(|child| stack::RetargetStep {
+            branch: child.head_ref_name.clone(),
+            new_base_ref: preflight.default_branch.clone(),
+        })
+        .collect();
+
+    let mut steps = stack::build_land_sync_plan(&stack, &preflight.default_branch, &branch);
+    attach_local_fork_points(&stack, &mut steps)?;
+
+    println!("Landing {} (merge method: {}).", branch, merge_method);
+    Ok(LandState {
+        steps,
+        branch,
+        merge_method,
+        head_sha,
+        return_branch,
+        retargets,
+        completed_retargets: 0,
+        merged: false,
+    })
+}
+
+/// Create `new_branch` on top of the current branch and move the current
+/// branch's children onto it.
+///
+/// The new branch is pushed when any child has a PR, so the child PRs can be
+/// retargeted to it; it gets its own PR once it has commits.
+pub(crate) fn run_insert(
+    preflight: &env::PreflightContext,
+    new_branch: Option<&str>,
+    continue_edit: bool,
+    reset_edit: bool,
+) -> ExitCode {
+    run_edit(
+        preflight,
+        "insert",
+        continue_edit,
+        reset_edit,
+        new_branch.map(|new_branch| move || plan_insert(preflight, new_branch)),
+    )
+}
+
+fn plan_insert(preflight: &env::PreflightContext, new_branch: &str) -> Result<EditState, String> {
+    let current_branch = &preflight.current_branch;
+    if current_branch == &preflight.default_branch {
+        return Err(format!(
+            "cannot insert above default branch {current_branch}; checkout the branch the new one should sit on and retry"
+        ));
+    }
+    let new_branch = apply_branch_prefix(preflight, new_branch);
+    check_new_branch(&preflight.remotes, &new_branch)?;
+    let stack = plan_edit_stack(preflight)?;
+
+    let children = stack::open_children(&stack, current_branch);
+    if children.is_empty() {
+        return Err(format!(
+            "{current_branch} has no child branches to insert {new_branch} below; use `stck new {new_branch}` to add a branch on top"
+        ));
+    }
+    let moves = children
+        .iter()
+        .map(|child| (child.head_ref_name.clone(), new_branch.clone()))
+        .collect::<Vec<_>>();
+    let plan = stack::build_reparent_plan(&stack, &preflight.default_branch, &moves)?;
+
+    let mut actions = vec![
+        EditAction::CreateBranch {
+            branch: new_branch.clone(),
+            start_point: gitops::resolve_ref(&format!("refs/heads/{current_branch}"))?,
+        },
+        EditAction::RecordParent {
+            branch: new_branch.clone(),
+            parent: current_branch.clone(),
+        },
+    ];
+    if children.iter().any(|child| child.state != PrState::Local) {
+        actions.push(EditAction::Publish {
+            branch: new_branch.clone(),
+        });
+    }
+    reparent_actions(preflight, &stack, plan, &mut actions)?;
+
+    println!(
+        "Inserting {new_branch} between {current_branch} and {}.",
+        moves
+            .iter()
+            .map(|(child, _)| child.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    Ok(EditState {
+        command: "insert".to_string(),
+        return_branch: new_branch,
+        actions,
+        completed_actions: 0,
+        failed_rebase_head: None,
+    })
+}
+
+/// Move `branch` to `target` within the stack, handing its children to its old parent.
+pub(crate) fn run_move(
+    preflight: &env::PreflightContext,
+    request: Option<(&str, &stack::MoveTarget)>,
+    continue_edit: bool,
+    reset_edit: bool,
+) -> ExitCode {
+    run_edit(
+        preflight,
+        "move",
+        continue_edit,
+        reset_edit,
+        request.map(|(branch, target)| move || plan_move(preflight, branch, target)),
+    )
+}
+
+fn plan_move(
+    preflight: &env::PreflightContext,
+    branch: &str,
+    target: &stack::MoveTarget,
+) -> Result<EditState, String> {
+    if preflight.current_branch == preflight.default_branch {
+        return Err(format!(
+            "cannot move branches from default branch {}; checkout a branch of the stack and retry",
+            preflight.default_branch
+        ));
+    }
+    let stack = plan_edit_stack(preflight)?;
+    let plan = stack::build_move_plan(&stack, &preflight.default_branch, branch, target)?;
+    let mut actions = Vec::new();
+    reparent_actions(preflight, &stack, plan, &mut actions)?;
+
+    match target {
+        stack::MoveTarget::Onto(onto) => println!("Moving {branch} onto {onto}."),
+        stack::MoveTarget::Before(before) => println!("Moving {branch} before {before}."),
+    }
+    Ok(EditState {
+        command: "move".to_string(),
+        return_branch: preflight.current_branch.clone(),
+        actions,
+        completed_actions: 0,
+        failed_rebase_head: None,
+    })
+}
+
+/// Fold the current branch into its parent and drop its PR.
+///
+/// The parent is fast-forwarded to the branch (rebasing the branch first when
+/// the parent has moved on), the branch's children are restacked and
+/// retargeted onto the parent, and the branch's PR is closed with a comment
+/// pointing at the parent before the branch is deleted locally and remotely.
+pub(crate) fn run_fold(
+    preflight: &env::PreflightContext,
+    continue_edit: bool,
+    reset_edit: bool,
+) -> ExitCode {
+    run_edit(
+        preflight,
+        "fold",
+        continue_edit,
+        reset_edit,
+        (!continue_edit && !reset_edit).then_some(|| plan_fold(preflight)),
+    )
+}
+
+fn plan_fold(preflight: &env::PreflightContext) -> Result<EditState, String> {
+    let branch = &preflight.current_branch;
+    if branch == &preflight.default_branch {
+        return Err(format!(
+            "cannot fold default branch {branch}; checkout the branch to fold and retry"
+        ));
+    }
+    let stack = plan_edit_stack(preflight)?;
+    let pr = stack
+        .find(branch)
+        .map(|index| &stack.prs()[index])
+        .filter(|pr| pr.state != PrState::Merged)
+        .ok_or_else(|| format!("{branch} is not an open branch of this stack"))?;
+    let parent = pr.base_ref_name.clone();
+    if parent == preflight.default_branch {
+        return Err(format!(
+            "{branch} is stacked directly on {parent}; use `stck land` to merge it instead"
+        ));
+    }
+    let parent_pr = stack.find(&parent).map(|index| &stack.prs()[index]);
+
+    let mut actions = Vec::new();
+    let parent_ref = format!("refs/heads/{parent}");
+    if !gitops::is_ancestor(&parent_ref, &format!("refs/heads/{branch}"))? {
+        actions.push(EditAction::Rebase(stack::SyncStep {
+            branch: branch.clone(),
+            old_base_ref: parent.clone(),
+            new_base_ref: parent.clone(),
+            fork_point: Some(edit_fork_point(preflight, &parent, branch)?),
+        }));
+    }
+    // The target is resolved when the step runs, after any rebase above.
+    actions.push(EditAction::ResetBranch {
+        branch: parent.clone(),
+        target: branch.clone(),
+    });
+    if parent_pr.is_some_and(|parent_pr| parent_pr.state != PrState::Local) {
+        if let Some(head) = gitops::remote_branch_head(&preflight.remotes, &parent)? {
+            actions.push(EditAction::Push {
+                branch: parent.clone(),
+                expected_remote_head: head,
+            });
+        }
+    }
+
+    let moves = stack::open_children(&stack, branch)
+        .into_iter()
+        .map(|child| (child.head_ref_name.clone(), parent.clone()))
+        .collect::<Vec<_>>();
+    let plan = stack::build_reparent_plan(&stack, &preflight.default_branch, &moves)?;
+    reparent_actions(preflight, &stack, plan, &mut actions)?;
+
+    if pr.state != PrState::Local {
+        let target = match parent_pr.filter(|parent_pr| parent_pr.state != PrState::Local) {
+            Some(parent_pr) => format!(
+                "{} (`{parent}`)",
+                preflight.forge.pr_reference(parent_pr.number)
+            ),
+            None => format!("`{parent}`"),
+        };
+        actions.push(EditAction::ClosePr {
+            branch: branch.clone(),
+            comment: format!(
+                "Folded into {target} with `stck fold`; these commits continue there."
+            ),
+        });
+    }
+    if let Some(head) = gitops::remote_branch_head(&preflight.remotes, branch)? {
+        actions.push(EditAction::DeleteRemoteBranch {
+            branch: branch.clone(),
+            expected_remote_head: head,
+        });
+    }
+    // Deleting the branch also drops its recorded stack parent from git config.
+    actions.push(EditAction::Checkout {
+        branch: parent.clone(),
+    });
+    actions.push(EditAction::DeleteBranch {
+        branch: branch.clone(),
+    });
+
+    println!("Folding {branch} into {parent}.");
+    Ok(EditState {
+        command: "fold".to_string(),
+        return_branch: parent,
+        actions,
+        completed_actions: 0,
+        failed_rebase_head: None,
+    })
+}
+
+/// Drop `branch` (the current branch by default) from the middle of its stack.
+///
+/// Its children are rebased onto its parent without its commits and their PRs
+/// retargeted, and its PR is closed. The branch itself is kept, outside the
+/// stack, unless `delete` removes it locally and on the remote.
+pub(crate) fn run_remove(
+    preflight: &env::PreflightContext,
+    branch: Option<&str>,
+    delete: bool,
+    continue_edit: bool,
+    reset_edit: bool,
+) -> ExitCode {
+    run_edit(
+        preflight,
+        "remove",
+        continue_edit,
+        reset_edit,
+        (!continue_edit && !reset_edit).then_some(move || {
+            plan_remove(
+                preflight,
+                branch.unwrap_or(&preflight.current_branch),
+                delete,
+            )
+        }),
+    )
+}
+
+fn plan_remove(
+    preflight: &env::PreflightContext,
+    branch: &str,
+    delete: bool,
+) -> Result<EditState, String> {
+    if branch == preflight.default_branch {
+        return Err(format!("cannot remove default branch {branch}"));
+    }
+    let stack = plan_edit_stack(preflight)?;
+    let pr = stack
+        .find(branch)
+        .map(|index| &stack.prs()[index])
+        .filter(|pr| pr.state != PrState::Merged)
+        .ok_or_else(|| format!("{branch} is not an open branch of this stack"))?;
+    let parent = stack::open_parent(&stack, branch)
+        .map_or(&preflight.default_branch, |parent| &parent.head_ref_name)
+        .clone();
+
+    let children = stack::open_children(&stack, branch);
+    let moves = children
+        .iter()
+        .map(|child| (child.head_ref_name.clone(), parent.clone()))
+        .collect::<Vec<_>>();
+    let plan = stack::build_reparent_plan(&stack, &preflight.default_branch, &moves)?;
+    let mut actions = Vec::new();
+    reparent_actions(preflight, &stack, plan, &mut actions)?;
+
+    if pr.state != PrState::Local {
+        actions.push(EditAction::ClosePr {
+            branch: branch.to_string(),
+            comment: "Removed from the stack with `stck remove`.".to_string(),
+        });
+    }
+    // Leaving the removed branch: prefer its parent, but stay in the stack
+    // when the parent is the default branch.
+    let return_branch = if preflight.current_branch != branch {
+        preflight.current_branch.clone()
+    } else if parent != preflight.default_branch {
+        parent.clone()
+    } else {
+        children
+            .first()
+            .map_or(&parent, |child| &child.head_ref_name)
+            .clone()
+    };
+    if delete {
+        if let Some(head) = gitops::remote_branch_head(&preflight.remotes, branch)? {
+            actions.push(EditAction::DeleteRemoteBranch {
+                branch: branch.to_string(),
+                expected_remote_head: head,
+            });
+        }
+        actions.push(EditAction::Checkout {
+            branch: return_branch.clone(),
+        });
+        actions.push(EditAction::DeleteBranch {
+            branch: branch.to_string(),
+        });
+    } else {
+        actions.push(EditAction::ForgetParent {
+            branch: branch.to_string(),
+        });
+    }
+
+    if children.is_empty() {
+        println!("Removing {branch} from the stack.");
+    } else {
+        println!(
+            "Removing {branch} from the stack; moving {} onto {parent}.",
+            children
+                .iter()
+                .map(|child| child.head_ref_name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    Ok(EditState {
+        command: "remove".to_string(),
+        return_branch,
+        actions,
+        completed_actions: 0,
+        failed_rebase_head: None,
+    })
+}
+
+/// Rename the current branch to `new_name`, keeping its PR and children intact.
+///
+/// Forges tie a PR to its head branch, so the PR is recreated for the new
+/// branch with the same title, description, draft state, reviewers,
+/// assignees, and labels. Child PRs are retargeted to the new branch before
+/// the old PR is closed and the old remote branch deleted.
+pub(crate) fn run_rename(
+    preflight: &env::PreflightContext,
+    new_name: Option<&str>,
+    continue_edit: bool,
+    reset_edit: bool,
+) -> ExitCode {
+    run_edit(
+        preflight,
+        "rename",
+        continue_edit,
+        reset_edit,
+        new_name.map(|new_name| move || plan_rename(preflight, new_name)),
+    )
+}
+
+fn plan_rename(preflight: &env::PreflightContext, new_name: &str) -> Result<EditState, String> {
+    let branch = &preflight.current_branch;
+    if branch == &preflight.default_branch {
+        return Err(format!("cannot rename default branch {branch}"));
+    }
+    let new_name = apply_branch_prefix(preflight, new_name);
+    check_new_branch(&preflight.remotes, &new_name)?;
+    let stack = plan_edit_stack(preflight)?;
+    let pr = stack
+        .find(branch)
+        .map(|index| &stack.prs()[index])
+        .filter(|pr| pr.state != PrState::Merged)
+        .ok_or_else(|| format!("{branch} is not an open branch of this stack"))?;
+
+    let mut actions = vec![EditAction::RenameBranch {
+        from: branch.clone(),
+        to: new_name.clone(),
+    }];
+    let remote_head = gitops::remote_branch_head(&preflight.remotes, branch)?;
+    if remote_head.is_some() {
+        actions.push(EditAction::Publish {
+            branch: new_name.clone(),
+        });
+    }
+    let has_pr = pr.state != PrState::Local;
+    if has_pr {
+        let content = preflight.forge.pr_content(branch)?;
+        actions.push(EditAction::CreatePr(NewPullRequest {
+            head: new_name.clone(),
+            ..content
+        }));
+    }
+    let retargets = stack::open_children(&stack, branch)
+        .into_iter()
+        .filter(|child| child.state != PrState::Local)
+        .map(|child| stack::RetargetStep {
+            branch: child.head_ref_name.clone(),
+            new_base_ref: new_name.clone(),
+        })
+        .collect::<Vec<_>>();
+    if !retargets.is_empty() && remote_head.is_none() {
+        return Err(format!(
+            "cannot retarget the PRs stacked on {branch}, which has not been pushed; run `stck submit` on {branch} first"
+        ));
+    }
+    actions.extend(retargets.into_iter().map(EditAction::Retarget));
+    if has_pr {
+        actions.push(EditAction::ClosePr {
+            branch: branch.clone(),
+            comment: format!(
+                "Renamed to `{new_name}` with `stck rename`; review continues in the PR for that branch."
+            ),
+        });
+    }
+    if let Some(head) = remote_head {
+        actions.push(EditAction::DeleteRemoteBranch {
+            branch: branch.clone(),
+            expected_remote_head: head,
+        });
+    }
+
+    println!("Renaming {branch} to {new_name}.");
+    Ok(EditState {
+        command: "rename".to_string(),
+        return_branch: new_name,
+        actions,
+        completed_actions: 0,
+        failed_rebase_head: None,
+    })
+}
+
+/// Split the current branch into consecutive stacked branches at commit boundaries.
+///
+/// Each `points` entry is `<branch>=<commit>`: the new branch takes over the
+/// commits from `<commit>` up to the next split point. The current branch
+/// keeps the commits before the first point, and its children move onto the
+/// last new branch. With no points, the commits are listed and the points are
+/// read from standard input.
+pub(crate) fn run_split(
+    preflight: &env::PreflightContext,
+    points: &[String],
+    continue_edit: bool,
+    reset_edit: bool,
+) -> ExitCode {
+    run_edit(
+        preflight,
+        "split",
+        continue_edit,
+        reset_edit,
+        (!continue_edit && !reset_edit).then_some(move || plan_split(preflight, points)),
+    )
+}
+
+fn plan_split(preflight: &env::PreflightContext, points: &[String]) -> Result<EditState, String> {
+    let branch = &preflight.current_branch;
+    if branch == &preflight.default_branch {
+        return Err(format!(
+            "cannot split default branch {branch}; checkout the branch to split and retry"
+        ));
+    }
+    let stack = plan_edit_stack(preflight)?;
+    let pr = stack
+        .find(branch)
+        .map(|index| &stack.prs()[index])
+        .filter(|pr| pr.state != PrState::Merged)
+        .ok_or_else(|| format!("{branch} is not an open branch of this stack"))?;
+    let parent = &pr.base_ref_name;
+    let commits = gitops::branch_commits(&edit_fork_point(preflight, parent, branch)?, branch)?;
+    if commits.len() < 2 {
+        return Err(format!(
+            "{branch} has {} commit(s) on top of {parent}; splitting needs at least two",
+            commits.len()
+        ));
+    }
+
+    let points = if points.is_empty() {
+        prompt_split_points(branch, &commits)?
+    } else {
+        points
+            .iter()
+            .map(|point| {
+                point
+                    .split_once('=')
+                    .map(|(name, commit)| (name.to_string(), commit.to_string()))
+                    .ok_or_else(|| {
+                        format!("split point `{point}` must look like `<branch>=<commit>`")
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    let mut splits = Vec::with_capacity(points.len());
+    for (name, commit) in points {
+        let name = apply_branch_prefix(preflight, &name);
+        check_new_branch(&preflight.remotes, &name)?;
+        let sha = gitops::resolve_ref(&commit)?;
+        let position = commits
+            .iter()
+            .position(|candidate| candidate.sha == sha)
+            .ok_or_else(|| {
+                format!("{commit} is not one of the commits {branch} adds on top of {parent}")
+            })?;
+        if position == 0 {
+            return Err(format!(
+                "{commit} is the first commit of {branch}, which keeps it; pick a later commit"
+            ));
+        }
+        if splits.iter().any(|(other, _)| *other == name) {
+            return Err(format!(
+                "branch {name} is named by more than one split point"
+            ));
+        }
+        if splits.iter().any(|(_, other)| *other == position) {
+            return Err(format!("more than one branch starts at {commit}"));
+        }
+        splits.push((name, position));
+    }
+    splits.sort_by_key(|(_, position)| *position);
+
+    let published = pr.state != PrState::Local;
+    let mut actions = Vec::new();
+    let mut prs = Vec::new();
+    for (index, (name, position)) in splits.iter().enumerate() {
+        let end = splits
+            .get(index + 1)
+            .map_or(commits.len(), |(_, next)| *next);
+        let base = index
+            .checked_sub(1)
+            .map_or(branch, |previous| &splits[previous].0);
+        actions.push(EditAction::CreateBranch {
+            branch: name.clone(),
+            start_point: commits[end - 1].sha.clone(),
+        });
+        actions.push(EditAction::RecordParent {
+            branch: name.clone(),
+            parent: base.clone(),
+        });
+        if published {
+            prs.push(new_pull_request(
+                preflight,
+                base,
+                name,
+                commits[*position].subject.clone(),
+                &PrOptions::default(),
+            )?);
+        }
+    }
+    actions.push(EditAction::ResetBranch {
+        branch: branch.clone(),
+        target: commits[splits[0].1 - 1].sha.clone(),
+    });
+    if published {
+        if let Some(head) = gitops::remote_branch_head(&preflight.remotes, branch)? {
+            actions.push(EditAction::Push {
+                branch: branch.clone(),
+                expected_remote_head: head,
+            });
+        }
+    }
+
+    let tip = &splits[splits.len() - 1].0;
+    let moves = stack::open_children(&stack, branch)
+        .into_iter()
+        .map(|child| (child.head_ref_name.clone(), tip.clone()))
+        .collect::<Vec<_>>();
+    let plan = stack::build_reparent_plan(&stack, &preflight.default_branch, &moves)?;
+    // Child PRs are retargeted onto the last new branch, so it must exist on
+    // the remote even when the split branch itself has no PR.
+    if published || !plan.retargets.is_empty() {
+        for (name, _) in &splits {
+            actions.push(EditAction::Publish {
+                branch: name.clone(),
+            });
+        }
+    }
+    reparent_actions(preflight, &stack, plan, &mut actions)?;
+    actions.extend(prs.into_iter().map(EditAction::CreatePr));
+
+    println!(
+        "Splitting {branch} into {branch}, {}.",
+        splits
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    Ok(EditState {
+        command: "split".to_string(),
+        return_branch: branch.clone(),
+        actions,
+        completed_actions: 0,
+        failed_rebase_head: None,
+    })
+}
+
+/// List the commits of `branch` and read `<number> <new-branch>` split points
+/// from standard input until an empty line.
+fn prompt_split_points(
+    branch: &str,
+    commits: &[gitops::CommitSummary],
+) -> Result<Vec<(String, String)>, String> {
+    println!("Commits on {branch}, oldest first:");
+    for (index, commit) in commits.iter().enumerate() {
+        println!("  {}. {} {}", index + 1, &commit.sha[..7], commit.subject);
+    }
+    println!(
+        "Enter `<number> <new-branch>` for each commit that starts a new branch, then an empty line:"
+    );
+
+    let mut points = Vec::new();
+    for line in std::io::stdin().lines() {
+        let line = line.map_err(|err| format!("failed to read split points: {err}"))?;
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        let (number, name) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| format!("expected `<number> <new-branch>`, found `{line}`"))?;
+        let commit = number
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| number.checked_sub(1))
+            .and_then(|index| commits.get(index))
+            .ok_or_else(|| {
+                format!(
+                    "`{number}` is not a commit number between 1 and {}",
+                    commits.len()
+                )
+            })?;
+        points.push((name.trim().to_string(), commit.sha.clone()));
+    }
+    if points.is_empty() {
+        return Err("no split points given; nothing to split".to_string());
+    }
+    Ok(points)
+}
+
+/// Check that no rebase is running, fetch, and discover the stack around the
+/// current branch for planning an edit.
+fn plan_edit_stack(preflight: &env::PreflightContext) -> Result<stack::StackTree, String> {
+    if gitops::rebase_in_progress()? {
+        return Err(
+            "rebase is in progress; run `git rebase --continue` or `git rebase --abort` before editing the stack"
+                .to_string(),
+        );
+    }
+    auto_fetch(preflight)?;
+    github::discover_stack_tree(
+        preflight.forge.as_ref(),
+        &preflight.remotes,
+        &preflight.current_branch,
+        &preflight.default_branch,
+    )
+}
+
+/// Append the actions that carry out `plan`: rebases, recorded parents,
+/// lease-protected pushes of published branches, then PR retargets.
+///
+/// Rebase ranges and push leases are pinned now, before any branch moves.
+fn reparent_actions(
+    preflight: &env::PreflightContext,
+    stack: &stack::StackTree,
+    plan: stack::ReparentPlan,
+    actions: &mut Vec<EditAction>,
+) -> Result<(), String> {
+    let mut pushes = Vec::new();
+    for mut step in plan.steps {
+        step.fork_point = Some(edit_fork_point(
+            preflight,
+            &step.old_base_ref,
+            &step.branch,
+        )?);
+        let published = stack
+            .find(&step.branch)
+            .is_some_and(|index| stack.prs()[index].state != PrState::Local);
+        if published {
+            if let Some(head) = gitops::remote_branch_head(&preflight.remotes, &step.branch)? {
+                pushes.push(EditAction::Push {
+                    branch: step.branch.clone(),
+                    expected_remote_head: head,
+                });
+            }
+        }
+        let record = EditAction::RecordParent {
+            branch: step.branch.clone(),
+            parent: step.new_base_ref.clone(),
+        };
+        actions.push(EditAction::Rebase(step));
+        actions.push(record);
+    }
+    actions.extend(pushes);
+    for retarget in plan.retargets {
+        // A PR base must exist on the forge; branches outside the stack are
+        // published by the caller.
+        let unpublished = stack.find(&retarget.new_base_ref).is_some_and(|index| {
+            stack.prs()[index].state == PrState::Local
+                && !matches!(
+                    gitops::remote_branch_exists(&preflight.remotes, &retarget.new_base_ref),
+                    Ok(true)
+                )
+        });
+        if unpublished {
+            return Err(format!(
+                "cannot retarget the PR for {} to {}, which has not been pushed; run `stck submit` on {} first",
+                retarget.branch, retarget.new_base_ref, retarget.new_base_ref
+            ));
+        }
+        actions.push(EditAction::Retarget(retarget));
+    }
+    Ok(())
+}
+
+/// Return the commit `branch` forked from `parent`, reading the default
+/// branch from its fetched remote ref and stack branches from local refs.
+fn edit_fork_point(
+    preflight: &env::PreflightContext,
+    parent: &str,
+    branch: &str,
+) -> Result<String, String> {
+    if parent == preflight.default_branch {
+        gitops::resolve_old_base_for_rebase(&preflight.remotes, parent, branch)
+    } else {
+        gitops::fork_point(&preflight.remotes, parent, branch)
+    }
+}
+
+/// Start, resume, or discard the stack edit run by `stck <command>`.
+///
+/// `plan` is `None` when only `--continue` or `--reset` was given. `--reset`
+/// only discards the saved edit; a saved edit must be continued or reset
+/// before another one is planned.
+fn run_edit(
+    preflight: &env::PreflightContext,
+    command: &str,
+    continue_edit: bool,
+    reset_edit: bool,
+    plan: Option<impl FnOnce() -> Result<EditState, String>>,
+) -> ExitCode {
+    let existing_state = match sync_state::load_edit() {
+        Ok(state) => state,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+    };
+    if let Some(state) = &existing_state {
+        if state.command != command {
+            eprintln!("error: {}", state.in_progress_error());
+            return ExitCode::from(1);
+        }
+    }
+
+    if reset_edit {
+        if let Err(message) = sync_state::clear() {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+        if existing_state.is_some() {
+            println!("Discarded saved {command} state.");
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    let state = match (existing_state, plan) {
+        (Some(state), _) if continue_edit => {
+            println!("Resuming previous {command} from saved state.");
+            state
+        }
+        (Some(state), _) => {
+            eprintln!("error: {}", state.in_progress_error());
+            return ExitCode::from(1);
+        }
+        (None, Some(plan)) if !continue_edit => {
+            let state = match plan() {
+                Ok(state) => state,
+                Err(message) => {
+                    eprintln!("error: {message}");
+                    return ExitCode::from(1);
+                }
+            };
+            if let Err(message) = sync_state::save_edit(&preflight.journal, &state) {
+                eprintln!("error: {message}");
+                return ExitCode::from(1);
+            }
+            state
+        }
+        (None, _) => {
+            eprintln!("error: no saved {command} state to continue");
+            return ExitCode::from(1);
+        }
+    };
+    execute_edit(preflight, state)
+}
+
+/// Run the remaining actions of a stack edit, saving progress after each one.
+///
+/// A failed rebase records the branch head so `--continue` can tell whether
+/// the user finished it; an aborted rebase, like any other failed action, is
+/// retried on `--continue`.
+fn execute_edit(preflight: &env::PreflightContext, mut state: EditState) -> ExitCode {
+    let command = format!("stck {}", state.command);
+    if let Some(failed_head) = state.failed_rebase_head.take() {
+        let rebase_in_progress = match gitops::rebase_in_progress() {
+            Ok(in_progress) => in_progress,
+            Err(message) => {
+                eprintln!("error: {message}");
+                return ExitCode::from(1);
+            }
+        };
+        if rebase_in_progress {
+            eprintln!("error: rebase is still in progress; run `git rebase --continue` (or `git rebase --abort`) before rerunning `{command} --continue`");
+            return ExitCode::from(1);
+        }
+        if let Some(EditAction::Rebase(step)) = state.actions.get(state.completed_actions) {
+            let current_head = match gitops::resolve_ref(&format!("refs/heads/{}", step.branch)) {
+                Ok(sha) => sha,
+                Err(message) => {
+                    eprintln!("error: {message}");
+                    return ExitCode::from(1);
+                }
+            };
+            // An unchanged head means the rebase was aborted, so it is retried.
+            if current_head != failed_head {
+                state.completed_actions += 1;
+            }
+        }
+        if let Err(message) = sync_state::save_edit(&preflight.journal, &state) {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+    }
+
+    for index in state.completed_actions..state.actions.len() {
+        if let EditAction::Rebase(step) = &state.actions[index] {
+            let branch_head = match gitops::resolve_ref(&format!("refs/heads/{}", step.branch)) {
+                Ok(sha) => sha,
+                Err(message) => {
+                    eprintln!("error: {message}");
+                    return ExitCode::from(1);
+                }
+            };
+            if let Err(message) = run_edit_rebase(preflight, step) {
+                state.failed_rebase_head = Some(branch_head);
+                if let Err(save_error) = sync_state::save_edit(&preflight.journal, &state) {
+                    eprintln!("error: {save_error}");
+                    return ExitCode::from(1);
+                }
+                eprintln!("error: {message}");
+                eprintln!();
+                eprintln!("To recover:");
+                eprintln!("  1. Resolve conflicts and run `git rebase --continue`");
+                eprintln!("     Then run `{command} --continue` to resume.");
+                eprintln!("  2. Or run `git rebase --abort` and then `{command} --reset` to discard the edit.");
+                return ExitCode::from(1);
+            }
+        } else if let Err(message) = run_edit_action(preflight, &state.actions[index]) {
+            eprintln!("error: {message}");
+            eprintln!();
+            eprintln!("Fix the error and rerun `{command} --continue` to resume.");
+            return ExitCode::from(1);
+        }
+
+        state.completed_actions = index + 1;
+        if let Err(message) = sync_state::save_edit(&preflight.journal, &state) {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+    }
+
+    println!("$ git checkout {}", state.return_branch);
+    if let Err(message) = gitops::checkout_branch(&state.return_branch) {
+        eprintln!("error: {message}");
+        return ExitCode::from(1);
+    }
+    if let Err(message) = sync_state::clear() {
+        eprintln!("error: {message}");
+        return ExitCode::from(1);
+    }
+
+    // The edit is complete; the navigation tables are a best-effort refresh
+    // that `stck nav` can redo. An edit that ends on the default branch left
+    // no stack around it to refresh.
+    if state.return_branch != preflight.default_branch {
+        let edited = env::PreflightContext {
+            current_branch: state.return_branch.clone(),
+            ..preflight.clone()
+        };
+        match refresh_stack_navigation(&edited) {
+            Ok(0) => {}
+            Ok(updated) => {
+                println!("Updated stack navigation in {updated} PR description(s).");
+            }
+            Err(message) => {
+                eprintln!("error: {message}");
+                eprintln!();
+                eprintln!("Fix the error and run `stck nav` to update the stack navigation.");
+                return ExitCode::from(1);
+            }
+        }
+    }
+    println!(
+        "{} finished; {} is checked out.",
+        command, state.return_branch
+    );
+    ExitCode::SUCCESS
+}
+
+/// Rebase `step.branch` onto its new parent from the pinned fork point.
+///
+/// Stack parents are rebased onto their local refs, which earlier actions of
+/// the edit may have moved; the default branch uses its fetched remote ref.
+fn run_edit_rebase(
+    preflight: &env::PreflightContext,
+    step: &stack::SyncStep,
+) -> Result<(), String> {
+    let onto_ref = if step.new_base_ref == preflight.default_branch {
+        gitops::resolve_onto_ref(&preflight.remotes, &step.new_base_ref)?
+    } else {
+        format!("refs/heads/{}", step.new_base_ref)
+    };
+    let old_base_sha = match &step.fork_point {
+        Some(fork_point) => fork_point.clone(),
+        None => gitops::resolve_old_base_for_rebase(
+            &preflight.remotes,
+            &step.old_base_ref,
+            &step.branch,
+        )?,
+    };
+    println!(
+        "Rebasing {} onto {} (from {})",
+        step.branch, step.new_base_ref, step.old_base_ref
+    );
+    println!(
+        "$ git rebase --onto {} {} {}",
+        onto_ref, old_base_sha, step.branch
+    );
+    gitops::rebase_onto(&onto_ref, &old_base_sha, &step.branch)
+        .map_err(|_| format!("rebase failed for branch {}", step.branch))
+}
+
+/// Run a single non-rebase edit action, echoing what it does first.
+fn run_edit_action(preflight: &env::PreflightContext, action: &EditAction) -> Result<(), String> {
+    match action {
+        EditAction::CreateBranch {
+            branch,
+            start_point,
+        } => {
+            println!("$ git branch {branch} {start_point}");
+            gitops::create_branch(branch, start_point)
+        }
+        EditAction::RecordParent { branch, parent } => {
+            let parent_ref = if *parent == preflight.default_branch {
+                gitops::resolve_onto_ref(&preflight.remotes, parent)?
+            } else {
+                format!("refs/heads/{parent}")
+            };
+            let fork_point = gitops::resolve_ref(&parent_ref)?;
+            gitops::record_local_parent(branch, parent, &fork_point)
+        }
+        EditAction::ResetBranch { branch, target } => {
+            if gitops::checked_out_branch()?.as_deref() == Some(branch.as_str()) {
+                println!("$ git reset --keep {target}");
+                gitops::reset_keep(target)
+            } else {
+                println!("$ git branch --force {branch} {target}");
+                gitops::force_branch(branch, target)
+            }
+        }
+        EditAction::RenameBranch { from, to } => {
+            // A rerun after the rename itself succeeded only repoints children.
+            if gitops::local_branch_exists(from)? {
+                println!("$ git branch -m {from} {to}");
+                gitops::rename_branch(from, to)?;
+            }
+            for child in gitops::list_local_parents()?
+                .into_iter()
+                .filter(|child| child.parent == *from)
+            {
+                let fork_point = match child.fork_point {
+                    Some(fork_point) => fork_point,
+                    None => gitops::resolve_ref(&format!("refs/heads/{to}"))?,
+                };
+                gitops::record_local_parent(&child.branch, to, &fork_point)?;
+            }
+            Ok(())
+        }
+        EditAction::ForgetParent { branch } => gitops::forget_local_parent(branch),
+        EditAction::Checkout { branch } => {
+            println!("$ git checkout {branch}");
+            gitops::checkout_branch(branch)
+        }
+        EditAction::DeleteBranch { branch } => {
+            if !gitops::local_branch_exists(branch)? {
+                return Ok(());
+            }
+            println!("$ git branch -D {branch}");
+            gitops::delete_branch(branch)
+        }
+        EditAction::Rebase(step) => run_edit_rebase(preflight, step),
+        EditAction::Publish { branch } => {
+            println!("$ git push -u {} {branch}", preflight.remotes.push);
+            gitops::push_set_upstream(&preflight.remotes, branch)
+        }
+        EditAction::Push {
+            branch,
+            expected_remote_head,
+        } => {
+            let local_head = gitops::resolve_ref(&format!("refs/heads/{branch}"))?;
+            if gitops::remote_branch_head(&preflight.remotes, branch)?.as_deref()
+                == Some(local_head.as_str())
+            {
+                println!(
+                    "Branch {branch} already matches {}; skipping.",
+                    preflight.remotes.push
+                );
+                return Ok(());
+            }
+            println!(
+                "$ git push --force-with-lease=refs/heads/{branch}:{expected_remote_head} {} {branch}",
+                preflight.remotes.push
+            );
+            gitops::push_force_with_lease(&preflight.remotes, branch, Some(expected_remote_head))
+        }
+        EditAction::DeleteRemoteBranch {
+            branch,
+            expected_remote_head,
+        } => {
+            if gitops::remote_branch_head(&preflight.remotes, branch)?.is_none() {
+                println!(
+                    "Branch {branch} is already gone from {}; skipping.",
+                    preflight.remotes.push
+                );
+                return Ok(());
+            }
+            println!(
+                "$ git push --force-with-lease=refs/heads/{branch}:{expected_remote_head} {} --delete {branch}",
+                preflight.remotes.push
+            );
+            gitops::delete_remote_branch(&preflight.remotes, branch, expected_remote_head)
+        }
+        EditAction::CreatePr(pr) => {
+            // A rerun after a failure that struck once the PR existed must not
+            // open a duplicate.
+            if preflight.forge.open_pr_exists(&pr.head)? {
+                println!("Branch {} already has an open PR.", pr.head);
+                return Ok(());
+            }
+            println!("{}", preflight.forge.describe(ForgeOperation::Create(pr)));
+            preflight.forge.create_pr(pr)?;
+            preflight.journal.record_pr_mutation(PrMutation::Create {
+                head: pr.head.clone(),
+                base: pr.base.clone(),
+            });
+            Ok(())
+        }
+        EditAction::ClosePr { branch, comment } => {
+            if !preflight.forge.open_pr_exists(branch)? {
+                println!("Branch {branch} has no open PR to close; skipping.");
+                return Ok(());
+            }
+            println!(
+                "{}",
+                preflight
+                    .forge
+                    .describe(ForgeOperation::Close { branch, comment })
+            );
+            preflight.forge.close_pr(branch, comment)?;
+            preflight.journal.record_pr_mutation(PrMutation::Close {
+                branch: branch.clone(),
+            });
+            Ok(())
+        }
+        EditAction::Retarget(retarget) => {
+            println!(
+                "Retargeting PR: {} -> {}",
+                retarget.branch, retarget.new_base_ref
+            );
+            println!(
+                "{}",
+                preflight.forge.describe(ForgeOperation::Retarget {
+                    branch: &retarget.branch,
+                    new_base: &retarget.new_base_ref,
+                })
+            );
+            preflight
+                .forge
+                .retarget_pr(&retarget.branch, &retarget.new_base_ref)?;
+            preflight.journal.record_pr_mutation(PrMutation::Retarget {
+                branch: retarget.branch.clone(),
+                new_base: retarget.new_base_ref.clone(),
+            });
+            Ok(())
+        }
+    }
+}
+
+/// Print the effective value of the setting `key`.
+pub(crate) fn run_config_get(key: &str) -> ExitCode {
+    let result = config::known_key(key).and_then(|key| {
+        config::settings().map(|settings| {
+            settings
+                .into_iter()
+                .find(|setting| setting.key == key)
+                .map(|setting| setting.value)
+                .unwrap_or_default()
+        })
+    });
+    match result {
+        Ok(value) => {
+            println!("{value}");
+            ExitCode::SUCCESS
+        }
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::from(1)
+        }
+    }
+}
+
+/// Store `value` for `key` in git config, or in the user file with `user`.
+pub(crate) fn run_config_set(key: &str, value: &str, user: bool) -> ExitCode {
+    if let Err(message) = config::set(key, value, user) {
+        eprintln!("error: {message}");
+        return ExitCode::from(1);
+    }
+    let target = if user {
+        config::user_file()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default()
+    } else {
+        format!("git config stck.{key}")
+    };
+    println!("Set {key} = {value} in {target}.");
+    ExitCode::SUCCESS
+}
+
+/// Print every setting with its effective value and source layer.
+pub(crate) fn run_config_list() -> ExitCode {
+    let settings = match config::settings() {
+        Ok(settings) => settings,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+    };
+    for setting in settings {
+        let value = if setting.value.is_empty() {
+            "<unset>"
+        } else {
+            setting.value.as_str()
+        };
+        println!("{} = {value} ({})", setting.key, setting.source);
+    }
+    ExitCode::SUCCESS
+}
+
+/// Print the most recent journaled operations, newest first.
+///
+/// With `json`, the raw [`sync_state::JournalEntry`] records are printed
+/// instead of the human-readable view.
+pub(crate) fn run_log(limit: usize, json: bool) -> ExitCode {
+    let mut entries = match sync_state::load_journal() {
+        Ok(entries) => entries,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return ExitCode::from(1);
+        }
+    };
+    entries.reverse();
+    entries.truncate(limit);
+
+    if json {
+        return match serde_json::to_string_pretty(&entries) {
+            Ok(document) => {
+                println!("{document}");
+                ExitCode::SUCCESS
+            }
+            Err(_) => {
+                eprintln!("error: failed to serialize the operation journal");
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    if entries.is_empty() {
+        println!("No stck operations recorded yet.");
+        return ExitCode::SUCCESS;
+    }
+    for (index, entry) in entries.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        let outcome = match entry.outcome {
+            sync_state::Outcome::Succeeded => "succeeded",
+            sync_state::Outcome::Failed => "failed",
+        };
+        println!(
+            "{}  stck {}  {outcome}",
+            util::format_utc(entry.timestamp),
+            entry.command
+        );
+        if let Some(plan) = &entry.plan {
+            println!("  plan: {}", describe_plan(plan));
+        }
+        for change in &entry.branches {
+            match (&change.before, &change.after) {
+                (Some(before), Some(after)) => {
+                    println!("  {}: {} -> {}", change.branch, &before[..7], &after[..7])
+                }
+                (None, Some(after)) => println!("  {}: created at {}", change.branch, &after[..7]),
+                (Some(before), None) => {
+                    println!("  {}: deleted (was {})", change.branch, &before[..7])
+                }
+                (None, None) => {}
+            }
+        }
+        for mutation in &entry.pr_mutations {
+            println!("  PR: {mutation}");
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Summarize the plan a journaled operation saved on one line.
+fn describe_plan(plan: &sync_state::LastPlanState) -> String {
+    let rebases = |steps: &[stack::SyncStep]| {
+        steps
+            .iter()
+            .map(|step| format!("rebase {} onto {}", step.branch, step.new_base_ref))
+            .collect::<Vec<_>>()
+    };
+    let parts = match plan {
+        sync_state::LastPlanState::Sync(sync) => rebases(&sync.steps),
+        sync_state::LastPlanState::Push(push) => push
+            .push_branches
+            .iter()
+            .map(|branch| format!("push {branch}"))
+            .chain(push.retargets.iter().map(|retarget| {
+                format!("retarget {} to {}", retarget.branch, retarget.new_base_ref)
+            }))
+            .collect(),
+        sync_state::LastPlanState::Land(land) => {
+            let mut parts = vec![format!("merge {} ({})", land.branch, land.merge_method)];
+            parts.extend(rebases(&land.steps));
+            parts
+        }
+        sync_state::LastPlanState::Edit(edit) => {
+            vec![format!("{} {} action(s)", edit.actions.len(), edit.command)]
+        }
+    };
+    if parts.is_empty() {
+        "nothing to do".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
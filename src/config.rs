@@ -0,0 +1,590 @@
+//! Layered `stck` settings.
+//!
+//! Each key is resolved from, in increasing precedence: built-in defaults, the
+//! user file (`$XDG_CONFIG_HOME/stck/config.toml`, falling back to
+//! `~/.config/stck/config.toml`), the repository's `.stck.toml`, and
+//! `git config stck.<key>`. The files use a flat subset of TOML: one
+//! `key = value` per line with string or boolean values and `#` comments.
+
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::gitops::{self, Remotes};
+
+/// Every setting `stck` understands, in display order.
+pub const KEYS: [&str; 9] = [
+    "remote",
+    "push-remote",
+    "trunk",
+    "draft",
+    "body-template",
+    "branch-prefix",
+    "auto-fetch",
+    "forge",
+    "gitea-url",
+];
+
+/// Forge backends the `forge` setting can select.
+pub const FORGES: [&str; 3] = ["github", "gitlab", "gitea"];
+
+/// Name of the repository-level configuration file.
+pub const REPO_FILE: &str = ".stck.toml";
+
+/// Effective settings used by commands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// Remote PRs target; the trunk branch is read from it.
+    pub remote: String,
+    /// Remote stack branches are pushed to, when it differs from `remote`
+    /// (e.g. a fork without push access to the base repository).
+    pub push_remote: Option<String>,
+    /// Trunk branch overriding the forge's default branch.
+    pub trunk: Option<String>,
+    /// Open new PRs as drafts unless `--no-draft` is given.
+    pub draft: bool,
+    /// Markdown file used as the PR description when `--body-file` is absent.
+    pub body_template: Option<PathBuf>,
+    /// Prefix prepended to branch names passed to `stck new`.
+    pub branch_prefix: String,
+    /// Fetch the remote before reading stack state in `status`, `sync`, and
+    /// parent discovery.
+    pub auto_fetch: bool,
+    /// Forge backend overriding detection from the remote host, one of [`FORGES`].
+    pub forge: Option<String>,
+    /// Base URL of a Gitea/Forgejo instance whose web host differs from the
+    /// remote's host.
+    pub gitea_url: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            remote: "origin".to_string(),
+            push_remote: None,
+            trunk: None,
+            draft: false,
+            body_template: None,
+            branch_prefix: String::new(),
+            auto_fetch: true,
+            forge: None,
+            gitea_url: None,
+        }
+    }
+}
+
+impl Config {
+    /// Return the base and push remotes remote operations should use.
+    pub fn remotes(&self) -> Remotes {
+        Remotes {
+            base: self.remote.clone(),
+            push: self
+                .push_remote
+                .clone()
+                .unwrap_or_else(|| self.remote.clone()),
+        }
+    }
+}
+
+/// Layer a setting was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Built-in default.
+    Default,
+    /// The user-level configuration file.
+    UserFile,
+    /// The repository's `.stck.toml`.
+    RepoFile,
+    /// `git config stck.<key>`.
+    GitConfig,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::UserFile => write!(f, "user config"),
+            Source::RepoFile => write!(f, "{REPO_FILE}"),
+            Source::GitConfig => write!(f, "git config"),
+        }
+    }
+}
+
+/// The effective raw value of one key and the layer it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    /// Setting name, one of [`KEYS`].
+    pub key: &'static str,
+    /// Raw value; empty when an optional setting is unset.
+    pub value: String,
+    /// Layer that supplied the value.
+    pub source: Source,
+}
+
+/// Resolve the effective configuration for the current repository.
+pub fn load() -> Result<Config, String> {
+    let repo_root = gitops::repo_root()?;
+    from_settings(&settings()?, repo_root.as_deref())
+}
+
+/// Return the effective raw value and source of every key in [`KEYS`].
+pub fn settings() -> Result<Vec<Setting>, String> {
+    let mut settings = KEYS
+        .iter()
+        .map(|&key| Setting {
+            key,
+            value: default_value(key),
+            source: Source::Default,
+        })
+        .collect::<Vec<_>>();
+
+    let mut layers = Vec::new();
+    if let Some(path) = user_file() {
+        layers.push((Source::UserFile, read_file(&path)?));
+    }
+    if let Some(root) = gitops::repo_root()? {
+        layers.push((Source::RepoFile, read_file(&root.join(REPO_FILE))?));
+        let git_values = KEYS
+            .iter()
+            .filter_map(|key| {
+                gitops::config_value(&format!("stck.{key}"))
+                    .map(|value| value.map(|value| (key.to_string(), value)))
+                    .transpose()
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        layers.push((Source::GitConfig, git_values));
+    }
+
+    for (source, values) in layers {
+        for (key, value) in values {
+            if let Some(setting) = settings.iter_mut().find(|setting| setting.key == key) {
+                setting.value = value;
+                setting.source = source;
+            }
+        }
+    }
+    Ok(settings)
+}
+
+/// Validate `value` for `key` and write it to git config, or to the user file
+/// when `user` is set.
+pub fn set(key: &str, value: &str, user: bool) -> Result<(), String> {
+    let key = known_key(key)?;
+    if is_bool_key(key) {
+        parse_bool(key, value)?;
+    }
+    if key == "forge" {
+        check_forge(value)?;
+    }
+
+    if !user {
+        return gitops::set_config_value(&format!("stck.{key}"), value);
+    }
+
+    let path = user_file()
+        .ok_or("could not locate the user config file; set XDG_CONFIG_HOME or HOME and retry")?;
+    let existing = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(format!("failed to read {}: {err}", path.display())),
+    };
+    let line = format!("{key} = {}", toml_value(key, value));
+    let mut replaced = false;
+    let mut lines = existing
+        .lines()
+        .map(|existing_line| {
+            if !replaced
+                && parse_line(existing_line)
+                    .ok()
+                    .flatten()
+                    .is_some_and(|(name, _)| name == key)
+            {
+                replaced = true;
+                line.clone()
+            } else {
+                existing_line.to_string()
+            }
+        })
+        .collect::<Vec<_>>();
+    if !replaced {
+        lines.push(line);
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|err| format!("failed to create {}: {err}", parent.display()))?;
+    }
+    fs::write(&path, format!("{}\n", lines.join("\n")))
+        .map_err(|err| format!("failed to write {}: {err}", path.display()))
+}
+
+/// Return `key` as a [`KEYS`] entry, rejecting unknown names.
+pub fn known_key(key: &str) -> Result<&'static str, String> {
+    KEYS.iter()
+        .copied()
+        .find(|known| *known == key)
+        .ok_or_else(|| {
+            format!(
+                "unknown config key `{key}`; expected one of: {}",
+                KEYS.join(", ")
+            )
+        })
+}
+
+/// Path of the user-level configuration file, if a home directory is known.
+pub fn user_file() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .filter(|value| !value.is_empty())
+                .map(|home| PathBuf::from(home).join(".config"))
+        })?;
+    Some(base.join("stck").join("config.toml"))
+}
+
+fn from_settings(settings: &[Setting], repo_root: Option<&Path>) -> Result<Config, String> {
+    let mut config = Config::default();
+    for setting in settings {
+        let value = setting.value.trim();
+        let invalid = |message: String| format!("{message} (from {})", setting.source);
+        match setting.key {
+            "remote" => {
+                if value.is_empty() {
+                    return Err(invalid("config key `remote` must not be empty".to_string()));
+                }
+                config.remote = value.to_string();
+            }
+            "push-remote" => config.push_remote = (!value.is_empty()).then(|| value.to_string()),
+            "trunk" => config.trunk = (!value.is_empty()).then(|| value.to_string()),
+            "draft" => config.draft = parse_bool(setting.key, value).map_err(invalid)?,
+            "body-template" => {
+                config.body_template = if value.is_empty() {
+                    None
+                } else {
+                    let path = PathBuf::from(value);
+                    match repo_root {
+                        Some(root) if setting.source == Source::RepoFile => {
+                            Some(inside_repo(root, &path).ok_or_else(|| {
+                                invalid(format!(
+                                    "config key `body-template` must be a relative path inside the repository, found `{value}`"
+                                ))
+                            })?)
+                        }
+                        Some(root) if path.is_relative() => Some(root.join(path)),
+                        _ => Some(path),
+                    }
+                }
+            }
+            "branch-prefix" => config.branch_prefix = value.to_string(),
+            "auto-fetch" => config.auto_fetch = parse_bool(setting.key, value).map_err(invalid)?,
+            "forge" => {
+                config.forge = if value.is_empty() {
+                    None
+                } else {
+                    check_forge(value).map_err(invalid)?;
+                    Some(value.to_string())
+                }
+            }
+            "gitea-url" => config.gitea_url = (!value.is_empty()).then(|| value.to_string()),
+            _ => {}
+        }
+    }
+    Ok(config)
+}
+
+/// Resolve `path` against `root`, returning `None` unless it is relative and
+/// stays inside the repository. Symlinks are followed when the file exists, so
+/// a checked-in link cannot point a shared `.stck.toml` at files elsewhere.
+fn inside_repo(root: &Path, path: &Path) -> Option<PathBuf> {
+    let mut depth = 0usize;
+    for component in path.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => depth = depth.checked_sub(1)?,
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    if path.starts_with("~") {
+        return None;
+    }
+
+    let joined = root.join(path);
+    match (joined.canonicalize(), root.canonicalize()) {
+        (Ok(resolved), Ok(root)) => resolved.starts_with(&root).then_some(joined),
+        _ => Some(joined),
+    }
+}
+
+fn default_value(key: &str) -> String {
+    let defaults = Config::default();
+    match key {
+        "remote" => defaults.remote,
+        "draft" => defaults.draft.to_string(),
+        "auto-fetch" => defaults.auto_fetch.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn is_bool_key(key: &str) -> bool {
+    matches!(key, "draft" | "auto-fetch")
+}
+
+fn check_forge(value: &str) -> Result<(), String> {
+    if FORGES.contains(&value) {
+        Ok(())
+    } else {
+        Err(format!(
+            "config key `forge` expects one of {}, found `{value}`",
+            FORGES.join(", ")
+        ))
+    }
+}
+
+/// Parse a boolean the way `git config --type=bool` does.
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(format!(
+            "config key `{key}` expects true or false, found `{value}`"
+        )),
+    }
+}
+
+fn toml_value(key: &str, value: &str) -> String {
+    if is_bool_key(key) {
+        parse_bool(key, value)
+            .map(|flag| flag.to_string())
+            .unwrap_or_else(|_| value.to_string())
+    } else {
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+            .replace('\t', "\\t");
+        format!("\"{escaped}\"")
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<(String, String)>, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("failed to read {}: {err}", path.display())),
+    };
+    parse(&text).map_err(|message| format!("invalid config file {}: {message}", path.display()))
+}
+
+/// Parse the flat TOML subset into `(key, value)` pairs, rejecting unknown keys.
+fn parse(text: &str) -> Result<Vec<(String, String)>, String> {
+    let mut values = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let parsed =
+            parse_line(line).map_err(|message| format!("line {}: {message}", index + 1))?;
+        if let Some((key, value)) = parsed {
+            known_key(&key).map_err(|message| format!("line {}: {message}", index + 1))?;
+            values.push((key, value));
+        }
+    }
+    Ok(values)
+}
+
+fn parse_line(line: &str) -> Result<Option<(String, String)>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    if line.starts_with('[') {
+        return Err("tables are not supported; put settings at the top level".to_string());
+    }
+    let (key, raw) = line
+        .split_once('=')
+        .ok_or_else(|| "expected `key = value`".to_string())?;
+    let key = key.trim().trim_matches('"').to_string();
+    let value = parse_value(raw.trim())?;
+    Ok(Some((key, value)))
+}
+
+fn parse_value(raw: &str) -> Result<String, String> {
+    if let Some(rest) = raw.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.chars();
+        while let Some(character) = chars.next() {
+            match character {
+                '"' => return expect_comment(chars.as_str()).map(|()| value),
+                '\\' => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('"') => value.push('"'),
+                    Some('\\') => value.push('\\'),
+                    other => {
+                        return Err(format!(
+                            "unsupported escape `\\{}`",
+                            other.map(String::from).unwrap_or_default()
+                        ))
+                    }
+                },
+                character => value.push(character),
+            }
+        }
+        return Err("unterminated string".to_string());
+    }
+    if let Some(rest) = raw.strip_prefix('\'') {
+        let (value, tail) = rest
+            .split_once('\'')
+            .ok_or_else(|| "unterminated string".to_string())?;
+        return expect_comment(tail).map(|()| value.to_string());
+    }
+
+    let value = raw.split('#').next().unwrap_or_default().trim();
+    match value {
+        "true" | "false" => Ok(value.to_string()),
+        _ => Err(format!(
+            "unsupported value `{value}`; quote strings and use true or false for flags"
+        )),
+    }
+}
+
+fn expect_comment(tail: &str) -> Result<(), String> {
+    let tail = tail.trim();
+    if tail.is_empty() || tail.starts_with('#') {
+        Ok(())
+    } else {
+        Err(format!("unexpected text after value: `{tail}`"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_settings, parse, Config, Setting, Source};
+    use std::path::{Path, PathBuf};
+
+    fn setting(key: &'static str, value: &str, source: Source) -> Setting {
+        Setting {
+            key,
+            value: value.to_string(),
+            source,
+        }
+    }
+
+    #[test]
+    fn parses_strings_booleans_and_comments() {
+        let values = parse(
+            "# stck settings\nremote = \"upstream\" # trailing\ndraft = true\nbranch-prefix = 'me/'\n\nbody-template = \"docs/pr.md\"\n",
+        )
+        .expect("config should parse");
+
+        assert_eq!(
+            values,
+            vec![
+                ("remote".to_string(), "upstream".to_string()),
+                ("draft".to_string(), "true".to_string()),
+                ("branch-prefix".to_string(), "me/".to_string()),
+                ("body-template".to_string(), "docs/pr.md".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_bare_strings() {
+        assert_eq!(
+            parse("colour = \"blue\"").expect_err("unknown key should fail"),
+            "line 1: unknown config key `colour`; expected one of: remote, push-remote, trunk, draft, body-template, branch-prefix, auto-fetch, forge, gitea-url"
+        );
+        assert_eq!(
+            parse("\nremote = upstream").expect_err("bare string should fail"),
+            "line 2: unsupported value `upstream`; quote strings and use true or false for flags"
+        );
+    }
+
+    #[test]
+    fn builds_config_from_settings_and_resolves_template_paths() {
+        let config = from_settings(
+            &[
+                setting("remote", "upstream", Source::RepoFile),
+                setting("push-remote", "fork", Source::UserFile),
+                setting("trunk", "develop", Source::GitConfig),
+                setting("draft", "yes", Source::UserFile),
+                setting("body-template", "docs/pr.md", Source::RepoFile),
+                setting("branch-prefix", "me/", Source::UserFile),
+                setting("auto-fetch", "false", Source::GitConfig),
+                setting("forge", "gitea", Source::RepoFile),
+                setting(
+                    "gitea-url",
+                    "https://git.example.org:3000",
+                    Source::UserFile,
+                ),
+            ],
+            Some(Path::new("/work/repo")),
+        )
+        .expect("config should build");
+
+        assert_eq!(
+            config,
+            Config {
+                remote: "upstream".to_string(),
+                push_remote: Some("fork".to_string()),
+                trunk: Some("develop".to_string()),
+                draft: true,
+                body_template: Some(PathBuf::from("/work/repo/docs/pr.md")),
+                branch_prefix: "me/".to_string(),
+                auto_fetch: false,
+                forge: Some("gitea".to_string()),
+                gitea_url: Some("https://git.example.org:3000".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn restricts_repository_template_paths_to_the_repository() {
+        let root = Path::new("/work/repo");
+        for value in [
+            "/etc/passwd",
+            "../outside.md",
+            "docs/../../outside.md",
+            "~/pr.md",
+        ] {
+            let error = from_settings(
+                &[setting("body-template", value, Source::RepoFile)],
+                Some(root),
+            )
+            .expect_err("escaping template path should fail");
+            assert_eq!(
+                error,
+                format!("config key `body-template` must be a relative path inside the repository, found `{value}` (from .stck.toml)")
+            );
+        }
+
+        let config = from_settings(
+            &[setting("body-template", "/home/me/pr.md", Source::UserFile)],
+            Some(root),
+        )
+        .expect("user templates may live anywhere");
+        assert_eq!(config.body_template, Some(PathBuf::from("/home/me/pr.md")));
+    }
+
+    #[test]
+    fn rejects_unknown_forges() {
+        let error = from_settings(&[setting("forge", "bitbucket", Source::RepoFile)], None)
+            .expect_err("unknown forge should fail");
+
+        assert_eq!(
+            error,
+            "config key `forge` expects one of github, gitlab, gitea, found `bitbucket` (from .stck.toml)"
+        );
+    }
+
+    #[test]
+    fn reports_the_source_of_invalid_values() {
+        let error = from_settings(&[setting("draft", "maybe", Source::GitConfig)], None)
+            .expect_err("invalid flag should fail");
+
+        assert_eq!(
+            error,
+            "config key `draft` expects true or false, found `maybe` (from git config)"
+        );
+    }
+}
@@ -1,83 +1,119 @@
 //! Repository and toolchain preflight checks required before running commands.
 
 use std::process::Command;
+use std::rc::Rc;
+
+use crate::config::{self, Config};
+use crate::forge::{self, Forge};
+use crate::gitops::Remotes;
+use crate::sync_state::Journal;
+use crate::util::ensure_command_available;
 
 /// Repository context gathered during preflight and reused by command handlers.
 #[derive(Debug, Clone)]
 pub struct PreflightContext {
-    /// The canonical GitHub repository name in `owner/name` form.
+    /// The forge backend hosting the repository's pull requests.
+    pub forge: Rc<dyn Forge>,
+    /// The canonical repository name in `owner/name` form.
     pub repository: String,
     /// The currently checked-out local branch.
     pub current_branch: String,
-    /// The repository's default branch as reported by GitHub.
+    /// The repository's default branch as reported by the forge, unless the
+    /// `trunk` setting overrides it.
+    pub default_branch: String,
+    /// Layered `stck` settings for the repository.
+    pub config: Config,
+    /// The base and push remotes from [`Config::remotes`].
+    pub remotes: Remotes,
+    /// Records the command's plan and PR changes when it is journaled.
+    pub journal: Journal,
+}
+
+/// Branch context for commands that run from local Git state alone.
+#[derive(Debug, Clone)]
+pub struct LocalContext {
+    /// The currently checked-out local branch.
+    pub current_branch: String,
+    /// The default branch recorded by the `<remote>/HEAD` ref, unless the
+    /// `trunk` setting overrides it.
     pub default_branch: String,
+    /// The configured base and push remotes.
+    pub remotes: Remotes,
+    /// Records the command's plan when it is journaled.
+    pub journal: Journal,
 }
 
 /// Validate the local repository and discover branch context needed by `stck`.
 ///
-/// This checks that `git` and `gh` are installed, GitHub authentication is
-/// available, the repository has an `origin` remote, the current HEAD is on a
-/// branch, the working tree is clean, and the default branch can be discovered.
+/// This loads the layered configuration, then checks that `git` is
+/// installed, the forge backend (by default `gh`) is installed and
+/// authenticated, the configured remotes (by default `origin`) exist, the
+/// current HEAD is on a branch, the working tree is clean, and the default
+/// branch can be discovered.
 pub fn run_preflight() -> Result<PreflightContext, String> {
     ensure_command_available("git")?;
-    ensure_command_available("gh")?;
-    ensure_gh_auth()?;
-    ensure_origin_remote()?;
+    let config = config::load()?;
+    let remotes = config.remotes();
+    let forge = forge::detect(&config)?;
+    forge.ensure_ready()?;
+    ensure_remote(&remotes.base)?;
+    if remotes.is_triangular() {
+        ensure_remote(&remotes.push)?;
+    }
     let current_branch = ensure_on_branch()?;
     ensure_clean_working_tree()?;
-    let (repository, default_branch) = discover_repository_context()?;
+    let metadata = forge.repo_metadata()?;
 
     Ok(PreflightContext {
-        repository,
+        forge,
+        repository: metadata.repository,
         current_branch,
-        default_branch,
+        default_branch: config.trunk.clone().unwrap_or(metadata.default_branch),
+        config,
+        remotes,
+        journal: Journal::default(),
     })
 }
 
-fn ensure_command_available(command: &str) -> Result<(), String> {
-    let output = Command::new(command)
-        .arg("--version")
-        .output()
-        .map_err(|_| {
-            format!("required command `{command}` was not found in PATH; install it and retry")
-        })?;
-
-    if output.status.success() {
-        Ok(())
-    } else {
-        Err(format!(
-            "failed to execute `{command} --version`; ensure `{command}` is installed and runnable"
-        ))
-    }
-}
-
-fn ensure_gh_auth() -> Result<(), String> {
-    let output = Command::new("gh")
-        .args(["auth", "status"])
-        .output()
-        .map_err(|_| {
-            "failed to run `gh auth status`; install GitHub CLI and authenticate".to_string()
-        })?;
+/// Validate the local repository without contacting GitHub or the network.
+///
+/// This checks that `git` is installed, the current HEAD is on a branch, and
+/// the working tree is clean. The default branch comes from the `trunk`
+/// setting or `<remote>/HEAD`, as recorded by `git clone` or
+/// `git remote set-head <remote> --auto`.
+pub fn run_local_preflight() -> Result<LocalContext, String> {
+    ensure_command_available("git")?;
+    let config = config::load()?;
+    let remotes = config.remotes();
+    let current_branch = ensure_on_branch()?;
+    ensure_clean_working_tree()?;
+    let default_branch = match &config.trunk {
+        Some(trunk) => trunk.clone(),
+        None => discover_local_default_branch(&remotes)?,
+    };
 
-    if output.status.success() {
-        Ok(())
-    } else {
-        Err("GitHub CLI is not authenticated; run `gh auth login` and retry".to_string())
-    }
+    Ok(LocalContext {
+        current_branch,
+        default_branch,
+        remotes,
+        journal: Journal::default(),
+    })
 }
 
-fn ensure_origin_remote() -> Result<(), String> {
+fn ensure_remote(remote: &str) -> Result<(), String> {
     let output = Command::new("git")
-        .args(["remote", "get-url", "origin"])
+        .args(["remote", "get-url", remote])
         .output()
         .map_err(|_| {
-            "failed to run `git remote get-url origin`; ensure this is a git repository".to_string()
+            format!("failed to run `git remote get-url {remote}`; ensure this is a git repository")
         })?;
 
     if output.status.success() {
         Ok(())
     } else {
-        Err("`origin` remote is missing; add it with `git remote add origin <url>`".to_string())
+        Err(format!(
+            "`{remote}` remote is missing; add it with `git remote add {remote} <url>`"
+        ))
     }
 }
 
@@ -96,7 +132,8 @@ fn ensure_on_branch() -> Result<String, String> {
     }
 }
 
-fn ensure_clean_working_tree() -> Result<(), String> {
+/// Fail unless the working tree has no staged, unstaged, or untracked changes.
+pub fn ensure_clean_working_tree() -> Result<(), String> {
     let output = Command::new("git")
         .args(["status", "--porcelain"])
         .output()
@@ -121,44 +158,25 @@ fn ensure_clean_working_tree() -> Result<(), String> {
     }
 }
 
-fn discover_repository_context() -> Result<(String, String), String> {
-    let output = Command::new("gh")
+fn discover_local_default_branch(remotes: &Remotes) -> Result<String, String> {
+    let remote = &remotes.base;
+    let output = Command::new("git")
         .args([
-            "repo",
-            "view",
-            "--json",
-            "nameWithOwner,defaultBranchRef",
-            "--jq",
-            r#"[.nameWithOwner, .defaultBranchRef.name] | @tsv"#,
+            "symbolic-ref",
+            "--quiet",
+            "--short",
+            &remotes.base_ref("HEAD"),
         ])
         .output()
-        .map_err(|_| "failed to discover repository default branch from GitHub".to_string())?;
-
-    if !output.status.success() {
-        return Err(
-            "could not discover default branch via GitHub CLI; ensure `origin` points to GitHub and `gh auth status` succeeds"
-                .to_string(),
-        );
-    }
-
-    let metadata = String::from_utf8_lossy(&output.stdout);
-    let Some((repository, default_branch)) = metadata.trim().split_once('\t') else {
-        return Err(
-            "repository metadata lookup returned an invalid result; verify repository metadata on GitHub"
-                .to_string(),
-        );
-    };
-    if default_branch.is_empty() {
-        Err(
-            "default branch lookup returned empty result; verify repository metadata on GitHub"
-                .to_string(),
-        )
-    } else if repository.is_empty() {
-        Err(
-            "repository identity lookup returned empty result; verify repository metadata on GitHub"
-                .to_string(),
-        )
-    } else {
-        Ok((repository.to_string(), default_branch.to_string()))
+        .map_err(|_| format!("failed to read `{remote}/HEAD`; ensure this is a git repository"))?;
+
+    let target = String::from_utf8_lossy(&output.stdout);
+    match target.trim().strip_prefix(&format!("{remote}/")) {
+        Some(default_branch) if output.status.success() && !default_branch.is_empty() => {
+            Ok(default_branch.to_string())
+        }
+        _ => Err(format!(
+            "could not determine the default branch offline; run `git remote set-head {remote} --auto` once while online"
+        )),
     }
 }
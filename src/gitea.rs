@@ -0,0 +1,794 @@
+//! Gitea/Forgejo pull request forge backend driven by the REST API.
+//!
+//! Requests go through `curl` so no HTTP client is linked into `stck`. The
+//! access token comes from `GITEA_TOKEN` (or `FORGEJO_TOKEN`) and is passed to
+//! `curl` on stdin so it never shows up in the process list.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::forge::{self, Forge, ForgeOperation, NewPullRequest, RepoMetadata};
+use crate::github::{MergeMethod, PrState, PullRequest};
+use crate::util::{ensure_command_available, shell_word};
+
+/// Environment variables checked, in order, for the API access token.
+pub const TOKEN_ENVS: [&str; 2] = ["GITEA_TOKEN", "FORGEJO_TOKEN"];
+
+const PAGE_LIMIT: usize = 50;
+
+/// Most pages of closed PRs, newest first, searched for a branch without an
+/// open PR. Stack parents are usually merged recently, so this bounds lookups
+/// for branches that never had a PR without missing the merges `sync` needs.
+const CLOSED_PAGES: usize = 4;
+
+/// Title prefix Gitea and Forgejo treat as a work-in-progress (draft) marker.
+const DRAFT_PREFIX: &str = "WIP: ";
+
+/// Forge backend that talks to a Gitea-compatible `/api/v1` endpoint.
+#[derive(Debug)]
+pub struct GiteaApi {
+    api_url: String,
+    repository: String,
+    /// `owner/name` of the fork PR heads live on, or `None` for the repository itself.
+    fork: Option<String>,
+    /// Open PRs of the repository, fetched once and kept current by mutations.
+    open: RefCell<Option<Vec<PullRequest>>>,
+    /// Closed and merged PRs read so far, most recently updated first.
+    closed: RefCell<ClosedPulls>,
+}
+
+#[derive(Debug, Default)]
+struct ClosedPulls {
+    pulls: Vec<PullRequest>,
+    pages: usize,
+    exhausted: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaPull {
+    number: u64,
+    state: String,
+    #[serde(default)]
+    merged: bool,
+    head: GiteaBranch,
+    base: GiteaBranch,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaBranch {
+    #[serde(rename = "ref")]
+    ref_name: String,
+    #[serde(default)]
+    repo: Option<GiteaRepoRef>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+struct GiteaRepoRef {
+    full_name: String,
+}
+
+/// The fields of a single PR that `stck rename` copies to a new PR.
+#[derive(Debug, Deserialize)]
+struct GiteaPullContent {
+    title: String,
+    #[serde(default)]
+    body: Option<String>,
+    base: GiteaBranch,
+    #[serde(default)]
+    requested_reviewers: Option<Vec<GiteaUser>>,
+    #[serde(default)]
+    assignees: Option<Vec<GiteaUser>>,
+    #[serde(default)]
+    labels: Vec<GiteaLabel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaUser {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaLabel {
+    id: u64,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaRepo {
+    full_name: String,
+    #[serde(default)]
+    default_branch: String,
+    #[serde(default = "allowed")]
+    allow_merge_commits: bool,
+    #[serde(default = "allowed")]
+    allow_squash_merge: bool,
+    #[serde(default = "allowed")]
+    allow_rebase: bool,
+}
+
+fn allowed() -> bool {
+    true
+}
+
+impl GiteaApi {
+    /// Build a backend for `repository` (`owner/name`) on the instance at
+    /// `base_url`, with PR heads on `fork` when stack branches live on a fork.
+    pub fn new(base_url: &str, repository: String, fork: Option<String>) -> Self {
+        Self {
+            api_url: format!("{}/api/v1", base_url.trim_end_matches('/')),
+            repository,
+            fork,
+            open: RefCell::new(None),
+            closed: RefCell::new(ClosedPulls::default()),
+        }
+    }
+
+    /// Return the `head` the API expects for `branch`: `owner:branch` for a fork.
+    fn head(&self, branch: &str) -> String {
+        match self.fork.as_deref().and_then(|fork| fork.split_once('/')) {
+            Some((owner, _)) => format!("{owner}:{branch}"),
+            None => branch.to_string(),
+        }
+    }
+
+    fn request(&self, method: &str, path: &str, body: Option<&Value>) -> Result<Vec<u8>, String> {
+        let url = format!("{}/{path}", self.api_url);
+        let mut config = vec![
+            format!("url = \"{}\"", curl_quote(&url)),
+            format!("request = \"{method}\""),
+            "silent".to_string(),
+            "show-error".to_string(),
+            "header = \"Accept: application/json\"".to_string(),
+            "write-out = \"\\n%{http_code}\"".to_string(),
+        ];
+        if let Some(token) = token() {
+            config.push(format!(
+                "header = \"Authorization: token {}\"",
+                curl_quote(&token)
+            ));
+        }
+        if let Some(body) = body {
+            config.push("header = \"Content-Type: application/json\"".to_string());
+            config.push(format!(
+                "data-binary = \"{}\"",
+                curl_quote(&body.to_string())
+            ));
+        }
+
+        let mut child = Command::new("curl")
+            .args(["--config", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|_| "failed to run `curl`; ensure curl is installed".to_string())?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(config.join("\n").as_bytes())
+                .map_err(|_| "failed to pass the request to `curl`".to_string())?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|_| "failed to run `curl`; ensure curl is installed".to_string())?;
+        if !output.status.success() {
+            return Err(crate::util::with_stderr(
+                &format!("request to {url} failed"),
+                &output.stderr,
+            ));
+        }
+
+        let (response, status) = split_status(&output.stdout)
+            .ok_or_else(|| format!("request to {url} returned an unreadable response"))?;
+        if (200..300).contains(&status) {
+            Ok(response.to_vec())
+        } else {
+            Err(api_error(method, path, status, response))
+        }
+    }
+
+    fn open_pulls(&self) -> Result<Vec<PullRequest>, String> {
+        if let Some(pulls) = self.open.borrow().as_ref() {
+            return Ok(pulls.clone());
+        }
+
+        let mut pulls = Vec::new();
+        for page in 1.. {
+            let (batch, count) = self.pulls_page("open", page)?;
+            pulls.extend(batch);
+            if count < PAGE_LIMIT {
+                break;
+            }
+        }
+        *self.open.borrow_mut() = Some(pulls.clone());
+        Ok(pulls)
+    }
+
+    /// Return the most recently updated closed or merged PR headed at `branch`,
+    /// reading further pages of closed PRs only until one turns up.
+    fn closed_pr_for_head(&self, branch: &str) -> Result<Option<PullRequest>, String> {
+        loop {
+            let page = {
+                let closed = self.closed.borrow();
+                if let Some(pr) = closed.pulls.iter().find(|pr| pr.head_ref_name == branch) {
+                    return Ok(Some(pr.clone()));
+                }
+                if closed.exhausted {
+                    return Ok(None);
+                }
+                closed.pages + 1
+            };
+            let (batch, count) = self.pulls_page("closed", page)?;
+            let mut closed = self.closed.borrow_mut();
+            closed.pulls.extend(batch);
+            closed.pages = page;
+            closed.exhausted = count < PAGE_LIMIT || page >= CLOSED_PAGES;
+        }
+    }
+
+    fn pulls_page(&self, state: &str, page: usize) -> Result<(Vec<PullRequest>, usize), String> {
+        let stdout = self.request(
+            "GET",
+            &format!(
+                "repos/{}/pulls?state={state}&sort=recentupdate&limit={PAGE_LIMIT}&page={page}",
+                self.repository
+            ),
+            None,
+        )?;
+        parse_pulls(&stdout, self.fork.as_deref())
+    }
+
+    /// Apply a successful change to PR `number` to the cached open PRs, moving
+    /// it out of them once it is no longer open.
+    fn update_cached(&self, number: u64, change: impl FnOnce(&mut PullRequest)) {
+        let mut open = self.open.borrow_mut();
+        let Some(pulls) = open.as_mut() else {
+            return;
+        };
+        let Some(index) = pulls.iter().position(|pr| pr.number == number) else {
+            return;
+        };
+        change(&mut pulls[index]);
+        if pulls[index].state != PrState::Open {
+            let pr = pulls.remove(index);
+            // Later closed pages shift by one; re-reading them is cheaper than
+            // guessing where the PR now sits.
+            *self.closed.borrow_mut() = ClosedPulls {
+                pulls: vec![pr],
+                ..ClosedPulls::default()
+            };
+        }
+    }
+
+    fn repo(&self) -> Result<GiteaRepo, String> {
+        let stdout = self.request("GET", &format!("repos/{}", self.repository), None)?;
+        serde_json::from_slice(&stdout).map_err(|_| {
+            "repository metadata lookup returned an invalid result; verify repository metadata on the forge"
+                .to_string()
+        })
+    }
+
+    /// Map label names onto the repository's label IDs, which the API expects.
+    fn label_ids(&self, names: &[String]) -> Result<Vec<u64>, String> {
+        if names.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut labels = Vec::new();
+        for page in 1.. {
+            let stdout = self.request(
+                "GET",
+                &format!(
+                    "repos/{}/labels?limit={PAGE_LIMIT}&page={page}",
+                    self.repository
+                ),
+                None,
+            )?;
+            let batch = serde_json::from_slice::<Vec<GiteaLabel>>(&stdout)
+                .map_err(|_| "label lookup returned an invalid result".to_string())?;
+            let count = batch.len();
+            labels.extend(batch);
+            if count < PAGE_LIMIT {
+                break;
+            }
+        }
+
+        names
+            .iter()
+            .map(|name| {
+                labels
+                    .iter()
+                    .find(|label| &label.name == name)
+                    .map(|label| label.id)
+                    .ok_or_else(|| format!("label `{name}` does not exist in {}", self.repository))
+            })
+            .collect()
+    }
+
+    fn open_pr_for_head(&self, branch: &str) -> Result<PullRequest, String> {
+        self.find_pr_for_head(branch)?
+            .filter(|pr| pr.state == PrState::Open)
+            .ok_or_else(|| format!("no open PR found for branch {branch}"))
+    }
+
+    fn mutate(&self, method: &str, path: &str, body: Value) -> Result<(), String> {
+        self.request(
+            method,
+            &format!("repos/{}/{path}", self.repository),
+            Some(&body),
+        )
+        .map(|_| ())
+    }
+}
+
+impl Forge for GiteaApi {
+    fn ensure_ready(&self) -> Result<(), String> {
+        ensure_command_available("curl")?;
+        if token().is_none() {
+            return Err(format!(
+                "no Gitea/Forgejo access token found; set {} and retry",
+                TOKEN_ENVS[0]
+            ));
+        }
+        Ok(())
+    }
+
+    fn repo_metadata(&self) -> Result<RepoMetadata, String> {
+        let repo = self.repo()?;
+        if repo.default_branch.is_empty() {
+            return Err(
+                "default branch lookup returned empty result; verify repository metadata on the forge"
+                    .to_string(),
+            );
+        }
+        Ok(RepoMetadata {
+            repository: repo.full_name,
+            default_branch: repo.default_branch,
+        })
+    }
+
+    fn find_pr_for_head(&self, branch: &str) -> Result<Option<PullRequest>, String> {
+        match forge::select_pr_for_head(self.open_pulls()?, branch)? {
+            Some(pr) => Ok(Some(pr)),
+            None => self.closed_pr_for_head(branch),
+        }
+    }
+
+    /// Only open PRs are listed: a PR merged into a stack branch rather than
+    /// the trunk has already left the stack.
+    fn list_children(&self, base: &str) -> Result<Vec<PullRequest>, String> {
+        Ok(self
+            .open_pulls()?
+            .into_iter()
+            .filter(|pr| pr.base_ref_name == base)
+            .collect())
+    }
+
+    fn create_pr(&self, pr: &NewPullRequest) -> Result<(), String> {
+        let failed = |err: String| format!("failed to create PR for branch {}: {err}", pr.head);
+        let labels = self.label_ids(&pr.labels).map_err(failed)?;
+        let title = if pr.draft {
+            format!("{DRAFT_PREFIX}{}", pr.title)
+        } else {
+            pr.title.clone()
+        };
+        let mut body = json!({ "base": pr.base, "head": self.head(&pr.head), "title": title, "body": pr.body });
+        if !pr.assignees.is_empty() {
+            body["assignees"] = json!(pr.assignees);
+        }
+        if !labels.is_empty() {
+            body["labels"] = json!(labels);
+        }
+
+        let stdout = self
+            .request(
+                "POST",
+                &format!("repos/{}/pulls", self.repository),
+                Some(&body),
+            )
+            .map_err(failed)?;
+        let created = serde_json::from_slice::<GiteaPull>(&stdout)
+            .ok()
+            .and_then(|pull| stack_pull(pull, self.fork.as_deref()));
+        let mut open = self.open.borrow_mut();
+        match created {
+            Some(created) => {
+                if let Some(open) = open.as_mut() {
+                    open.push(created);
+                }
+            }
+            None => *open = None,
+        }
+        drop(open);
+        if pr.reviewers.is_empty() {
+            return Ok(());
+        }
+
+        let number = serde_json::from_slice::<Value>(&stdout)
+            .ok()
+            .and_then(|pull| pull.get("number").and_then(Value::as_u64))
+            .ok_or_else(|| failed("the created PR has no number".to_string()))?;
+        self.mutate(
+            "POST",
+            &format!("pulls/{number}/requested_reviewers"),
+            json!({ "reviewers": pr.reviewers }),
+        )
+        .map_err(|err| {
+            format!(
+                "created PR for branch {} but failed to request reviewers: {err}",
+                pr.head
+            )
+        })
+    }
+
+    fn retarget_pr(&self, branch: &str, new_base: &str) -> Result<(), String> {
+        let pr = self.fetch_pr_for_head(branch)?;
+        self.mutate(
+            "PATCH",
+            &format!("pulls/{}", pr.number),
+            json!({ "base": new_base }),
+        )
+        .map_err(|err| {
+            format!(
+                "failed to retarget PR for branch {branch} to {new_base}: {err}; rerun `stck push`"
+            )
+        })?;
+        self.update_cached(pr.number, |pr| pr.base_ref_name = new_base.to_string());
+        Ok(())
+    }
+
+    fn pr_body(&self, branch: &str) -> Result<String, String> {
+        let pr = self.open_pr_for_head(branch)?;
+        let stdout = self.request(
+            "GET",
+            &format!("repos/{}/pulls/{}", self.repository, pr.number),
+            None,
+        )?;
+        let pull = serde_json::from_slice::<Value>(&stdout)
+            .map_err(|_| format!("failed to parse PR body for branch {branch}"))?;
+        Ok(pull
+            .get("body")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    fn pr_content(&self, branch: &str) -> Result<NewPullRequest, String> {
+        let pr = self.open_pr_for_head(branch)?;
+        let stdout = self.request(
+            "GET",
+            &format!("repos/{}/pulls/{}", self.repository, pr.number),
+            None,
+        )?;
+        parse_pull_content(&stdout, branch)
+    }
+
+    fn update_pr_body(&self, branch: &str, body: &str) -> Result<(), String> {
+        let pr = self.open_pr_for_head(branch)?;
+        self.mutate(
+            "PATCH",
+            &format!("pulls/{}", pr.number),
+            json!({ "body": body }),
+        )
+        .map_err(|err| format!("failed to update PR body for branch {branch}: {err}"))
+    }
+
+    /// PR comments live on the issue that shares the PR number.
+    fn close_pr(&self, branch: &str, comment: &str) -> Result<(), String> {
+        let pr = self.open_pr_for_head(branch)?;
+        self.mutate(
+            "POST",
+            &format!("issues/{}/comments", pr.number),
+            json!({ "body": comment }),
+        )
+        .and_then(|()| {
+            self.mutate(
+                "PATCH",
+                &format!("pulls/{}", pr.number),
+                json!({ "state": "closed" }),
+            )
+        })
+        .map_err(|err| format!("failed to close PR for branch {branch}: {err}"))?;
+        self.update_cached(pr.number, |pr| pr.state = PrState::Closed);
+        Ok(())
+    }
+
+    fn allowed_merge_methods(&self) -> Result<Vec<MergeMethod>, String> {
+        let repo = self.repo()?;
+        Ok([
+            (repo.allow_merge_commits, MergeMethod::Merge),
+            (repo.allow_squash_merge, MergeMethod::Squash),
+            (repo.allow_rebase, MergeMethod::Rebase),
+        ]
+        .into_iter()
+        .filter_map(|(allowed, method)| allowed.then_some(method))
+        .collect())
+    }
+
+    fn merge_pr(&self, branch: &str, method: MergeMethod, head_sha: &str) -> Result<(), String> {
+        let pr = self.fetch_pr_for_head(branch)?;
+        self.mutate(
+            "POST",
+            &format!("pulls/{}/merge", pr.number),
+            json!({ "Do": merge_style(method), "head_commit_id": head_sha }),
+        )
+        .map_err(|err| {
+            format!("failed to merge PR for branch {branch}: {err}; rerun `stck land`")
+        })?;
+        self.update_cached(pr.number, |pr| pr.state = PrState::Merged);
+        Ok(())
+    }
+
+    fn describe(&self, operation: ForgeOperation<'_>) -> String {
+        let pulls = format!("{}/repos/{}/pulls", self.api_url, self.repository);
+        match operation {
+            ForgeOperation::Create(pr) => {
+                let title = if pr.draft {
+                    format!("{DRAFT_PREFIX}{}", pr.title)
+                } else {
+                    pr.title.clone()
+                };
+                let mut line = format!(
+                    "$ POST {pulls} base={} head={} title={}",
+                    pr.base,
+                    self.head(&pr.head),
+                    shell_word(&title)
+                );
+                for (field, values) in [
+                    ("assignees", &pr.assignees),
+                    ("labels", &pr.labels),
+                    ("reviewers", &pr.reviewers),
+                ] {
+                    if !values.is_empty() {
+                        line.push_str(&format!(" {field}={}", shell_word(&values.join(","))));
+                    }
+                }
+                line
+            }
+            ForgeOperation::Retarget { branch, new_base } => {
+                format!("$ PATCH {pulls}/<PR for {branch}> base={new_base}")
+            }
+            ForgeOperation::Merge {
+                branch,
+                method,
+                head_sha,
+            } => format!(
+                "$ POST {pulls}/<PR for {branch}>/merge Do={} head_commit_id={head_sha}",
+                merge_style(method)
+            ),
+            ForgeOperation::UpdateBody { branch } => {
+                format!("$ PATCH {pulls}/<PR for {branch}> body=<stack navigation>")
+            }
+            ForgeOperation::Close { branch, comment } => format!(
+                "$ POST {}/repos/{}/issues/<PR for {branch}>/comments body={}\n$ PATCH {pulls}/<PR for {branch}> state=closed",
+                self.api_url,
+                self.repository,
+                shell_word(comment)
+            ),
+        }
+    }
+}
+
+fn token() -> Option<String> {
+    TOKEN_ENVS
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+}
+
+fn merge_style(method: MergeMethod) -> &'static str {
+    match method {
+        MergeMethod::Merge => "merge",
+        MergeMethod::Squash => "squash",
+        MergeMethod::Rebase => "rebase",
+    }
+}
+
+/// Escape `value` for a double-quoted `curl --config` string.
+fn curl_quote(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t")
+}
+
+/// Split curl's `write-out` status line off the response body.
+fn split_status(stdout: &[u8]) -> Option<(&[u8], u16)> {
+    let newline = stdout.iter().rposition(|byte| *byte == b'\n')?;
+    let status = std::str::from_utf8(&stdout[newline + 1..])
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some((&stdout[..newline], status))
+}
+
+fn api_error(method: &str, path: &str, status: u16, body: &[u8]) -> String {
+    let message = serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|value| value.get("message")?.as_str().map(str::to_string))
+        .filter(|message| !message.is_empty());
+    match (status, message) {
+        (401 | 403, _) => format!(
+            "{method} {path} was rejected with HTTP {status}; check that {} holds a valid access token",
+            TOKEN_ENVS[0]
+        ),
+        (_, Some(message)) => format!("{method} {path} failed with HTTP {status}: {message}"),
+        (_, None) => format!("{method} {path} failed with HTTP {status}"),
+    }
+}
+
+/// Parse one page of PRs, returning the PRs headed on the stack's repository
+/// (`fork`, or the base repository itself) and the raw page size.
+fn parse_pulls(bytes: &[u8], fork: Option<&str>) -> Result<(Vec<PullRequest>, usize), String> {
+    let pulls = serde_json::from_slice::<Vec<GiteaPull>>(bytes)
+        .map_err(|_| "failed to parse pull request metadata from the forge API".to_string())?;
+    let count = pulls.len();
+    let pulls = pulls
+        .into_iter()
+        .filter_map(|pr| stack_pull(pr, fork))
+        .collect();
+    Ok((pulls, count))
+}
+
+/// Map `pr` onto a [`PullRequest`], or `None` when its head is not on the
+/// stack's repository: PRs from other forks (or whose fork was deleted) are
+/// not part of the stack.
+fn stack_pull(pr: GiteaPull, fork: Option<&str>) -> Option<PullRequest> {
+    let head_repo = pr.head.repo.as_ref().map(|repo| repo.full_name.as_str());
+    let on_stack_repository = match fork {
+        Some(fork) => head_repo.is_some_and(|repo| repo.eq_ignore_ascii_case(fork)),
+        None => head_repo.is_some() && pr.head.repo == pr.base.repo,
+    };
+    on_stack_repository.then_some(PullRequest {
+        number: pr.number,
+        head_ref_name: pr.head.ref_name,
+        base_ref_name: pr.base.ref_name,
+        state: match (pr.state.as_str(), pr.merged) {
+            (_, true) => PrState::Merged,
+            ("closed", false) => PrState::Closed,
+            _ => PrState::Open,
+        },
+    })
+}
+
+fn parse_pull_content(bytes: &[u8], branch: &str) -> Result<NewPullRequest, String> {
+    let pull = serde_json::from_slice::<GiteaPullContent>(bytes)
+        .map_err(|_| format!("failed to parse PR for branch {branch}"))?;
+    let logins = |users: Option<Vec<GiteaUser>>| {
+        users
+            .unwrap_or_default()
+            .into_iter()
+            .map(|user| user.login)
+            .collect()
+    };
+    let (title, draft) = match pull.title.strip_prefix(DRAFT_PREFIX) {
+        Some(title) => (title.to_string(), true),
+        None => (pull.title, false),
+    };
+    Ok(NewPullRequest {
+        base: pull.base.ref_name,
+        head: branch.to_string(),
+        title,
+        body: pull.body.unwrap_or_default(),
+        draft,
+        reviewers: logins(pull.requested_reviewers),
+        assignees: logins(pull.assignees),
+        labels: pull.labels.into_iter().map(|label| label.name).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{api_error, curl_quote, parse_pull_content, parse_pulls, split_status};
+    use crate::github::{PrState, PullRequest};
+
+    #[test]
+    fn parse_pull_content_strips_the_draft_prefix() {
+        let content = parse_pull_content(
+            br#"{"title":"WIP: Add login","body":"Adds the login form.","base":{"ref":"feature-a"},"requested_reviewers":[{"login":"alice"}],"assignees":null,"labels":[{"id":3,"name":"stacked"}]}"#,
+            "feature-b",
+        )
+        .expect("pull should parse");
+
+        assert_eq!(content.base, "feature-a");
+        assert_eq!(content.head, "feature-b");
+        assert_eq!(content.title, "Add login");
+        assert!(content.draft);
+        assert_eq!(content.body, "Adds the login form.");
+        assert_eq!(content.reviewers, vec!["alice"]);
+        assert!(content.assignees.is_empty());
+        assert_eq!(content.labels, vec!["stacked"]);
+    }
+
+    #[test]
+    fn parse_pulls_maps_merged_flag_and_skips_forks() {
+        let (pulls, count) = parse_pulls(
+            br#"[
+                {"number":1,"state":"closed","merged":true,"head":{"ref":"feature-a","repo":{"full_name":"o/r"}},"base":{"ref":"main","repo":{"full_name":"o/r"}}},
+                {"number":2,"state":"open","merged":false,"head":{"ref":"feature-b","repo":{"full_name":"o/r"}},"base":{"ref":"feature-a","repo":{"full_name":"o/r"}}},
+                {"number":3,"state":"closed","merged":false,"head":{"ref":"feature-c","repo":{"full_name":"o/r"}},"base":{"ref":"main","repo":{"full_name":"o/r"}}},
+                {"number":4,"state":"open","merged":false,"head":{"ref":"feature-b","repo":{"full_name":"fork/r"}},"base":{"ref":"main","repo":{"full_name":"o/r"}}},
+                {"number":5,"state":"open","merged":false,"head":{"ref":"gone","repo":null},"base":{"ref":"main","repo":{"full_name":"o/r"}}}
+            ]"#,
+            None,
+        )
+        .expect("pulls should parse");
+
+        assert_eq!(count, 5);
+        assert_eq!(
+            pulls,
+            vec![
+                PullRequest {
+                    number: 1,
+                    head_ref_name: "feature-a".to_string(),
+                    base_ref_name: "main".to_string(),
+                    state: PrState::Merged,
+                },
+                PullRequest {
+                    number: 2,
+                    head_ref_name: "feature-b".to_string(),
+                    base_ref_name: "feature-a".to_string(),
+                    state: PrState::Open,
+                },
+                PullRequest {
+                    number: 3,
+                    head_ref_name: "feature-c".to_string(),
+                    base_ref_name: "main".to_string(),
+                    state: PrState::Closed,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_pulls_keeps_only_the_configured_fork_heads() {
+        let (pulls, _) = parse_pulls(
+            br#"[
+                {"number":1,"state":"open","merged":false,"head":{"ref":"feature-a","repo":{"full_name":"o/r"}},"base":{"ref":"main","repo":{"full_name":"o/r"}}},
+                {"number":2,"state":"open","merged":false,"head":{"ref":"feature-a","repo":{"full_name":"Me/r"}},"base":{"ref":"main","repo":{"full_name":"o/r"}}},
+                {"number":3,"state":"open","merged":false,"head":{"ref":"feature-a","repo":{"full_name":"other/r"}},"base":{"ref":"main","repo":{"full_name":"o/r"}}}
+            ]"#,
+            Some("me/r"),
+        )
+        .expect("pulls should parse");
+
+        assert_eq!(
+            pulls.iter().map(|pr| pr.number).collect::<Vec<_>>(),
+            vec![2]
+        );
+    }
+
+    #[test]
+    fn split_status_separates_body_and_http_code() {
+        assert_eq!(
+            split_status(b"{\"ok\":true}\n201"),
+            Some((&b"{\"ok\":true}"[..], 201))
+        );
+        assert_eq!(split_status(b"\n204"), Some((&b""[..], 204)));
+        assert_eq!(split_status(b"no status"), None);
+    }
+
+    #[test]
+    fn api_error_prefers_the_forge_message() {
+        assert_eq!(
+            api_error(
+                "PATCH",
+                "repos/o/r/pulls/2",
+                422,
+                br#"{"message":"base not found"}"#
+            ),
+            "PATCH repos/o/r/pulls/2 failed with HTTP 422: base not found"
+        );
+        assert!(api_error("GET", "repos/o/r", 401, b"").contains("GITEA_TOKEN"));
+    }
+
+    #[test]
+    fn curl_quote_escapes_quotes_and_newlines() {
+        assert_eq!(curl_quote("a \"b\"\n\\c"), "a \\\"b\\\"\\n\\\\c");
+    }
+}
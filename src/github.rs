@@ -1,9 +1,14 @@
-//! GitHub pull request discovery and mutation helpers backed by the `gh` CLI.
+//! Pull request metadata, stack discovery, and the `gh` CLI forge backend.
 
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::process::Command;
 
-use crate::util::with_stderr;
+use crate::forge::{self, Forge, ForgeOperation, NewPullRequest, RepoMetadata};
+use crate::gitops::{self, LocalParent, Remotes};
+use crate::stack::StackTree;
+use crate::util::{ensure_command_available, shell_word, with_stderr};
 
 /// The GitHub state of a pull request as returned by `gh`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
@@ -15,6 +20,9 @@ pub enum PrState {
     Merged,
     /// The pull request is closed without being merged.
     Closed,
+    /// The branch has no pull request yet; its parent comes from metadata
+    /// recorded locally by `stck`.
+    Local,
 }
 
 impl std::fmt::Display for PrState {
@@ -23,11 +31,34 @@ impl std::fmt::Display for PrState {
             PrState::Open => write!(f, "OPEN"),
             PrState::Merged => write!(f, "MERGED"),
             PrState::Closed => write!(f, "CLOSED"),
+            PrState::Local => write!(f, "LOCAL"),
         }
     }
 }
 
-/// Minimal pull request metadata needed to reason about a linear stack.
+/// A merge strategy accepted by `gh pr merge`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeMethod {
+    /// Create a merge commit.
+    Merge,
+    /// Squash the PR commits into a single commit.
+    Squash,
+    /// Rebase the PR commits onto the base branch.
+    Rebase,
+}
+
+impl std::fmt::Display for MergeMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeMethod::Merge => write!(f, "merge"),
+            MergeMethod::Squash => write!(f, "squash"),
+            MergeMethod::Rebase => write!(f, "rebase"),
+        }
+    }
+}
+
+/// Minimal pull request metadata needed to reason about a stack.
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct PullRequest {
     /// The GitHub pull request number.
@@ -48,9 +79,46 @@ struct OpenPullRequestHead {
     head_ref_name: String,
     #[serde(rename = "isCrossRepository", default)]
     is_cross_repository: bool,
+    #[serde(rename = "headRepositoryOwner", default)]
+    head_repository_owner: Option<RepositoryOwner>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RepositoryOwner {
+    login: String,
 }
 
+/// The fields of `gh pr view --json` that `stck rename` copies to a new PR.
 #[derive(Debug, Deserialize)]
+struct PullRequestContent {
+    #[serde(rename = "baseRefName")]
+    base_ref_name: String,
+    title: String,
+    #[serde(default)]
+    body: String,
+    #[serde(rename = "isDraft", default)]
+    is_draft: bool,
+    /// Team review requests carry no `login` and are not copied.
+    #[serde(rename = "reviewRequests", default)]
+    review_requests: Vec<UserLogin>,
+    #[serde(default)]
+    assignees: Vec<UserLogin>,
+    #[serde(default)]
+    labels: Vec<LabelName>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserLogin {
+    #[serde(default)]
+    login: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LabelName {
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
 struct PullRequestCandidate {
     number: u64,
     #[serde(rename = "headRefName")]
@@ -60,6 +128,36 @@ struct PullRequestCandidate {
     state: PrState,
     #[serde(rename = "isCrossRepository", default)]
     is_cross_repository: bool,
+    #[serde(rename = "headRepositoryOwner", default)]
+    head_repository_owner: Option<RepositoryOwner>,
+}
+
+impl PullRequestCandidate {
+    fn is_stack_head(&self, fork_owner: Option<&str>, branch: &str) -> bool {
+        self.head_ref_name == branch
+            && is_stack_repository(
+                fork_owner,
+                self.is_cross_repository,
+                self.head_repository_owner.as_ref(),
+            )
+    }
+}
+
+/// Return whether a PR head lives where `stck` pushes stack branches: the
+/// base repository itself, or the fork owned by `fork_owner` in a fork
+/// (triangular) workflow.
+fn is_stack_repository(
+    fork_owner: Option<&str>,
+    is_cross_repository: bool,
+    head_owner: Option<&RepositoryOwner>,
+) -> bool {
+    match fork_owner {
+        None => !is_cross_repository,
+        Some(fork_owner) => {
+            is_cross_repository
+                && head_owner.is_some_and(|owner| owner.login.eq_ignore_ascii_case(fork_owner))
+        }
+    }
 }
 
 impl From<PullRequestCandidate> for PullRequest {
@@ -73,16 +171,77 @@ impl From<PullRequestCandidate> for PullRequest {
     }
 }
 
-/// Discover the full linear stack surrounding `current_branch`.
+/// Discover the stack tree surrounding `current_branch`.
 ///
-/// The returned list is ordered from the stack root to the highest descendant
-/// branch. The function fails if any parent PR is missing, if multiple open
-/// children exist for a branch, or if the PR graph forms a cycle.
-pub fn discover_linear_stack(
+/// Discovery first walks PR bases from `current_branch` up to the stack root,
+/// then collects every non-closed descendant of that root. Branches without a
+/// PR join the tree through parents recorded locally by `stck new`, as
+/// [`PrState::Local`] nodes. The returned tree is ordered so parents precede
+/// children, with siblings sorted by branch name. The function fails if any
+/// parent has neither a PR nor a recorded parent, or if the graph forms a cycle.
+pub fn discover_stack_tree(
+    forge: &dyn Forge,
+    remotes: &Remotes,
     current_branch: &str,
     default_branch: &str,
-) -> Result<Vec<PullRequest>, String> {
-    let current = fetch_pr_for_branch(current_branch)?;
+) -> Result<StackTree, String> {
+    let local_parents = gitops::list_local_parents()?;
+    // Batched lookups only save round trips; any branch they miss is looked
+    // up individually during discovery.
+    prefetch_stack_prs(
+        forge,
+        remotes,
+        current_branch,
+        default_branch,
+        &local_parents,
+    )?;
+    discover_stack_tree_with(forge, current_branch, default_branch, &local_parents)
+}
+
+/// Warm the forge's PR lookups for the branches likely to be in the stack.
+///
+/// The first batch covers `current_branch`, the local branches it builds on or
+/// that build on it, and recorded local parents. A second batch covers any
+/// further branches those PRs point at, such as siblings in a branching stack.
+fn prefetch_stack_prs(
+    forge: &dyn Forge,
+    remotes: &Remotes,
+    current_branch: &str,
+    default_branch: &str,
+    local_parents: &[LocalParent],
+) -> Result<(), String> {
+    let mut branches = gitops::list_stack_neighbourhood(remotes, current_branch, default_branch)?;
+    branches.push(current_branch.to_string());
+    for local in local_parents {
+        branches.push(local.branch.clone());
+        branches.push(local.parent.clone());
+    }
+    branches.retain(|branch| branch != default_branch);
+    branches.sort();
+    branches.dedup();
+
+    let found = forge.prefetch(&branches)?;
+    let mut next = found
+        .iter()
+        .flat_map(|pr| [&pr.head_ref_name, &pr.base_ref_name])
+        .filter(|branch| *branch != default_branch && !branches.contains(branch))
+        .cloned()
+        .collect::<Vec<_>>();
+    next.sort();
+    next.dedup();
+    if !next.is_empty() {
+        forge.prefetch(&next)?;
+    }
+    Ok(())
+}
+
+fn discover_stack_tree_with(
+    forge: &dyn Forge,
+    current_branch: &str,
+    default_branch: &str,
+    local_parents: &[LocalParent],
+) -> Result<StackTree, String> {
+    let current = stack_node_for_branch(forge, current_branch, local_parents)?;
 
     let mut seen = vec![current.head_ref_name.clone()];
     let mut to_current = vec![current.clone()];
@@ -90,7 +249,7 @@ pub fn discover_linear_stack(
 
     // Walk up to root (default branch)
     while cursor_base != default_branch {
-        let parent = fetch_pr_for_branch(&cursor_base)?;
+        let parent = stack_node_for_branch(forge, &cursor_base, local_parents)?;
         if seen.iter().any(|b| b == &parent.head_ref_name) {
             return Err(format!(
                 "cycle detected in stack at branch {}",
@@ -101,129 +260,642 @@ pub fn discover_linear_stack(
         cursor_base = parent.base_ref_name.clone();
         to_current.push(parent);
     }
+    to_current.reverse();
 
-    // Walk down from current to tip
-    let mut cursor_head = current.head_ref_name.clone();
-    let mut below_current: Vec<PullRequest> = Vec::new();
-    loop {
-        let children = fetch_children_for_base(&cursor_head)?;
-        let mut eligible: Vec<PullRequest> = children
+    // Walk down from the root, depth first, collecting every subtree
+    let mut stack = StackTree::default();
+    let mut pending = vec![(to_current[0].clone(), None)];
+    while let Some((pr, parent)) = pending.pop() {
+        let mut children: Vec<PullRequest> = forge
+            .list_children(&pr.head_ref_name)?
             .into_iter()
-            .filter(|pr| pr.state != PrState::Closed)
+            .filter(|child| child.state != PrState::Closed)
             .collect();
-        eligible.sort_by(|a, b| a.head_ref_name.cmp(&b.head_ref_name));
-
-        match eligible.len() {
-            0 => break,
-            1 => {
-                let child = eligible.into_iter().next().unwrap();
-                if seen.iter().any(|b| b == &child.head_ref_name) {
-                    return Err(format!(
-                        "cycle detected in stack at branch {}",
-                        child.head_ref_name
-                    ));
-                }
-                seen.push(child.head_ref_name.clone());
-                cursor_head = child.head_ref_name.clone();
-                below_current.push(child);
+        for local in local_children_for_base(forge, &pr.head_ref_name, local_parents)? {
+            if !children
+                .iter()
+                .any(|child| child.head_ref_name == local.head_ref_name)
+            {
+                children.push(local);
             }
-            _ => {
-                let candidates = eligible
+        }
+        // Keep the path to the current branch even if the child listing omits it.
+        if let Some(path_child) = path_child_of(&to_current, &pr.head_ref_name) {
+            if !children
+                .iter()
+                .any(|child| child.head_ref_name == path_child.head_ref_name)
+            {
+                children.push(path_child.clone());
+            }
+        }
+        children.sort_by(|a, b| a.head_ref_name.cmp(&b.head_ref_name));
+
+        for child in &children {
+            let already_collected = child.head_ref_name == pr.head_ref_name
+                || stack.find(&child.head_ref_name).is_some()
+                || pending
                     .iter()
-                    .map(|c| c.head_ref_name.as_str())
-                    .collect::<Vec<_>>()
-                    .join(", ");
+                    .any(|(queued, _)| queued.head_ref_name == child.head_ref_name);
+            if already_collected {
                 return Err(format!(
-                    "non-linear stack detected at {}; child candidates: {}",
-                    cursor_head, candidates
+                    "cycle detected in stack at branch {}",
+                    child.head_ref_name
                 ));
             }
         }
+
+        let index = stack.push(pr, parent);
+        pending.extend(children.into_iter().rev().map(|child| (child, Some(index))));
     }
 
-    to_current.reverse();
-    let mut stack = to_current;
-    stack.extend(below_current);
     Ok(stack)
 }
 
-/// Change the GitHub base branch for the PR whose head matches `branch`.
-pub fn retarget_pr_base(branch: &str, new_base: &str) -> Result<(), String> {
-    let output = Command::new("gh")
-        .args(["pr", "edit", branch, "--base", new_base])
-        .output()
-        .map_err(|_| "failed to run `gh pr edit`; ensure GitHub CLI is installed".to_string())?;
+fn path_child_of<'a>(path: &'a [PullRequest], branch: &str) -> Option<&'a PullRequest> {
+    let index = path.iter().position(|pr| pr.head_ref_name == branch)?;
+    path.get(index + 1)
+}
+
+/// Most branches sent in one `gh api graphql` request by [`GhCli::prefetch`].
+const PREFETCH_BATCH: usize = 50;
 
-    if output.status.success() {
-        Ok(())
-    } else {
-        Err(with_stderr(
-            &format!(
-                "failed to retarget PR base for branch {branch} to {new_base}; fix the GitHub error and rerun `stck push`"
-            ),
-            &output.stderr,
-        ))
+/// Forge backend that shells out to the GitHub CLI (`gh`).
+#[derive(Debug, Default)]
+pub struct GhCli {
+    cache: RefCell<PrCache>,
+    /// Owner of the fork stack branches are pushed to, when PR heads live on a
+    /// fork rather than the base repository.
+    fork_owner: Option<String>,
+}
+
+/// PR lookups answered by [`GhCli::prefetch`], keyed by branch name.
+#[derive(Debug, Default)]
+struct PrCache {
+    heads: HashMap<String, Vec<PullRequestCandidate>>,
+    children: HashMap<String, Vec<PullRequest>>,
+}
+
+impl GhCli {
+    /// Build a backend whose PR heads live on the fork owned by `fork_owner`,
+    /// or on the base repository when `None`.
+    pub fn new(fork_owner: Option<String>) -> Self {
+        Self {
+            cache: RefCell::default(),
+            fork_owner,
+        }
+    }
+
+    /// Return the `gh` selector for the PR headed by `branch`, qualified with
+    /// the fork owner (`owner:branch`) in a fork workflow.
+    fn selector(&self, branch: &str) -> String {
+        match &self.fork_owner {
+            Some(owner) => format!("{owner}:{branch}"),
+            None => branch.to_string(),
+        }
+    }
+
+    fn cached_heads(&self, branch: &str) -> Option<Vec<PullRequestCandidate>> {
+        self.cache.borrow().heads.get(branch).cloned()
+    }
+
+    /// Drop cached lookups once a PR changes on GitHub.
+    fn invalidate(&self) {
+        *self.cache.borrow_mut() = PrCache::default();
+    }
+
+    /// Look up one batch of branches, returning `None` when GitHub rejects the
+    /// query itself, e.g. an Enterprise Server whose schema lacks a field it
+    /// uses. Individual lookups still work there, so callers fall back to them.
+    fn prefetch_batch(&self, branches: &[String]) -> Result<Option<Vec<PullRequest>>, String> {
+        let mut args = vec![
+            "api".to_string(),
+            "graphql".to_string(),
+            "-f".to_string(),
+            format!("query={}", prefetch_query(branches.len())),
+            "-F".to_string(),
+            "owner={owner}".to_string(),
+            "-F".to_string(),
+            "name={repo}".to_string(),
+        ];
+        for (index, branch) in branches.iter().enumerate() {
+            args.push("-f".to_string());
+            args.push(format!("b{index}={branch}"));
+        }
+
+        let output = Command::new("gh").args(&args).output().map_err(|_| {
+            "failed to run `gh api graphql`; ensure GitHub CLI is installed".to_string()
+        })?;
+        if is_graphql_rejection(&output.stdout) {
+            return Ok(None);
+        }
+        // Other GraphQL errors come with a response body whose message
+        // `parse_prefetch_response` reports; anything else failed before
+        // GitHub answered.
+        let has_graphql_errors = serde_json::from_slice::<serde_json::Value>(&output.stdout)
+            .is_ok_and(|response| response.get("errors").is_some());
+        if !output.status.success() && !has_graphql_errors {
+            return Err(with_stderr(
+                "failed to look up stack PRs via GitHub GraphQL",
+                &output.stderr,
+            ));
+        }
+
+        let lookups = parse_prefetch_response(&output.stdout, branches.len())?;
+        let mut found = Vec::new();
+        let mut cache = self.cache.borrow_mut();
+        for (branch, (heads, children)) in branches.iter().zip(lookups) {
+            found.extend(
+                heads
+                    .iter()
+                    .filter(|pr| pr.is_stack_head(self.fork_owner.as_deref(), branch))
+                    .cloned()
+                    .map(PullRequest::from),
+            );
+            found.extend(children.iter().cloned());
+            cache.heads.insert(branch.clone(), heads);
+            cache.children.insert(branch.clone(), children);
+        }
+        Ok(Some(found))
     }
 }
 
-/// Return whether an open same-repository pull request exists for `branch`.
-///
-/// An empty structured result is treated as `Ok(false)`. Other `gh` failures
-/// are surfaced as actionable errors. Cross-repository results with the same
-/// head name are ignored.
-pub fn pr_exists_for_head(branch: &str) -> Result<bool, String> {
-    let output = Command::new("gh")
-        .args([
-            "pr",
-            "list",
-            "--head",
-            branch,
-            "--state",
-            "open",
-            "--limit",
-            "100",
-            "--json",
-            "headRefName,isCrossRepository",
-        ])
-        .output()
-        .map_err(|_| "failed to run `gh pr list`; ensure GitHub CLI is installed".to_string())?;
-
-    if !output.status.success() {
-        return Err(with_stderr(
-            &format!(
-                "failed to check PR for branch {branch}; ensure `gh auth status` succeeds and retry"
+impl Forge for GhCli {
+    fn ensure_ready(&self) -> Result<(), String> {
+        ensure_command_available("gh")?;
+
+        let output = Command::new("gh")
+            .args(["auth", "status"])
+            .output()
+            .map_err(|_| {
+                "failed to run `gh auth status`; install GitHub CLI and authenticate".to_string()
+            })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err("GitHub CLI is not authenticated; run `gh auth login` and retry".to_string())
+        }
+    }
+
+    fn repo_metadata(&self) -> Result<RepoMetadata, String> {
+        let output = Command::new("gh")
+            .args([
+                "repo",
+                "view",
+                "--json",
+                "nameWithOwner,defaultBranchRef",
+                "--jq",
+                r#"[.nameWithOwner, .defaultBranchRef.name] | @tsv"#,
+            ])
+            .output()
+            .map_err(|_| "failed to discover repository default branch from GitHub".to_string())?;
+
+        if !output.status.success() {
+            return Err(
+                "could not discover default branch via GitHub CLI; ensure `origin` points to GitHub and `gh auth status` succeeds"
+                    .to_string(),
+            );
+        }
+
+        let metadata = String::from_utf8_lossy(&output.stdout);
+        let Some((repository, default_branch)) = metadata.trim().split_once('\t') else {
+            return Err(
+                "repository metadata lookup returned an invalid result; verify repository metadata on GitHub"
+                    .to_string(),
+            );
+        };
+        if default_branch.is_empty() {
+            Err(
+                "default branch lookup returned empty result; verify repository metadata on GitHub"
+                    .to_string(),
+            )
+        } else if repository.is_empty() {
+            Err(
+                "repository identity lookup returned empty result; verify repository metadata on GitHub"
+                    .to_string(),
+            )
+        } else {
+            Ok(RepoMetadata {
+                repository: repository.to_string(),
+                default_branch: default_branch.to_string(),
+            })
+        }
+    }
+
+    fn find_pr_for_head(&self, branch: &str) -> Result<Option<PullRequest>, String> {
+        if let Some(candidates) = self.cached_heads(branch) {
+            return pr_for_head(candidates, branch, self.fork_owner.as_deref());
+        }
+
+        let output = Command::new("gh")
+            .args([
+                "pr",
+                "list",
+                "--head",
+                branch,
+                "--state",
+                "all",
+                "--limit",
+                "100",
+                "--json",
+                "number,headRefName,baseRefName,state,isCrossRepository,headRepositoryOwner",
+            ])
+            .output()
+            .map_err(|_| {
+                "failed to run `gh pr list`; ensure GitHub CLI is installed".to_string()
+            })?;
+
+        if !output.status.success() {
+            return Err(with_stderr(
+                &format!("failed to fetch PR for branch {branch}"),
+                &output.stderr,
+            ));
+        }
+
+        let candidates = serde_json::from_slice::<Vec<PullRequestCandidate>>(&output.stdout)
+            .map_err(|_| format!("failed to parse PR metadata for branch {branch}"))?;
+        pr_for_head(candidates, branch, self.fork_owner.as_deref())
+    }
+
+    /// An empty structured result is treated as `Ok(false)`. Results with the
+    /// same head name from other repositories (other forks, or the base
+    /// repository in a fork workflow) are ignored.
+    fn open_pr_exists(&self, branch: &str) -> Result<bool, String> {
+        let fork_owner = self.fork_owner.as_deref();
+        if let Some(candidates) = self.cached_heads(branch) {
+            return Ok(candidates
+                .iter()
+                .any(|pr| pr.is_stack_head(fork_owner, branch) && pr.state == PrState::Open));
+        }
+
+        let output = Command::new("gh")
+            .args([
+                "pr",
+                "list",
+                "--head",
+                branch,
+                "--state",
+                "open",
+                "--limit",
+                "100",
+                "--json",
+                "headRefName,isCrossRepository,headRepositoryOwner",
+            ])
+            .output()
+            .map_err(|_| {
+                "failed to run `gh pr list`; ensure GitHub CLI is installed".to_string()
+            })?;
+
+        if !output.status.success() {
+            return Err(with_stderr(
+                &format!(
+                    "failed to check PR for branch {branch}; ensure `gh auth status` succeeds and retry"
+                ),
+                &output.stderr,
+            ));
+        }
+
+        let prs = serde_json::from_slice::<Vec<OpenPullRequestHead>>(&output.stdout)
+            .map_err(|_| format!("failed to parse PR lookup metadata for branch {branch}"))?;
+        Ok(prs.iter().any(|pr| {
+            pr.head_ref_name == branch
+                && is_stack_repository(
+                    fork_owner,
+                    pr.is_cross_repository,
+                    pr.head_repository_owner.as_ref(),
+                )
+        }))
+    }
+
+    fn list_children(&self, base: &str) -> Result<Vec<PullRequest>, String> {
+        if let Some(children) = self.cache.borrow().children.get(base) {
+            return Ok(children.clone());
+        }
+
+        let output = Command::new("gh")
+            .args([
+                "pr",
+                "list",
+                "--base",
+                base,
+                "--state",
+                "all",
+                "--limit",
+                "100",
+                "--json",
+                "number,headRefName,baseRefName,state",
+            ])
+            .output()
+            .map_err(|_| {
+                "failed to run `gh pr list`; ensure GitHub CLI is installed".to_string()
+            })?;
+
+        if !output.status.success() {
+            return Err(with_stderr(
+                &format!("failed to list PRs with base {base}"),
+                &output.stderr,
+            ));
+        }
+
+        parse_pull_requests_json(&output.stdout)
+    }
+
+    /// Branches are looked up in batches of [`PREFETCH_BATCH`] per
+    /// `gh api graphql` call, fetching both the PRs headed by each branch and
+    /// the PRs based on it.
+    fn prefetch(&self, branches: &[String]) -> Result<Vec<PullRequest>, String> {
+        let mut found = Vec::new();
+        for batch in branches.chunks(PREFETCH_BATCH) {
+            match self.prefetch_batch(batch)? {
+                Some(batch) => found.extend(batch),
+                None => break,
+            }
+        }
+        Ok(found)
+    }
+
+    fn create_pr(&self, pr: &NewPullRequest) -> Result<(), String> {
+        self.invalidate();
+        let output = Command::new("gh")
+            .args([
+                "pr",
+                "create",
+                "--base",
+                &pr.base,
+                "--head",
+                &self.selector(&pr.head),
+                "--title",
+                &pr.title,
+                "--body",
+                &pr.body,
+            ])
+            .args(pr.cli_option_args())
+            .output()
+            .map_err(|_| {
+                "failed to run `gh pr create`; ensure GitHub CLI is installed".to_string()
+            })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(with_stderr(
+                &format!(
+                    "failed to create PR for branch {}; fix the GitHub error and retry",
+                    pr.head
+                ),
+                &output.stderr,
+            ))
+        }
+    }
+
+    fn retarget_pr(&self, branch: &str, new_base: &str) -> Result<(), String> {
+        self.invalidate();
+        let output = Command::new("gh")
+            .args(["pr", "edit", &self.selector(branch), "--base", new_base])
+            .output()
+            .map_err(|_| {
+                "failed to run `gh pr edit`; ensure GitHub CLI is installed".to_string()
+            })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(with_stderr(
+                &format!(
+                    "failed to retarget PR base for branch {branch} to {new_base}; fix the GitHub error and rerun `stck push`"
+                ),
+                &output.stderr,
+            ))
+        }
+    }
+
+    fn describe(&self, operation: ForgeOperation<'_>) -> String {
+        match operation {
+            ForgeOperation::Create(pr) => format!(
+                "$ gh pr create --base {} --head {} --title {} --body \"<stack context>\"{}",
+                pr.base,
+                self.selector(&pr.head),
+                shell_word(&pr.title),
+                describe_option_args(pr)
             ),
-            &output.stderr,
-        ));
+            ForgeOperation::Retarget { branch, new_base } => {
+                format!("$ gh pr edit {} --base {new_base}", self.selector(branch))
+            }
+            ForgeOperation::Merge {
+                branch,
+                method,
+                head_sha,
+            } => format!(
+                "$ gh pr merge {} --{method} --match-head-commit {head_sha}",
+                self.selector(branch)
+            ),
+            ForgeOperation::UpdateBody { branch } => {
+                format!(
+                    "$ gh pr edit {} --body \"<stack navigation>\"",
+                    self.selector(branch)
+                )
+            }
+            ForgeOperation::Close { branch, comment } => format!(
+                "$ gh pr close {} --comment {}",
+                self.selector(branch),
+                shell_word(comment)
+            ),
+        }
     }
 
-    let prs = serde_json::from_slice::<Vec<OpenPullRequestHead>>(&output.stdout)
-        .map_err(|_| format!("failed to parse PR lookup metadata for branch {branch}"))?;
-    Ok(prs
-        .iter()
-        .any(|pr| !pr.is_cross_repository && pr.head_ref_name == branch))
+    fn pr_body(&self, branch: &str) -> Result<String, String> {
+        let output = Command::new("gh")
+            .args([
+                "pr",
+                "view",
+                &self.selector(branch),
+                "--json",
+                "body",
+                "--jq",
+                ".body",
+            ])
+            .output()
+            .map_err(|_| {
+                "failed to run `gh pr view`; ensure GitHub CLI is installed".to_string()
+            })?;
+
+        if !output.status.success() {
+            return Err(with_stderr(
+                &format!("failed to read PR body for branch {branch}"),
+                &output.stderr,
+            ));
+        }
+
+        // `--jq` prints the raw string followed by a newline.
+        let body = String::from_utf8_lossy(&output.stdout);
+        Ok(body.strip_suffix('\n').unwrap_or(&body).to_string())
+    }
+
+    fn pr_content(&self, branch: &str) -> Result<NewPullRequest, String> {
+        let output = Command::new("gh")
+            .args([
+                "pr",
+                "view",
+                &self.selector(branch),
+                "--json",
+                "baseRefName,title,body,isDraft,reviewRequests,assignees,labels",
+            ])
+            .output()
+            .map_err(|_| {
+                "failed to run `gh pr view`; ensure GitHub CLI is installed".to_string()
+            })?;
+
+        if !output.status.success() {
+            return Err(with_stderr(
+                &format!("failed to read PR for branch {branch}"),
+                &output.stderr,
+            ));
+        }
+        parse_pr_content(&output.stdout, branch)
+    }
+
+    fn update_pr_body(&self, branch: &str, body: &str) -> Result<(), String> {
+        let output = Command::new("gh")
+            .args(["pr", "edit", &self.selector(branch), "--body", body])
+            .output()
+            .map_err(|_| {
+                "failed to run `gh pr edit`; ensure GitHub CLI is installed".to_string()
+            })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(with_stderr(
+                &format!(
+                    "failed to update PR body for branch {branch}; fix the GitHub error and retry"
+                ),
+                &output.stderr,
+            ))
+        }
+    }
+
+    fn close_pr(&self, branch: &str, comment: &str) -> Result<(), String> {
+        self.invalidate();
+        let output = Command::new("gh")
+            .args(["pr", "close", &self.selector(branch), "--comment", comment])
+            .output()
+            .map_err(|_| {
+                "failed to run `gh pr close`; ensure GitHub CLI is installed".to_string()
+            })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(with_stderr(
+                &format!("failed to close PR for branch {branch}; fix the GitHub error and retry"),
+                &output.stderr,
+            ))
+        }
+    }
+
+    /// Methods are listed in the order GitHub offers them: merge commit,
+    /// squash, then rebase.
+    fn allowed_merge_methods(&self) -> Result<Vec<MergeMethod>, String> {
+        let output = Command::new("gh")
+            .args([
+                "repo",
+                "view",
+                "--json",
+                "mergeCommitAllowed,squashMergeAllowed,rebaseMergeAllowed",
+                "--jq",
+                "[.mergeCommitAllowed, .squashMergeAllowed, .rebaseMergeAllowed] | @tsv",
+            ])
+            .output()
+            .map_err(|_| {
+                "failed to run `gh repo view`; ensure GitHub CLI is installed".to_string()
+            })?;
+
+        if !output.status.success() {
+            return Err(with_stderr(
+                "failed to read allowed merge methods from GitHub",
+                &output.stderr,
+            ));
+        }
+
+        parse_allowed_merge_methods(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// GitHub rejects the merge if the PR head no longer matches `head_sha`, so
+    /// a concurrent push cannot sneak unreviewed commits into the landed PR.
+    fn merge_pr(&self, branch: &str, method: MergeMethod, head_sha: &str) -> Result<(), String> {
+        self.invalidate();
+        let method_flag = format!("--{method}");
+        let output = Command::new("gh")
+            .args([
+                "pr",
+                "merge",
+                &self.selector(branch),
+                &method_flag,
+                "--match-head-commit",
+                head_sha,
+            ])
+            .output()
+            .map_err(|_| {
+                "failed to run `gh pr merge`; ensure GitHub CLI is installed".to_string()
+            })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(with_stderr(
+                &format!(
+                    "failed to merge PR for branch {branch}; fix the GitHub error and rerun `stck land`"
+                ),
+                &output.stderr,
+            ))
+        }
+    }
 }
 
-/// Create a pull request with the given base, head, title, and body.
-pub fn create_pr(base: &str, head: &str, title: &str, body: &str) -> Result<(), String> {
-    let output = Command::new("gh")
-        .args([
-            "pr", "create", "--base", base, "--head", head, "--title", title, "--body", body,
-        ])
-        .output()
-        .map_err(|_| "failed to run `gh pr create`; ensure GitHub CLI is installed".to_string())?;
+fn parse_pr_content(bytes: &[u8], branch: &str) -> Result<NewPullRequest, String> {
+    let content = serde_json::from_slice::<PullRequestContent>(bytes)
+        .map_err(|_| format!("PR lookup for branch {branch} returned an invalid result"))?;
+    let logins = |users: Vec<UserLogin>| users.into_iter().filter_map(|user| user.login).collect();
+    Ok(NewPullRequest {
+        base: content.base_ref_name,
+        head: branch.to_string(),
+        title: content.title,
+        body: content.body,
+        draft: content.is_draft,
+        reviewers: logins(content.review_requests),
+        assignees: logins(content.assignees),
+        labels: content.labels.into_iter().map(|label| label.name).collect(),
+    })
+}
 
-    if output.status.success() {
-        Ok(())
-    } else {
-        Err(with_stderr(
-            &format!("failed to create PR for branch {head}; fix the GitHub error and retry"),
-            &output.stderr,
-        ))
+fn parse_allowed_merge_methods(raw: &str) -> Result<Vec<MergeMethod>, String> {
+    let flags = raw.trim().split('\t').collect::<Vec<_>>();
+    if flags.len() != 3 || flags.iter().any(|flag| *flag != "true" && *flag != "false") {
+        return Err(
+            "merge method lookup returned an invalid result; verify repository settings on GitHub"
+                .to_string(),
+        );
     }
+
+    Ok(
+        [MergeMethod::Merge, MergeMethod::Squash, MergeMethod::Rebase]
+            .into_iter()
+            .zip(flags)
+            .filter(|(_, flag)| *flag == "true")
+            .map(|(method, _)| method)
+            .collect(),
+    )
 }
 
+/// Opening marker of the `stck`-managed block in PR descriptions.
+pub const STACK_BLOCK_START: &str = "<!-- stck:stack:start -->";
+/// Closing marker of the `stck`-managed block in PR descriptions.
+pub const STACK_BLOCK_END: &str = "<!-- stck:stack:end -->";
+
 /// Build the deterministic stack context included in newly created PRs.
+///
+/// The context sits inside the managed block, so the first `stck push` or
+/// `stck nav` replaces it with the full navigation table.
 pub fn stack_pr_body(base: &str, default_branch: &str) -> String {
     let position = if base == default_branch {
         "Root"
@@ -232,10 +904,79 @@ pub fn stack_pr_body(base: &str, default_branch: &str) -> String {
     };
     let base = markdown_code_span(base);
     format!(
-        "This pull request is part of a stack.\n\n- **Position:** {position}\n- **Base:** {base}"
+        "{STACK_BLOCK_START}\nThis pull request is part of a stack.\n\n- **Position:** {position}\n- **Base:** {base}\n{STACK_BLOCK_END}"
     )
 }
 
+/// Render the managed navigation block listing every PR in `stack`, with the
+/// row for `current_branch` highlighted.
+///
+/// `reference` renders a PR number the way the forge links it. Branches
+/// without a PR are listed as not yet submitted.
+pub fn stack_navigation_block(
+    stack: &StackTree,
+    current_branch: &str,
+    reference: impl Fn(u64) -> String,
+) -> String {
+    let mut lines = vec![
+        STACK_BLOCK_START.to_string(),
+        "**Stack** (maintained by `stck`; edits inside this block are overwritten)".to_string(),
+        String::new(),
+        "| | PR | Branch | Base | State |".to_string(),
+        "| --- | --- | --- | --- | --- |".to_string(),
+    ];
+    for pr in stack.prs() {
+        let is_current = pr.head_ref_name == current_branch;
+        let number = if pr.state == PrState::Local {
+            "—".to_string()
+        } else {
+            reference(pr.number)
+        };
+        let branch = markdown_code_span(&pr.head_ref_name);
+        let (marker, number, branch) = if is_current {
+            ("→", format!("**{number}**"), format!("**{branch}**"))
+        } else {
+            ("", number, branch)
+        };
+        let state = match pr.state {
+            PrState::Local => "not submitted".to_string(),
+            state => state.to_string().to_lowercase(),
+        };
+        lines.push(format!(
+            "| {marker} | {number} | {branch} | {} | {state} |",
+            markdown_code_span(&pr.base_ref_name)
+        ));
+    }
+    lines.push(STACK_BLOCK_END.to_string());
+    lines.join("\n")
+}
+
+/// Replace the managed block in `body` with `block`, keeping all other text.
+///
+/// A body without a complete block gets `block` appended after a blank line.
+pub fn replace_stack_block(body: &str, block: &str) -> String {
+    if let Some(start) = body.find(STACK_BLOCK_START) {
+        if let Some(end) = body[start..].find(STACK_BLOCK_END) {
+            let end = start + end + STACK_BLOCK_END.len();
+            return format!("{}{block}{}", &body[..start], &body[end..]);
+        }
+    }
+    let body = body.trim_end();
+    if body.is_empty() {
+        block.to_string()
+    } else {
+        format!("{body}\n\n{block}")
+    }
+}
+
+/// Render the optional `gh`/`glab` create flags of `pr` for an echoed command.
+pub fn describe_option_args(pr: &NewPullRequest) -> String {
+    pr.cli_option_args()
+        .iter()
+        .map(|arg| format!(" {}", shell_word(arg)))
+        .collect()
+}
+
 fn markdown_code_span(value: &str) -> String {
     let longest_run = value
         .split(|character| character != '`')
@@ -246,93 +987,150 @@ fn markdown_code_span(value: &str) -> String {
     format!("{fence}{value}{fence}")
 }
 
-fn fetch_pr_for_branch(branch: &str) -> Result<PullRequest, String> {
-    let output = Command::new("gh")
-        .args([
-            "pr",
-            "list",
-            "--head",
-            branch,
-            "--state",
-            "all",
-            "--limit",
-            "100",
-            "--json",
-            "number,headRefName,baseRefName,state,isCrossRepository",
-        ])
-        .output()
-        .map_err(|_| "failed to run `gh pr list`; ensure GitHub CLI is installed".to_string())?;
-
-    if !output.status.success() {
-        return Err(with_stderr(
-            &format!("failed to fetch PR for branch {branch}"),
-            &output.stderr,
-        ));
+/// Resolve the stack node for `branch` from its PR, falling back to a parent
+/// recorded locally when the branch has no PR yet.
+fn stack_node_for_branch(
+    forge: &dyn Forge,
+    branch: &str,
+    local_parents: &[LocalParent],
+) -> Result<PullRequest, String> {
+    if let Some(pr) = forge.find_pr_for_head(branch)? {
+        return Ok(pr);
     }
+    local_parents
+        .iter()
+        .find(|local| local.branch == branch)
+        .map(local_stack_node)
+        .ok_or_else(|| format!("no PR found for branch {branch}; create a PR first"))
+}
 
-    let candidates = serde_json::from_slice::<Vec<PullRequestCandidate>>(&output.stdout)
-        .map_err(|_| format!("failed to parse PR metadata for branch {branch}"))?;
-    select_pr_for_head(candidates, branch)
+/// Represent a branch without a PR as a [`PrState::Local`] stack node.
+fn local_stack_node(local: &LocalParent) -> PullRequest {
+    PullRequest {
+        number: 0,
+        head_ref_name: local.branch.clone(),
+        base_ref_name: local.parent.clone(),
+        state: PrState::Local,
+    }
+}
+
+/// List local-only branches recorded as children of `branch`.
+///
+/// Branches that no longer exist locally, or that have since been published
+/// as a PR, are skipped; PR metadata always wins over local records.
+fn local_children_for_base(
+    forge: &dyn Forge,
+    branch: &str,
+    local_parents: &[LocalParent],
+) -> Result<Vec<PullRequest>, String> {
+    let mut children = Vec::new();
+    for local in local_parents.iter().filter(|local| local.parent == branch) {
+        if gitops::local_branch_exists(&local.branch)?
+            && forge.find_pr_for_head(&local.branch)?.is_none()
+        {
+            children.push(local_stack_node(local));
+        }
+    }
+    Ok(children)
 }
 
-fn select_pr_for_head(
+fn pr_for_head(
     candidates: Vec<PullRequestCandidate>,
     branch: &str,
-) -> Result<PullRequest, String> {
-    let mut candidates = candidates
+    fork_owner: Option<&str>,
+) -> Result<Option<PullRequest>, String> {
+    let candidates = candidates
         .into_iter()
-        .filter(|pr| !pr.is_cross_repository && pr.head_ref_name == branch)
-        .collect::<Vec<_>>();
+        .filter(|pr| pr.is_stack_head(fork_owner, branch))
+        .map(PullRequest::from)
+        .collect();
+    forge::select_pr_for_head(candidates, branch)
+}
 
-    let open_count = candidates
-        .iter()
-        .filter(|pr| pr.state == PrState::Open)
-        .count();
-    if open_count > 1 {
-        return Err(format!(
-            "multiple open PRs found for branch {branch}; close duplicates before retrying"
+/// Build a GraphQL query that, for each branch variable `$b<i>`, asks for the
+/// PRs headed by it (alias `h<i>`) and the PRs based on it (alias `c<i>`).
+fn prefetch_query(branch_count: usize) -> String {
+    let mut variables = String::from("$owner: String!, $name: String!");
+    let mut fields = String::new();
+    for index in 0..branch_count {
+        variables.push_str(&format!(", $b{index}: String!"));
+        fields.push_str(&format!(
+            " h{index}: pullRequests(headRefName: $b{index}, first: 100) {{ nodes {{ ...stackPr }} }}\
+             c{index}: pullRequests(baseRefName: $b{index}, first: 100) {{ nodes {{ ...stackPr }} }}"
         ));
     }
-    if open_count == 1 {
-        let index = candidates
-            .iter()
-            .position(|pr| pr.state == PrState::Open)
-            .ok_or_else(|| format!("failed to select open PR for branch {branch}"))?;
-        return Ok(candidates.swap_remove(index).into());
-    }
+    format!(
+        "query({variables}) {{ repository(owner: $owner, name: $name) {{{fields} }} }} \
+         fragment stackPr on PullRequest {{ number headRefName baseRefName state isCrossRepository headRepositoryOwner {{ login }} }}"
+    )
+}
 
-    candidates
-        .into_iter()
-        .next()
-        .map(Into::into)
-        .ok_or_else(|| format!("no PR found for branch {branch}; create a PR first"))
+type PrefetchLookup = (Vec<PullRequestCandidate>, Vec<PullRequest>);
+
+/// Return whether a `gh api graphql` response rejects the query itself: every
+/// error is a schema validation error, such as a field the server's schema
+/// lacks. Other GraphQL errors (unknown repository, rate limiting, partial
+/// data) are not rejections and are reported.
+fn is_graphql_rejection(bytes: &[u8]) -> bool {
+    let Ok(response) = serde_json::from_slice::<serde_json::Value>(bytes) else {
+        return false;
+    };
+    let Some(errors) = response.get("errors").and_then(serde_json::Value::as_array) else {
+        return false;
+    };
+    !errors.is_empty()
+        && errors.iter().all(|error| {
+            error
+                .pointer("/extensions/code")
+                .or_else(|| error.get("type"))
+                .and_then(serde_json::Value::as_str)
+                .is_some_and(|code| GRAPHQL_SCHEMA_ERRORS.contains(&code))
+        })
 }
 
-fn fetch_children_for_base(branch: &str) -> Result<Vec<PullRequest>, String> {
-    let output = Command::new("gh")
-        .args([
-            "pr",
-            "list",
-            "--base",
-            branch,
-            "--state",
-            "all",
-            "--limit",
-            "100",
-            "--json",
-            "number,headRefName,baseRefName,state",
-        ])
-        .output()
-        .map_err(|_| "failed to run `gh pr list`; ensure GitHub CLI is installed".to_string())?;
-
-    if !output.status.success() {
-        return Err(with_stderr(
-            &format!("failed to list PRs with base {branch}"),
-            &output.stderr,
+/// GraphQL validation error codes meaning the server's schema cannot run the
+/// batched query, e.g. an older GitHub Enterprise Server.
+const GRAPHQL_SCHEMA_ERRORS: [&str; 4] = [
+    "undefinedField",
+    "undefinedType",
+    "argumentNotAccepted",
+    "argumentLiteralsIncompatible",
+];
+
+/// Split a [`prefetch_query`] response into per-branch head and child lookups.
+fn parse_prefetch_response(
+    bytes: &[u8],
+    branch_count: usize,
+) -> Result<Vec<PrefetchLookup>, String> {
+    let invalid = || "failed to parse stack PR metadata from GitHub GraphQL".to_string();
+    let response = serde_json::from_slice::<serde_json::Value>(bytes).map_err(|_| invalid())?;
+    if let Some(message) = response
+        .pointer("/errors/0/message")
+        .and_then(serde_json::Value::as_str)
+    {
+        return Err(format!(
+            "failed to look up stack PRs via GitHub GraphQL: {message}"
         ));
     }
-
-    parse_pull_requests_json(&output.stdout)
+    let repository = response.pointer("/data/repository").ok_or_else(invalid)?;
+
+    let nodes = |alias: String| -> Result<Vec<PullRequestCandidate>, String> {
+        let nodes = repository
+            .get(&alias)
+            .and_then(|connection| connection.get("nodes"))
+            .ok_or_else(invalid)?;
+        serde_json::from_value(nodes.clone()).map_err(|_| invalid())
+    };
+    (0..branch_count)
+        .map(|index| {
+            let heads = nodes(format!("h{index}"))?;
+            let children = nodes(format!("c{index}"))?
+                .into_iter()
+                .map(PullRequest::from)
+                .collect();
+            Ok((heads, children))
+        })
+        .collect()
 }
 
 fn parse_pull_requests_json(bytes: &[u8]) -> Result<Vec<PullRequest>, String> {
@@ -340,103 +1138,43 @@ fn parse_pull_requests_json(bytes: &[u8]) -> Result<Vec<PullRequest>, String> {
         .map_err(|_| "failed to parse PR metadata from GitHub CLI output".to_string())
 }
 
-/// Test-only stack builder that mirrors `discover_linear_stack` without shelling out.
+/// Test-only stack builder that runs `discover_stack_tree` against an in-memory forge.
 #[cfg(test)]
-pub fn build_linear_stack(
+pub fn build_stack_tree(
     prs: &[PullRequest],
     current_branch: &str,
     default_branch: &str,
-) -> Result<Vec<PullRequest>, String> {
-    use std::collections::HashMap;
-    let by_head: HashMap<&str, &PullRequest> = prs
-        .iter()
-        .map(|pr| (pr.head_ref_name.as_str(), pr))
-        .collect();
-
-    let current = by_head
-        .get(current_branch)
-        .copied()
-        .ok_or_else(|| format!("no PR found for branch {current_branch}; create a PR first"))?;
-
-    let mut seen = vec![current.head_ref_name.clone()];
-    let mut to_current = vec![current.clone()];
-    let mut cursor = current;
-
-    while cursor.base_ref_name != default_branch {
-        let parent = by_head
-            .get(cursor.base_ref_name.as_str())
-            .copied()
-            .ok_or_else(|| {
-                format!(
-                    "no PR found for branch {}; create a PR first",
-                    cursor.base_ref_name
-                )
-            })?;
-
-        if seen.iter().any(|branch| branch == &parent.head_ref_name) {
-            return Err(format!(
-                "cycle detected in stack at branch {}",
-                parent.head_ref_name
-            ));
-        }
-
-        seen.push(parent.head_ref_name.clone());
-        to_current.push(parent.clone());
-        cursor = parent;
-    }
-
-    let mut current_to_top = vec![current.clone()];
-    cursor = current;
-    loop {
-        let mut children: Vec<&PullRequest> = prs
+) -> Result<StackTree, String> {
+    use crate::forge::{FixtureForge, FixturePullRequest, FixtureState};
+
+    let forge = FixtureForge::in_memory(FixtureState {
+        repository: "example/stck".to_string(),
+        default_branch: default_branch.to_string(),
+        merge_methods: Vec::new(),
+        pull_requests: prs
             .iter()
-            .filter(|candidate| {
-                candidate.base_ref_name == cursor.head_ref_name
-                    && candidate.state != PrState::Closed
+            .map(|pr| FixturePullRequest {
+                pr: pr.clone(),
+                title: String::new(),
+                body: String::new(),
+                draft: false,
+                reviewers: Vec::new(),
+                assignees: Vec::new(),
+                labels: Vec::new(),
+                comments: Vec::new(),
             })
-            .collect();
-
-        children.sort_by(|a, b| a.head_ref_name.cmp(&b.head_ref_name));
-
-        match children.len() {
-            0 => break,
-            1 => {
-                let child = children[0];
-                if seen.iter().any(|branch| branch == &child.head_ref_name) {
-                    return Err(format!(
-                        "cycle detected in stack at branch {}",
-                        child.head_ref_name
-                    ));
-                }
-                seen.push(child.head_ref_name.clone());
-                current_to_top.push(child.clone());
-                cursor = child;
-            }
-            _ => {
-                let candidates = children
-                    .iter()
-                    .map(|child| child.head_ref_name.as_str())
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                return Err(format!(
-                    "non-linear stack detected at {}; child candidates: {}",
-                    cursor.head_ref_name, candidates
-                ));
-            }
-        }
-    }
-
-    to_current.reverse();
-    let mut stack = to_current;
-    stack.extend(current_to_top.into_iter().skip(1));
-    Ok(stack)
+            .collect(),
+    });
+    discover_stack_tree_with(&forge, current_branch, default_branch, &[])
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        build_linear_stack, select_pr_for_head, stack_pr_body, PrState, PullRequest,
-        PullRequestCandidate,
+        build_stack_tree, is_graphql_rejection, parse_allowed_merge_methods, parse_pr_content,
+        parse_prefetch_response, pr_for_head, prefetch_query, replace_stack_block,
+        stack_navigation_block, stack_pr_body, MergeMethod, PrState, PullRequest,
+        PullRequestCandidate, RepositoryOwner,
     };
 
     fn pr(number: u64, head: &str, base: &str) -> PullRequest {
@@ -455,6 +1193,17 @@ mod tests {
             base_ref_name: base.to_string(),
             state,
             is_cross_repository: false,
+            head_repository_owner: None,
+        }
+    }
+
+    fn fork_candidate(number: u64, owner: &str, head: &str, base: &str) -> PullRequestCandidate {
+        PullRequestCandidate {
+            is_cross_repository: true,
+            head_repository_owner: Some(RepositoryOwner {
+                login: owner.to_string(),
+            }),
+            ..candidate(number, head, base, PrState::Open)
         }
     }
 
@@ -462,7 +1211,7 @@ mod tests {
     fn builds_root_pr_stack_context() {
         assert_eq!(
             stack_pr_body("main", "main"),
-            "This pull request is part of a stack.\n\n- **Position:** Root\n- **Base:** `main`"
+            "<!-- stck:stack:start -->\nThis pull request is part of a stack.\n\n- **Position:** Root\n- **Base:** `main`\n<!-- stck:stack:end -->"
         );
     }
 
@@ -470,7 +1219,7 @@ mod tests {
     fn builds_child_pr_stack_context() {
         assert_eq!(
             stack_pr_body("feature-base", "main"),
-            "This pull request is part of a stack.\n\n- **Position:** Child\n- **Base:** `feature-base`"
+            "<!-- stck:stack:start -->\nThis pull request is part of a stack.\n\n- **Position:** Child\n- **Base:** `feature-base`\n<!-- stck:stack:end -->"
         );
     }
 
@@ -478,19 +1227,58 @@ mod tests {
     fn preserves_backticks_in_stack_base_branch() {
         assert_eq!(
             stack_pr_body("feature`base", "main"),
-            "This pull request is part of a stack.\n\n- **Position:** Child\n- **Base:** ``feature`base``"
+            "<!-- stck:stack:start -->\nThis pull request is part of a stack.\n\n- **Position:** Child\n- **Base:** ``feature`base``\n<!-- stck:stack:end -->"
         );
     }
 
+    #[test]
+    fn renders_navigation_block_with_current_pr_highlighted() {
+        let prs = vec![
+            pr(100, "feature-base", "main"),
+            pr(101, "feature-top", "feature-base"),
+        ];
+        let stack = build_stack_tree(&prs, "feature-top", "main").expect("stack should build");
+
+        assert_eq!(
+            stack_navigation_block(&stack, "feature-top", |number| format!("#{number}")),
+            "<!-- stck:stack:start -->\n**Stack** (maintained by `stck`; edits inside this block are overwritten)\n\n| | PR | Branch | Base | State |\n| --- | --- | --- | --- | --- |\n|  | #100 | `feature-base` | `main` | open |\n| → | **#101** | **`feature-top`** | `feature-base` | open |\n<!-- stck:stack:end -->"
+        );
+    }
+
+    #[test]
+    fn replaces_only_the_managed_block_in_a_pr_body() {
+        let body =
+            "Intro text.\n\n<!-- stck:stack:start -->\nold\n<!-- stck:stack:end -->\n\nFooter.";
+        let block = "<!-- stck:stack:start -->\nnew\n<!-- stck:stack:end -->";
+
+        assert_eq!(
+            replace_stack_block(body, block),
+            "Intro text.\n\n<!-- stck:stack:start -->\nnew\n<!-- stck:stack:end -->\n\nFooter."
+        );
+    }
+
+    #[test]
+    fn appends_the_managed_block_when_a_pr_body_has_none() {
+        let block = "<!-- stck:stack:start -->\nnew\n<!-- stck:stack:end -->";
+
+        assert_eq!(
+            replace_stack_block("Hand-written summary.\n", block),
+            "Hand-written summary.\n\n<!-- stck:stack:start -->\nnew\n<!-- stck:stack:end -->"
+        );
+        assert_eq!(replace_stack_block("", block), block);
+    }
+
     #[test]
     fn structured_head_lookup_prefers_open_pr_over_history() {
-        let selected = select_pr_for_head(
+        let selected = pr_for_head(
             vec![
                 candidate(100, "feature", "old-base", PrState::Merged),
                 candidate(101, "feature", "main", PrState::Open),
             ],
             "feature",
+            None,
         )
+        .expect("lookup should succeed")
         .expect("open PR should be selected");
 
         assert_eq!(selected.number, 101);
@@ -499,20 +1287,18 @@ mod tests {
 
     #[test]
     fn structured_head_lookup_reports_empty_results() {
-        let error =
-            select_pr_for_head(Vec::new(), "feature").expect_err("empty lookup should fail");
-
-        assert_eq!(error, "no PR found for branch feature; create a PR first");
+        assert_eq!(pr_for_head(Vec::new(), "feature", None), Ok(None));
     }
 
     #[test]
     fn structured_head_lookup_rejects_multiple_open_prs() {
-        let error = select_pr_for_head(
+        let error = pr_for_head(
             vec![
                 candidate(100, "feature", "main", PrState::Open),
                 candidate(101, "feature", "release", PrState::Open),
             ],
             "feature",
+            None,
         )
         .expect_err("ambiguous lookup should fail");
 
@@ -522,6 +1308,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn head_lookup_matches_only_the_configured_fork() {
+        let candidates = vec![
+            candidate(100, "feature", "main", PrState::Open),
+            fork_candidate(101, "someone-else", "feature", "main"),
+            fork_candidate(102, "Me", "feature", "main"),
+        ];
+
+        let own = pr_for_head(candidates.clone(), "feature", None)
+            .expect("lookup should succeed")
+            .expect("same-repository PR should match");
+        assert_eq!(own.number, 100);
+
+        let fork = pr_for_head(candidates, "feature", Some("me"))
+            .expect("lookup should succeed")
+            .expect("fork PR should match");
+        assert_eq!(fork.number, 102);
+
+        assert_eq!(
+            pr_for_head(
+                vec![fork_candidate(103, "someone-else", "feature", "main")],
+                "feature",
+                Some("me"),
+            ),
+            Ok(None)
+        );
+    }
+
     #[test]
     fn builds_linear_stack_from_ancestor_to_descendant() {
         let prs = vec![
@@ -530,8 +1344,9 @@ mod tests {
             pr(102, "feature-top", "feature-mid"),
         ];
 
-        let stack = build_linear_stack(&prs, "feature-mid", "main").expect("stack should build");
+        let stack = build_stack_tree(&prs, "feature-mid", "main").expect("stack should build");
         let heads = stack
+            .prs()
             .iter()
             .map(|item| item.head_ref_name.as_str())
             .collect::<Vec<_>>();
@@ -543,7 +1358,7 @@ mod tests {
     fn errors_when_current_branch_pr_is_missing() {
         let prs = vec![pr(100, "feature-base", "main")];
 
-        let error = build_linear_stack(&prs, "feature-mid", "main")
+        let error = build_stack_tree(&prs, "feature-mid", "main")
             .expect_err("missing current branch PR should error");
 
         assert_eq!(
@@ -556,7 +1371,7 @@ mod tests {
     fn errors_when_parent_pr_is_missing() {
         let prs = vec![pr(101, "feature-mid", "feature-base")];
 
-        let error = build_linear_stack(&prs, "feature-mid", "main")
+        let error = build_stack_tree(&prs, "feature-mid", "main")
             .expect_err("missing parent PR should error");
 
         assert_eq!(
@@ -566,21 +1381,48 @@ mod tests {
     }
 
     #[test]
-    fn errors_on_non_linear_descendants() {
+    fn builds_tree_for_branching_descendants() {
         let prs = vec![
             pr(100, "feature-base", "main"),
             pr(101, "feature-mid", "feature-base"),
-            pr(102, "feature-child-a", "feature-mid"),
             pr(103, "feature-child-b", "feature-mid"),
+            pr(102, "feature-child-a", "feature-mid"),
+            pr(104, "feature-grandchild", "feature-child-a"),
         ];
 
-        let error = build_linear_stack(&prs, "feature-mid", "main")
-            .expect_err("non-linear descendants should error");
+        let stack = build_stack_tree(&prs, "feature-mid", "main").expect("tree should build");
+        let heads = stack
+            .prs()
+            .iter()
+            .map(|item| item.head_ref_name.as_str())
+            .collect::<Vec<_>>();
 
         assert_eq!(
-            error,
-            "non-linear stack detected at feature-mid; child candidates: feature-child-a, feature-child-b"
+            heads,
+            vec![
+                "feature-base",
+                "feature-mid",
+                "feature-child-a",
+                "feature-grandchild",
+                "feature-child-b",
+            ]
         );
+        assert_eq!(stack.parent(4).map(|pr| pr.number), Some(101));
+        assert!(!stack.is_linear());
+    }
+
+    #[test]
+    fn includes_sibling_subtrees_of_ancestors() {
+        let prs = vec![
+            pr(100, "feature-base", "main"),
+            pr(101, "feature-mid", "feature-base"),
+            pr(102, "feature-sibling", "feature-base"),
+        ];
+
+        let stack = build_stack_tree(&prs, "feature-mid", "main").expect("tree should build");
+
+        assert_eq!(stack.len(), 3);
+        assert_eq!(stack.parent(2).map(|pr| pr.number), Some(100));
     }
 
     #[test]
@@ -591,7 +1433,7 @@ mod tests {
         ];
 
         let error =
-            build_linear_stack(&prs, "feature-a", "main").expect_err("cycle should be detected");
+            build_stack_tree(&prs, "feature-a", "main").expect_err("cycle should be detected");
 
         assert_eq!(error, "cycle detected in stack at branch feature-a");
     }
@@ -610,8 +1452,9 @@ mod tests {
             pr(103, "feature-top", "feature-mid"),
         ];
 
-        let stack = build_linear_stack(&prs, "feature-mid", "main").expect("stack should build");
+        let stack = build_stack_tree(&prs, "feature-mid", "main").expect("stack should build");
         let heads = stack
+            .prs()
             .iter()
             .map(|item| item.head_ref_name.as_str())
             .collect::<Vec<_>>();
@@ -621,8 +1464,8 @@ mod tests {
     }
 
     #[test]
-    fn closed_pr_does_not_cause_non_linear_error() {
-        // Two children of feature-mid, but one is Closed — should not trigger non-linear error
+    fn closed_pr_does_not_branch_the_stack() {
+        // Two children of feature-mid, but one is Closed — the stack stays linear
         let prs = vec![
             pr(100, "feature-base", "main"),
             pr(101, "feature-mid", "feature-base"),
@@ -635,8 +1478,9 @@ mod tests {
             pr(103, "feature-child-a", "feature-mid"),
         ];
 
-        let stack = build_linear_stack(&prs, "feature-mid", "main").expect("stack should build");
-        assert_eq!(stack.last().unwrap().head_ref_name, "feature-child-a");
+        let stack = build_stack_tree(&prs, "feature-mid", "main").expect("stack should build");
+        assert_eq!(stack.prs().last().unwrap().head_ref_name, "feature-child-a");
+        assert!(stack.is_linear());
     }
 
     #[test]
@@ -705,4 +1549,102 @@ mod tests {
         let parsed = serde_json::from_str::<Vec<PullRequest>>(raw);
         assert!(parsed.is_err(), "malformed list JSON should fail parse");
     }
+
+    #[test]
+    fn parses_allowed_merge_methods_in_github_order() {
+        assert_eq!(
+            parse_allowed_merge_methods("false\ttrue\ttrue\n"),
+            Ok(vec![MergeMethod::Squash, MergeMethod::Rebase])
+        );
+        assert_eq!(
+            parse_allowed_merge_methods("false\tfalse\tfalse"),
+            Ok(Vec::new())
+        );
+        assert!(parse_allowed_merge_methods("true\ttrue").is_err());
+    }
+
+    #[test]
+    fn parses_pr_content_and_skips_team_review_requests() {
+        let raw = br#"{
+            "baseRefName": "feature-a",
+            "title": "Add login",
+            "body": "Adds the login form.",
+            "isDraft": true,
+            "reviewRequests": [{"login": "octocat"}, {"name": "Core", "slug": "core"}],
+            "assignees": [{"login": "hubot"}],
+            "labels": [{"name": "stacked"}]
+        }"#;
+
+        let content = parse_pr_content(raw, "feature-b").expect("content should parse");
+
+        assert_eq!(content.base, "feature-a");
+        assert_eq!(content.head, "feature-b");
+        assert_eq!(content.title, "Add login");
+        assert_eq!(content.body, "Adds the login form.");
+        assert!(content.draft);
+        assert_eq!(content.reviewers, vec!["octocat"]);
+        assert_eq!(content.assignees, vec!["hubot"]);
+        assert_eq!(content.labels, vec!["stacked"]);
+    }
+
+    #[test]
+    fn prefetch_query_aliases_head_and_child_lookups_per_branch() {
+        let query = prefetch_query(2);
+        assert!(
+            query.starts_with("query($owner: String!, $name: String!, $b0: String!, $b1: String!)")
+        );
+        assert!(query.contains("h0: pullRequests(headRefName: $b0, first: 100)"));
+        assert!(query.contains("c1: pullRequests(baseRefName: $b1, first: 100)"));
+        assert!(query.contains(
+            "fragment stackPr on PullRequest { number headRefName baseRefName state isCrossRepository headRepositoryOwner { login } }"
+        ));
+    }
+
+    #[test]
+    fn parse_prefetch_response_splits_lookups_per_branch() {
+        let lookups = parse_prefetch_response(
+            br#"{"data":{"repository":{
+                "h0":{"nodes":[{"number":1,"headRefName":"feature-a","baseRefName":"main","state":"MERGED","isCrossRepository":false}]},
+                "c0":{"nodes":[{"number":2,"headRefName":"feature-b","baseRefName":"feature-a","state":"OPEN","isCrossRepository":false}]},
+                "h1":{"nodes":[]},
+                "c1":{"nodes":[]}
+            }}}"#,
+            2,
+        )
+        .expect("prefetch response should parse");
+
+        assert_eq!(lookups.len(), 2);
+        assert_eq!(lookups[0].0[0].number, 1);
+        assert_eq!(lookups[0].0[0].state, PrState::Merged);
+        assert_eq!(lookups[0].1, vec![pr(2, "feature-b", "feature-a")]);
+        assert!(lookups[1].0.is_empty() && lookups[1].1.is_empty());
+    }
+
+    #[test]
+    fn parse_prefetch_response_reports_graphql_errors() {
+        let err = parse_prefetch_response(
+            br#"{"errors":[{"message":"Could not resolve to a Repository"}]}"#,
+            1,
+        )
+        .expect_err("GraphQL errors should fail the prefetch");
+        assert!(err.contains("Could not resolve to a Repository"));
+    }
+
+    #[test]
+    fn graphql_rejections_are_told_apart_from_other_failures() {
+        assert!(is_graphql_rejection(
+            br#"{"errors":[{"message":"Field 'x' doesn't exist on type 'Repository'","extensions":{"code":"undefinedField"}}]}"#
+        ));
+        assert!(!is_graphql_rejection(
+            br#"{"errors":[{"type":"NOT_FOUND","message":"Could not resolve to a Repository"}]}"#
+        ));
+        assert!(!is_graphql_rejection(
+            br#"{"errors":[{"type":"RATE_LIMITED","message":"API rate limit exceeded"}]}"#
+        ));
+        assert!(!is_graphql_rejection(
+            br#"{"data":{"repository":null},"errors":[{"message":"Something went wrong"}]}"#
+        ));
+        assert!(!is_graphql_rejection(br#"{"data":{"repository":{}}}"#));
+        assert!(!is_graphql_rejection(b""));
+    }
 }
@@ -0,0 +1,585 @@
+//! GitLab merge request forge backend driven by the `glab` CLI.
+//!
+//! Merge requests are mapped onto [`PullRequest`]: the MR `iid` becomes the
+//! number, source and target branches become head and base, and `opened`,
+//! `merged`, and `closed` become the matching [`PrState`].
+
+use serde::Deserialize;
+use std::cell::OnceCell;
+use std::process::Command;
+
+use crate::forge::{self, Forge, ForgeOperation, NewPullRequest, RepoMetadata};
+use crate::github::{self, MergeMethod, PrState, PullRequest};
+use crate::util::{ensure_command_available, shell_word, with_stderr};
+
+/// Forge backend that shells out to the GitLab CLI (`glab`).
+#[derive(Debug, Clone, Default)]
+pub struct GlabCli {
+    host: Option<String>,
+    /// Path of the fork merge requests come from, or `None` for the project itself.
+    fork: Option<String>,
+    /// Project ID of `fork`, resolved on first use.
+    fork_id: OnceCell<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MergeRequest {
+    iid: u64,
+    source_branch: String,
+    target_branch: String,
+    state: String,
+    #[serde(default)]
+    source_project_id: Option<u64>,
+    #[serde(default)]
+    target_project_id: Option<u64>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    reviewers: Vec<GitlabUser>,
+    #[serde(default)]
+    assignees: Vec<GitlabUser>,
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitlabUser {
+    username: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Project {
+    #[serde(default)]
+    id: Option<u64>,
+    path_with_namespace: String,
+    #[serde(default)]
+    default_branch: Option<String>,
+    #[serde(default)]
+    merge_method: Option<String>,
+    #[serde(default)]
+    squash_option: Option<String>,
+}
+
+impl GlabCli {
+    /// Build a backend for the GitLab instance at `host`, or `glab`'s default,
+    /// with merge requests coming from `fork` when stack branches live on a fork.
+    pub fn new(host: Option<String>, fork: Option<String>) -> Self {
+        Self {
+            host,
+            fork,
+            fork_id: OnceCell::new(),
+        }
+    }
+
+    fn glab(&self) -> Command {
+        let mut command = Command::new("glab");
+        if let Some(host) = &self.host {
+            command.env("GITLAB_HOST", host);
+        }
+        command
+    }
+
+    fn api_get(&self, path: &str, context: &str) -> Result<Vec<u8>, String> {
+        let output =
+            self.glab().args(["api", path]).output().map_err(|_| {
+                "failed to run `glab api`; ensure GitLab CLI is installed".to_string()
+            })?;
+
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(with_stderr(context, &output.stderr))
+        }
+    }
+
+    fn project(&self) -> Result<Project, String> {
+        let stdout = self.api_get(
+            "projects/:id",
+            "could not read project metadata via GitLab CLI; ensure `origin` points to GitLab and `glab auth status` succeeds",
+        )?;
+        serde_json::from_slice(&stdout).map_err(|_| {
+            "project metadata lookup returned an invalid result; verify repository metadata on GitLab"
+                .to_string()
+        })
+    }
+
+    /// Return the project ID merge requests of the stack come from, or `None`
+    /// when they come from the target project itself.
+    fn source_project_id(&self) -> Result<Option<u64>, String> {
+        let Some(fork) = &self.fork else {
+            return Ok(None);
+        };
+        if let Some(id) = self.fork_id.get() {
+            return Ok(Some(*id));
+        }
+        let stdout = self.api_get(
+            &format!("projects/{}", encode_query_value(fork)),
+            &format!("could not read fork project {fork} via GitLab CLI"),
+        )?;
+        let id = serde_json::from_slice::<Project>(&stdout)
+            .ok()
+            .and_then(|project| project.id)
+            .ok_or_else(|| format!("fork project lookup for {fork} returned an invalid result"))?;
+        Ok(Some(*self.fork_id.get_or_init(|| id)))
+    }
+
+    fn list_merge_requests(&self, filter: &str, branch: &str) -> Result<Vec<PullRequest>, String> {
+        Ok(self
+            .fetch_merge_requests(filter, branch)?
+            .into_iter()
+            .map(PullRequest::from)
+            .collect())
+    }
+
+    fn fetch_merge_requests(
+        &self,
+        filter: &str,
+        branch: &str,
+    ) -> Result<Vec<MergeRequest>, String> {
+        let path = format!(
+            "projects/:id/merge_requests?{filter}={}&state=all&per_page=100",
+            encode_query_value(branch)
+        );
+        let stdout = self.api_get(
+            &path,
+            &format!("failed to list merge requests for {branch}"),
+        )?;
+        parse_merge_requests(&stdout, self.source_project_id()?)
+    }
+}
+
+impl MergeRequest {
+    fn pr_state(&self) -> PrState {
+        match self.state.as_str() {
+            "merged" => PrState::Merged,
+            "closed" => PrState::Closed,
+            // `locked` is a transient state while GitLab merges an open MR.
+            _ => PrState::Open,
+        }
+    }
+}
+
+impl From<MergeRequest> for PullRequest {
+    fn from(mr: MergeRequest) -> Self {
+        Self {
+            number: mr.iid,
+            state: mr.pr_state(),
+            head_ref_name: mr.source_branch,
+            base_ref_name: mr.target_branch,
+        }
+    }
+}
+
+impl Forge for GlabCli {
+    fn ensure_ready(&self) -> Result<(), String> {
+        ensure_command_available("glab")?;
+
+        let mut command = self.glab();
+        command.args(["auth", "status"]);
+        if let Some(host) = &self.host {
+            command.args(["--hostname", host]);
+        }
+        let output = command.output().map_err(|_| {
+            "failed to run `glab auth status`; install GitLab CLI and authenticate".to_string()
+        })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err("GitLab CLI is not authenticated; run `glab auth login` and retry".to_string())
+        }
+    }
+
+    fn repo_metadata(&self) -> Result<RepoMetadata, String> {
+        let project = self.project()?;
+        let default_branch = project.default_branch.unwrap_or_default();
+        if default_branch.is_empty() {
+            return Err(
+                "default branch lookup returned empty result; verify repository metadata on GitLab"
+                    .to_string(),
+            );
+        }
+        Ok(RepoMetadata {
+            repository: project.path_with_namespace,
+            default_branch,
+        })
+    }
+
+    fn find_pr_for_head(&self, branch: &str) -> Result<Option<PullRequest>, String> {
+        let candidates = self.list_merge_requests("source_branch", branch)?;
+        forge::select_pr_for_head(candidates, branch)
+    }
+
+    fn list_children(&self, base: &str) -> Result<Vec<PullRequest>, String> {
+        self.list_merge_requests("target_branch", base)
+    }
+
+    fn create_pr(&self, pr: &NewPullRequest) -> Result<(), String> {
+        let output = self
+            .glab()
+            .args([
+                "mr",
+                "create",
+                "--source-branch",
+                &pr.head,
+                "--target-branch",
+                &pr.base,
+                "--title",
+                &pr.title,
+                "--description",
+                &pr.body,
+                "--yes",
+            ])
+            .args(self.fork.iter().flat_map(|fork| ["--head", fork.as_str()]))
+            .args(pr.cli_option_args())
+            .output()
+            .map_err(|_| {
+                "failed to run `glab mr create`; ensure GitLab CLI is installed".to_string()
+            })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(with_stderr(
+                &format!(
+                    "failed to create merge request for branch {}; fix the GitLab error and retry",
+                    pr.head
+                ),
+                &output.stderr,
+            ))
+        }
+    }
+
+    fn retarget_pr(&self, branch: &str, new_base: &str) -> Result<(), String> {
+        let output = self
+            .glab()
+            .args(["mr", "update", branch, "--target-branch", new_base])
+            .output()
+            .map_err(|_| {
+                "failed to run `glab mr update`; ensure GitLab CLI is installed".to_string()
+            })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(with_stderr(
+                &format!(
+                    "failed to retarget merge request for branch {branch} to {new_base}; fix the GitLab error and rerun `stck push`"
+                ),
+                &output.stderr,
+            ))
+        }
+    }
+
+    fn pr_body(&self, branch: &str) -> Result<String, String> {
+        self.fetch_merge_requests("source_branch", branch)?
+            .into_iter()
+            .find(|mr| mr.source_branch == branch && mr.pr_state() == PrState::Open)
+            .map(|mr| mr.description.unwrap_or_default())
+            .ok_or_else(|| {
+                format!("failed to read merge request description for branch {branch}; no open merge request exists")
+            })
+    }
+
+    fn pr_content(&self, branch: &str) -> Result<NewPullRequest, String> {
+        self.fetch_merge_requests("source_branch", branch)?
+            .into_iter()
+            .find(|mr| mr.source_branch == branch && mr.pr_state() == PrState::Open)
+            .map(|mr| {
+                let usernames = |users: Vec<GitlabUser>| {
+                    users.into_iter().map(|user| user.username).collect()
+                };
+                NewPullRequest {
+                    base: mr.target_branch,
+                    head: mr.source_branch,
+                    title: mr.title,
+                    body: mr.description.unwrap_or_default(),
+                    draft: mr.draft,
+                    reviewers: usernames(mr.reviewers),
+                    assignees: usernames(mr.assignees),
+                    labels: mr.labels,
+                }
+            })
+            .ok_or_else(|| {
+                format!("failed to read merge request for branch {branch}; no open merge request exists")
+            })
+    }
+
+    fn update_pr_body(&self, branch: &str, body: &str) -> Result<(), String> {
+        let output = self
+            .glab()
+            .args(["mr", "update", branch, "--description", body])
+            .output()
+            .map_err(|_| {
+                "failed to run `glab mr update`; ensure GitLab CLI is installed".to_string()
+            })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(with_stderr(
+                &format!(
+                    "failed to update merge request description for branch {branch}; fix the GitLab error and retry"
+                ),
+                &output.stderr,
+            ))
+        }
+    }
+
+    /// `glab mr close` takes no comment, so the note is added first.
+    fn close_pr(&self, branch: &str, comment: &str) -> Result<(), String> {
+        for (args, action) in [
+            (
+                vec!["mr", "note", branch, "--message", comment],
+                "comment on",
+            ),
+            (vec!["mr", "close", branch], "close"),
+        ] {
+            let output = self.glab().args(&args).output().map_err(|_| {
+                format!(
+                    "failed to run `glab {} {}`; ensure GitLab CLI is installed",
+                    args[0], args[1]
+                )
+            })?;
+            if !output.status.success() {
+                return Err(with_stderr(
+                    &format!(
+                        "failed to {action} merge request for branch {branch}; fix the GitLab error and retry"
+                    ),
+                    &output.stderr,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// GitLab links merge requests as `!<iid>`; `#<iid>` would point at an issue.
+    fn pr_reference(&self, number: u64) -> String {
+        format!("!{number}")
+    }
+
+    /// GitLab fixes the merge strategy per project; squashing is allowed
+    /// unless the project forbids or enforces it.
+    fn allowed_merge_methods(&self) -> Result<Vec<MergeMethod>, String> {
+        let project = self.project()?;
+        Ok(merge_methods_for(
+            project.merge_method.as_deref(),
+            project.squash_option.as_deref(),
+        ))
+    }
+
+    fn merge_pr(&self, branch: &str, method: MergeMethod, head_sha: &str) -> Result<(), String> {
+        let output = self
+            .glab()
+            .args(merge_args(branch, method, head_sha))
+            .output()
+            .map_err(|_| {
+                "failed to run `glab mr merge`; ensure GitLab CLI is installed".to_string()
+            })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(with_stderr(
+                &format!(
+                    "failed to merge merge request for branch {branch}; fix the GitLab error and rerun `stck land`"
+                ),
+                &output.stderr,
+            ))
+        }
+    }
+
+    fn describe(&self, operation: ForgeOperation<'_>) -> String {
+        match operation {
+            ForgeOperation::Create(pr) => format!(
+                "$ glab mr create --source-branch {} --target-branch {} --title {} --description \"<stack context>\" --yes{}{}",
+                pr.head,
+                pr.base,
+                shell_word(&pr.title),
+                self.fork
+                    .as_ref()
+                    .map(|fork| format!(" --head {fork}"))
+                    .unwrap_or_default(),
+                github::describe_option_args(pr)
+            ),
+            ForgeOperation::Retarget { branch, new_base } => {
+                format!("$ glab mr update {branch} --target-branch {new_base}")
+            }
+            ForgeOperation::Merge {
+                branch,
+                method,
+                head_sha,
+            } => format!("$ glab {}", merge_args(branch, method, head_sha).join(" ")),
+            ForgeOperation::UpdateBody { branch } => {
+                format!("$ glab mr update {branch} --description \"<stack navigation>\"")
+            }
+            ForgeOperation::Close { branch, comment } => format!(
+                "$ glab mr note {branch} --message {}\n$ glab mr close {branch}",
+                shell_word(comment)
+            ),
+        }
+    }
+}
+
+fn merge_args<'a>(branch: &'a str, method: MergeMethod, head_sha: &'a str) -> Vec<&'a str> {
+    let mut args = vec!["mr", "merge", branch, "--sha", head_sha, "--yes"];
+    match method {
+        MergeMethod::Merge => {}
+        MergeMethod::Squash => args.push("--squash"),
+        MergeMethod::Rebase => args.push("--rebase"),
+    }
+    args
+}
+
+fn merge_methods_for(merge_method: Option<&str>, squash_option: Option<&str>) -> Vec<MergeMethod> {
+    // `ff` projects only fast-forward, which matches stck's rebase method;
+    // `merge` and `rebase_merge` both create a merge commit.
+    let project_method = match merge_method {
+        Some("ff") => MergeMethod::Rebase,
+        _ => MergeMethod::Merge,
+    };
+    match squash_option {
+        Some("never") => vec![project_method],
+        Some("always") => vec![MergeMethod::Squash],
+        _ if project_method == MergeMethod::Merge => {
+            vec![MergeMethod::Merge, MergeMethod::Squash]
+        }
+        _ => vec![MergeMethod::Squash, project_method],
+    }
+}
+
+/// Parse merge requests, keeping those from `source_project` (the fork stack
+/// branches live on), or from the target project itself when `None`.
+fn parse_merge_requests(
+    bytes: &[u8],
+    source_project: Option<u64>,
+) -> Result<Vec<MergeRequest>, String> {
+    let merge_requests = serde_json::from_slice::<Vec<MergeRequest>>(bytes)
+        .map_err(|_| "failed to parse merge request metadata from GitLab CLI output".to_string())?;
+    Ok(merge_requests
+        .into_iter()
+        // Merge requests from other forks share branch names with ours but are
+        // not part of the stack.
+        .filter(|mr| match source_project {
+            Some(id) => mr.source_project_id == Some(id),
+            None => mr.source_project_id == mr.target_project_id,
+        })
+        .collect())
+}
+
+fn encode_query_value(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_query_value, merge_methods_for, parse_merge_requests};
+    use crate::github::{MergeMethod, PrState, PullRequest};
+
+    #[test]
+    fn parse_merge_requests_reads_review_metadata() {
+        let mrs = parse_merge_requests(
+            br#"[{"iid":4,"source_branch":"feature-b","target_branch":"feature-a","state":"opened","title":"Add login","draft":true,"reviewers":[{"username":"alice"}],"assignees":[{"username":"bob"}],"labels":["stacked"]}]"#,
+            None,
+        )
+        .expect("merge requests should parse");
+
+        assert_eq!(mrs[0].title, "Add login");
+        assert!(mrs[0].draft);
+        assert_eq!(mrs[0].reviewers[0].username, "alice");
+        assert_eq!(mrs[0].assignees[0].username, "bob");
+        assert_eq!(mrs[0].labels, vec!["stacked"]);
+    }
+
+    #[test]
+    fn parse_merge_requests_maps_states_and_skips_forks() {
+        let prs: Vec<PullRequest> = parse_merge_requests(
+            br#"[
+                {"iid":3,"source_branch":"feature-a","target_branch":"main","state":"merged","source_project_id":1,"target_project_id":1},
+                {"iid":4,"source_branch":"feature-b","target_branch":"feature-a","state":"opened","source_project_id":1,"target_project_id":1},
+                {"iid":5,"source_branch":"feature-c","target_branch":"feature-a","state":"closed","source_project_id":1,"target_project_id":1},
+                {"iid":6,"source_branch":"feature-b","target_branch":"feature-a","state":"opened","source_project_id":9,"target_project_id":1}
+            ]"#,
+            None,
+        )
+        .expect("merge requests should parse")
+        .into_iter()
+        .map(PullRequest::from)
+        .collect();
+
+        assert_eq!(
+            prs,
+            vec![
+                PullRequest {
+                    number: 3,
+                    head_ref_name: "feature-a".to_string(),
+                    base_ref_name: "main".to_string(),
+                    state: PrState::Merged,
+                },
+                PullRequest {
+                    number: 4,
+                    head_ref_name: "feature-b".to_string(),
+                    base_ref_name: "feature-a".to_string(),
+                    state: PrState::Open,
+                },
+                PullRequest {
+                    number: 5,
+                    head_ref_name: "feature-c".to_string(),
+                    base_ref_name: "feature-a".to_string(),
+                    state: PrState::Closed,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_merge_requests_keeps_only_the_fork_source_project() {
+        let iids = parse_merge_requests(
+            br#"[
+                {"iid":4,"source_branch":"feature-b","target_branch":"main","state":"opened","source_project_id":1,"target_project_id":1},
+                {"iid":6,"source_branch":"feature-b","target_branch":"main","state":"opened","source_project_id":9,"target_project_id":1}
+            ]"#,
+            Some(9),
+        )
+        .expect("merge requests should parse")
+        .into_iter()
+        .map(|mr| mr.iid)
+        .collect::<Vec<_>>();
+
+        assert_eq!(iids, vec![6]);
+    }
+
+    #[test]
+    fn merge_methods_follow_project_settings() {
+        assert_eq!(
+            merge_methods_for(Some("merge"), Some("default_off")),
+            vec![MergeMethod::Merge, MergeMethod::Squash]
+        );
+        assert_eq!(
+            merge_methods_for(Some("ff"), Some("never")),
+            vec![MergeMethod::Rebase]
+        );
+        assert_eq!(
+            merge_methods_for(Some("rebase_merge"), Some("always")),
+            vec![MergeMethod::Squash]
+        );
+    }
+
+    #[test]
+    fn encode_query_value_escapes_branch_separators() {
+        assert_eq!(encode_query_value("team/feature-a"), "team%2Ffeature-a");
+    }
+}
@@ -1,46 +1,85 @@
 //! Git subprocess helpers used by stack planning and command execution.
 
+use std::io::Write;
 use std::process::{Command, Stdio};
 use std::{env, path::PathBuf};
 
 use crate::util::with_stderr;
 
-/// Fetch updated refs from the `origin` remote.
-pub fn fetch_origin() -> Result<(), String> {
+/// Remotes stck reads from and pushes to, as configured by `remote` and
+/// `push-remote`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remotes {
+    /// The remote PRs target and the trunk branch is read from.
+    pub base: String,
+    /// The remote stack branches are pushed to, which is `base` unless a
+    /// separate push remote (e.g. a fork) is configured.
+    pub push: String,
+}
+
+impl Remotes {
+    /// Return whether stack branches live on a different remote than the one
+    /// PRs target.
+    pub fn is_triangular(&self) -> bool {
+        self.base != self.push
+    }
+
+    /// Return the base remote-tracking ref of `branch`, e.g. `refs/remotes/origin/main`.
+    pub fn base_ref(&self, branch: &str) -> String {
+        format!("refs/remotes/{}/{branch}", self.base)
+    }
+
+    /// Return the push remote-tracking ref of `branch`, e.g. `refs/remotes/origin/<branch>`.
+    pub fn push_ref(&self, branch: &str) -> String {
+        format!("refs/remotes/{}/{branch}", self.push)
+    }
+}
+
+/// Fetch updated refs from the base remote and, when it differs, the push remote.
+pub fn fetch_remotes(remotes: &Remotes) -> Result<(), String> {
+    fetch(&remotes.base)?;
+    if remotes.is_triangular() {
+        fetch(&remotes.push)?;
+    }
+    Ok(())
+}
+
+fn fetch(remote: &str) -> Result<(), String> {
     let output = Command::new("git")
-        .args(["fetch", "origin"])
+        .args(["fetch", remote])
         .output()
         .map_err(|_| {
-            "failed to run `git fetch origin`; ensure this is a git repository".to_string()
+            format!("failed to run `git fetch {remote}`; ensure this is a git repository")
         })?;
 
     if output.status.success() {
         Ok(())
     } else {
         Err(with_stderr(
-            "failed to fetch from `origin`; check remote connectivity and permissions",
+            &format!("failed to fetch from `{remote}`; check remote connectivity and permissions"),
             &output.stderr,
         ))
     }
 }
 
-/// List branch names advertised by the fetched `origin` remote.
+/// List branch names advertised by the fetched push remote.
 ///
-/// The symbolic `origin/HEAD` ref is excluded because it is an alias for the
+/// The symbolic `<remote>/HEAD` ref is excluded because it is an alias for the
 /// default branch rather than a candidate stack branch.
-pub fn list_origin_branches() -> Result<Vec<String>, String> {
+pub fn list_remote_branches(remotes: &Remotes) -> Result<Vec<String>, String> {
+    let remote = &remotes.push;
     let output = Command::new("git")
         .args([
             "for-each-ref",
             "--format=%(refname:strip=3)",
-            "refs/remotes/origin",
+            &format!("refs/remotes/{remote}"),
         ])
         .output()
         .map_err(|_| "failed to run `git for-each-ref`".to_string())?;
 
     if !output.status.success() {
         return Err(with_stderr(
-            "failed to list branches from `origin`",
+            &format!("failed to list branches from `{remote}`"),
             &output.stderr,
         ));
     }
@@ -55,13 +94,56 @@ pub fn list_origin_branches() -> Result<Vec<String>, String> {
     Ok(branches)
 }
 
-/// Return whether the local branch head differs from `origin/<branch>`.
+/// List local branches related to `branch` that are not yet in `default_branch`.
+///
+/// These are the branches `branch` builds on plus the branches built on top of
+/// it, i.e. the likely members of its stack.
+pub fn list_stack_neighbourhood(
+    remotes: &Remotes,
+    branch: &str,
+    default_branch: &str,
+) -> Result<Vec<String>, String> {
+    let default_ref = resolve_onto_ref(remotes, default_branch)?;
+    let mut branches = Vec::new();
+    for relation in ["--merged", "--contains"] {
+        let output = Command::new("git")
+            .args([
+                "for-each-ref",
+                "--format=%(refname:short)",
+                relation,
+                branch,
+                "--no-merged",
+                &default_ref,
+                "refs/heads",
+            ])
+            .output()
+            .map_err(|_| "failed to run `git for-each-ref`".to_string())?;
+
+        if !output.status.success() {
+            return Err(with_stderr(
+                &format!("failed to list branches related to {branch}"),
+                &output.stderr,
+            ));
+        }
+        branches.extend(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|name| !name.is_empty())
+                .map(str::to_string),
+        );
+    }
+    branches.sort();
+    branches.dedup();
+    Ok(branches)
+}
+
+/// Return whether the local branch head differs from `<push remote>/<branch>`.
 ///
 /// Missing remote refs are treated as needing a push so newly created branches
 /// show up as actionable.
-pub fn branch_needs_push(branch: &str) -> Result<bool, String> {
+pub fn branch_needs_push(remotes: &Remotes, branch: &str) -> Result<bool, String> {
     let local_ref = format!("refs/heads/{branch}");
-    let remote_ref = format!("refs/remotes/origin/{branch}");
+    let remote_ref = remotes.push_ref(branch);
 
     let local_sha = rev_parse(&local_ref)?;
     let remote_sha = match rev_parse(&remote_ref) {
@@ -77,18 +159,18 @@ pub fn resolve_ref(reference: &str) -> Result<String, String> {
     rev_parse(reference)
 }
 
-/// Resolve the `--onto` target ref for a rebase, preferring `origin/<branch>`
+/// Resolve the `--onto` target ref for a rebase, preferring `<remote>/<branch>`
 /// over the local ref so the rebase target reflects the fetched remote state.
-pub fn resolve_onto_ref(base_branch: &str) -> Result<String, String> {
-    resolve_base_ref(base_branch)
+pub fn resolve_onto_ref(remotes: &Remotes, base_branch: &str) -> Result<String, String> {
+    resolve_base_ref(remotes, base_branch)
 }
 
-/// Resolve a branch through its fetched `origin` ref, then its local ref.
+/// Resolve a branch through its fetched remote ref, then its local ref.
 ///
 /// Parent discovery uses this to avoid stale or missing local branches while
 /// retaining a fallback for clones without the corresponding tracking ref.
-pub fn resolve_branch_ref_remote_first(branch: &str) -> Result<String, String> {
-    let remote_ref = format!("refs/remotes/origin/{branch}");
+pub fn resolve_branch_ref_remote_first(remotes: &Remotes, branch: &str) -> Result<String, String> {
+    let remote_ref = remotes.push_ref(branch);
     if ref_exists(&remote_ref)? {
         return Ok(remote_ref);
     }
@@ -99,7 +181,8 @@ pub fn resolve_branch_ref_remote_first(branch: &str) -> Result<String, String> {
     }
 
     Err(format!(
-        "could not resolve branch `{branch}` from `origin` or local refs"
+        "could not resolve branch `{branch}` from `{}` or local refs",
+        remotes.push
     ))
 }
 
@@ -108,11 +191,15 @@ pub fn resolve_branch_ref_remote_first(branch: &str) -> Result<String, String> {
 /// This prefers the merge-base between `branch` and `base_branch` so sync can
 /// recover from squash merges and rewritten ancestry. If no merge-base can be
 /// found, the resolved base branch ref is used as a fallback.
-pub fn resolve_old_base_for_rebase(base_branch: &str, branch: &str) -> Result<String, String> {
+pub fn resolve_old_base_for_rebase(
+    remotes: &Remotes,
+    base_branch: &str,
+    branch: &str,
+) -> Result<String, String> {
     // Try merge-base between the branch and the old base ref to find the true
     // fork point. This handles squash-merge and rewritten-ancestry scenarios
     // where the base branch tip may have moved past the actual divergence point.
-    let base_ref = resolve_base_ref(base_branch)?;
+    let base_ref = resolve_base_ref(remotes, base_branch)?;
     let branch_ref = format!("refs/heads/{branch}");
     if let Ok(fork) = merge_base(&base_ref, &branch_ref) {
         return Ok(fork);
@@ -122,14 +209,36 @@ pub fn resolve_old_base_for_rebase(base_branch: &str, branch: &str) -> Result<St
     rev_parse(&base_ref)
 }
 
-fn resolve_base_ref(base_branch: &str) -> Result<String, String> {
+/// Return the commit `branch` forked from its stack parent `parent`.
+///
+/// Unlike [`resolve_old_base_for_rebase`] this prefers the local parent
+/// branch, which stack edits treat as the source of truth for the commits
+/// already below `branch`.
+pub fn fork_point(remotes: &Remotes, parent: &str, branch: &str) -> Result<String, String> {
+    let local_ref = format!("refs/heads/{parent}");
+    let parent_ref = if ref_exists(&local_ref)? {
+        local_ref
+    } else {
+        resolve_base_ref(remotes, parent)?
+    };
+    merge_base(&parent_ref, &format!("refs/heads/{branch}"))
+}
+
+fn resolve_base_ref(remotes: &Remotes, base_branch: &str) -> Result<String, String> {
     // Prefer the remote ref because `stck sync` fetches before planning.
     // Using the local ref for shared branches like the default branch can
     // produce a stale merge-base when the remote has advanced (e.g. after a
     // PR merge on GitHub while local main has not been pulled).
-    let remote_ref = format!("refs/remotes/origin/{base_branch}");
-    if ref_exists(&remote_ref)? {
-        return Ok(remote_ref);
+    // The base remote wins so a stale trunk copy on a fork is never used;
+    // parent stack branches usually exist only on the push remote.
+    let mut remote_refs = vec![remotes.base_ref(base_branch)];
+    if remotes.is_triangular() {
+        remote_refs.push(remotes.push_ref(base_branch));
+    }
+    for remote_ref in remote_refs {
+        if ref_exists(&remote_ref)? {
+            return Ok(remote_ref);
+        }
     }
 
     let local_ref = format!("refs/heads/{base_branch}");
@@ -138,7 +247,8 @@ fn resolve_base_ref(base_branch: &str) -> Result<String, String> {
     }
 
     Err(format!(
-        "could not resolve old base branch `{base_branch}` locally or on origin; fetch and/or restore the branch, then rerun `stck sync`"
+        "could not resolve old base branch `{base_branch}` locally or on {}; fetch and/or restore the branch, then rerun `stck sync`",
+        remotes.push
     ))
 }
 
@@ -213,10 +323,14 @@ pub fn rebase_in_progress() -> Result<bool, String> {
 
 /// Return whether `branch` is behind the fetched remote default branch.
 ///
-/// This uses `origin/<default_branch>` and therefore expects callers to fetch
-/// before relying on the result.
-pub fn branch_needs_sync_with_default(default_branch: &str, branch: &str) -> Result<bool, String> {
-    let default_ref = format!("refs/remotes/origin/{default_branch}");
+/// This uses `<base remote>/<default_branch>` and therefore expects callers to
+/// fetch before relying on the result.
+pub fn branch_needs_sync_with_default(
+    remotes: &Remotes,
+    default_branch: &str,
+    branch: &str,
+) -> Result<bool, String> {
+    let default_ref = remotes.base_ref(default_branch);
     let branch_ref = format!("refs/heads/{branch}");
     let output = Command::new("git")
         .args(["merge-base", "--is-ancestor", &default_ref, &branch_ref])
@@ -253,14 +367,14 @@ pub fn rebase_onto(new_base: &str, old_base: &str, branch: &str) -> Result<(), S
     }
 }
 
-/// Push `branch` to `origin` as a regular (fast-forward) push.
+/// Push `branch` to the push remote as a regular (fast-forward) push.
 ///
 /// Unlike [`push_force_with_lease`] this does **not** rewrite remote history.
 /// A non-fast-forward push will fail, which is the desired safety behaviour
 /// when the caller simply wants to publish new local commits.
-pub fn push_branch(branch: &str) -> Result<(), String> {
+pub fn push_branch(remotes: &Remotes, branch: &str) -> Result<(), String> {
     let status = Command::new("git")
-        .args(["push", "origin", branch])
+        .args(["push", &remotes.push, branch])
         .stderr(Stdio::inherit())
         .status()
         .map_err(|_| "failed to run `git push`; ensure this is a git repository".to_string())?;
@@ -274,12 +388,13 @@ pub fn push_branch(branch: &str) -> Result<(), String> {
     }
 }
 
-/// Push `branch` to `origin` with an explicit expected remote branch tip.
+/// Push `branch` to the push remote with an explicit expected remote branch tip.
 ///
 /// `None` means the remote branch must still be absent. Pinning the lease to a
 /// specific expected state prevents an intervening fetch from weakening the
 /// overwrite protection.
 pub fn push_force_with_lease(
+    remotes: &Remotes,
     branch: &str,
     expected_remote_head: Option<&str>,
 ) -> Result<(), String> {
@@ -288,7 +403,7 @@ pub fn push_force_with_lease(
         expected_remote_head.unwrap_or_default()
     );
     let status = Command::new("git")
-        .args(["push", &lease, "origin", branch])
+        .args(["push", &lease, &remotes.push, branch])
         .stderr(Stdio::inherit())
         .status()
         .map_err(|_| "failed to run `git push`; ensure this is a git repository".to_string())?;
@@ -302,6 +417,30 @@ pub fn push_force_with_lease(
     }
 }
 
+/// Delete `branch` from the push remote, failing unless its tip is still
+/// `expected_remote_head`.
+pub fn delete_remote_branch(
+    remotes: &Remotes,
+    branch: &str,
+    expected_remote_head: &str,
+) -> Result<(), String> {
+    let lease = format!("--force-with-lease=refs/heads/{branch}:{expected_remote_head}");
+    let status = Command::new("git")
+        .args(["push", &lease, &remotes.push, "--delete", branch])
+        .stderr(Stdio::inherit())
+        .status()
+        .map_err(|_| "failed to run `git push`; ensure this is a git repository".to_string())?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "failed to delete branch {branch} from `{}`",
+            remotes.push
+        ))
+    }
+}
+
 /// Return whether `branch` has an upstream tracking branch configured.
 pub fn branch_has_upstream(branch: &str) -> Result<bool, String> {
     let output = Command::new("git")
@@ -326,24 +465,232 @@ pub fn local_branch_exists(branch: &str) -> Result<bool, String> {
     ref_exists(&format!("refs/heads/{branch}"))
 }
 
-/// Return whether `origin/<branch>` exists locally.
-pub fn remote_branch_exists(branch: &str) -> Result<bool, String> {
-    ref_exists(&format!("refs/remotes/origin/{branch}"))
+/// Return whether `<push remote>/<branch>` exists locally.
+pub fn remote_branch_exists(remotes: &Remotes, branch: &str) -> Result<bool, String> {
+    ref_exists(&remotes.push_ref(branch))
 }
 
-/// Resolve the fetched `origin/<branch>` tip, or return `None` when absent.
-pub fn remote_branch_head(branch: &str) -> Result<Option<String>, String> {
-    let remote_ref = format!("refs/remotes/origin/{branch}");
+/// Resolve the fetched `<push remote>/<branch>` tip, or return `None` when absent.
+pub fn remote_branch_head(remotes: &Remotes, branch: &str) -> Result<Option<String>, String> {
+    let remote_ref = remotes.push_ref(branch);
     if !ref_exists(&remote_ref)? {
         return Ok(None);
     }
     resolve_ref(&remote_ref).map(Some)
 }
 
-/// Push `branch` to `origin` and configure it as the upstream branch.
-pub fn push_set_upstream(branch: &str) -> Result<(), String> {
+/// Return the URL configured for `remote`.
+pub fn remote_url(remote: &str) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(["remote", "get-url", remote])
+        .output()
+        .map_err(|_| {
+            format!("failed to run `git remote get-url {remote}`; ensure this is a git repository")
+        })?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(with_stderr(
+            &format!("failed to read the `{remote}` remote URL"),
+            &output.stderr,
+        ))
+    }
+}
+
+/// Read a single git config value, returning `None` when it is unset or empty.
+pub fn config_value(key: &str) -> Result<Option<String>, String> {
+    let output = Command::new("git")
+        .args(["config", "--get", key])
+        .output()
+        .map_err(|_| "failed to run `git config`; ensure this is a git repository".to_string())?;
+
+    // `git config --get` exits with 1 when the key is unset.
+    match output.status.code() {
+        Some(0) => {
+            let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            Ok((!value.is_empty()).then_some(value))
+        }
+        Some(1) => Ok(None),
+        _ => Err(with_stderr(
+            &format!("failed to read `{key}` from git config"),
+            &output.stderr,
+        )),
+    }
+}
+
+/// Write `value` to the repository's local git config under `key`.
+pub fn set_config_value(key: &str, value: &str) -> Result<(), String> {
+    let output = Command::new("git")
+        .args(["config", "--local", key, value])
+        .output()
+        .map_err(|_| "failed to run `git config`; ensure this is a git repository".to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(with_stderr(
+            &format!("failed to write `{key}` to git config"),
+            &output.stderr,
+        ))
+    }
+}
+
+/// Return the top-level directory of the current worktree, if inside one.
+pub fn repo_root() -> Result<Option<PathBuf>, String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .map_err(|_| "failed to run `git rev-parse --show-toplevel`".to_string())?;
+
+    let root = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok((output.status.success() && !root.is_empty()).then(|| PathBuf::from(root)))
+}
+
+/// Stack parent recorded in git config for a local branch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalParent {
+    /// Branch whose parent was recorded.
+    pub branch: String,
+    /// Branch the recorded branch is stacked on.
+    pub parent: String,
+    /// Parent commit the branch was created or last restacked on, if recorded.
+    pub fork_point: Option<String>,
+}
+
+/// Record `parent` and `fork_point` as the stack parent of `branch`.
+///
+/// The values are stored as `branch.<name>.stck-parent` and
+/// `branch.<name>.stck-fork-point` so they follow the branch in git config.
+pub fn record_local_parent(branch: &str, parent: &str, fork_point: &str) -> Result<(), String> {
+    for (key, value) in [("stck-parent", parent), ("stck-fork-point", fork_point)] {
+        let output = Command::new("git")
+            .args(["config", &format!("branch.{branch}.{key}"), value])
+            .output()
+            .map_err(|_| {
+                "failed to run `git config`; ensure this is a git repository".to_string()
+            })?;
+
+        if !output.status.success() {
+            return Err(with_stderr(
+                &format!("failed to record stack parent for branch {branch}"),
+                &output.stderr,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Set the recorded stack parent and fork point of `branch` to exactly these
+/// values, dropping whichever is `None`.
+pub fn set_local_parent(
+    branch: &str,
+    parent: Option<&str>,
+    fork_point: Option<&str>,
+) -> Result<(), String> {
+    forget_local_parent(branch)?;
+    for (key, value) in [("stck-parent", parent), ("stck-fork-point", fork_point)] {
+        let Some(value) = value else {
+            continue;
+        };
+        let output = Command::new("git")
+            .args(["config", &format!("branch.{branch}.{key}"), value])
+            .output()
+            .map_err(|_| {
+                "failed to run `git config`; ensure this is a git repository".to_string()
+            })?;
+
+        if !output.status.success() {
+            return Err(with_stderr(
+                &format!("failed to restore stack parent for branch {branch}"),
+                &output.stderr,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Drop the stack parent recorded for `branch`, if any.
+pub fn forget_local_parent(branch: &str) -> Result<(), String> {
+    for key in ["stck-parent", "stck-fork-point"] {
+        let output = Command::new("git")
+            .args(["config", "--unset-all", &format!("branch.{branch}.{key}")])
+            .output()
+            .map_err(|_| {
+                "failed to run `git config`; ensure this is a git repository".to_string()
+            })?;
+
+        // Exit code 5 means the key was not set.
+        if !output.status.success() && output.status.code() != Some(5) {
+            return Err(with_stderr(
+                &format!("failed to forget stack parent for branch {branch}"),
+                &output.stderr,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Return the stack parent recorded for `branch`, if any.
+pub fn local_parent(branch: &str) -> Result<Option<LocalParent>, String> {
+    Ok(list_local_parents()?
+        .into_iter()
+        .find(|local| local.branch == branch))
+}
+
+/// List every stack parent recorded in git config.
+pub fn list_local_parents() -> Result<Vec<LocalParent>, String> {
     let output = Command::new("git")
-        .args(["push", "-u", "origin", branch])
+        .args([
+            "config",
+            "--get-regexp",
+            r"^branch\..*\.stck-(parent|fork-point)$",
+        ])
+        .output()
+        .map_err(|_| "failed to run `git config`; ensure this is a git repository".to_string())?;
+
+    // `git config --get-regexp` exits with 1 when nothing matches.
+    match output.status.code() {
+        Some(0) | Some(1) => {}
+        _ => {
+            return Err(with_stderr(
+                "failed to read recorded stack parents from git config",
+                &output.stderr,
+            ))
+        }
+    }
+
+    let mut parents: Vec<LocalParent> = Vec::new();
+    let mut fork_points = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((key, value)) = line.split_once(' ') else {
+            continue;
+        };
+        let Some(key) = key.strip_prefix("branch.") else {
+            continue;
+        };
+        if let Some(branch) = key.strip_suffix(".stck-parent") {
+            parents.push(LocalParent {
+                branch: branch.to_string(),
+                parent: value.to_string(),
+                fork_point: None,
+            });
+        } else if let Some(branch) = key.strip_suffix(".stck-fork-point") {
+            fork_points.push((branch.to_string(), value.to_string()));
+        }
+    }
+    for (branch, fork_point) in fork_points {
+        if let Some(local) = parents.iter_mut().find(|local| local.branch == branch) {
+            local.fork_point = Some(fork_point);
+        }
+    }
+    Ok(parents)
+}
+
+/// Push `branch` to the push remote and set it as the upstream branch.
+pub fn push_set_upstream(remotes: &Remotes, branch: &str) -> Result<(), String> {
+    let output = Command::new("git")
+        .args(["push", "-u", &remotes.push, branch])
         .output()
         .map_err(|_| "failed to run `git push -u`; ensure this is a git repository".to_string())?;
 
@@ -378,6 +725,74 @@ pub fn checkout_new_branch(branch: &str) -> Result<(), String> {
     }
 }
 
+/// Create a local branch at `start_point` without checking it out.
+pub fn create_branch(branch: &str, start_point: &str) -> Result<(), String> {
+    let output = Command::new("git")
+        .args(["branch", branch, start_point])
+        .output()
+        .map_err(|_| "failed to run `git branch`; ensure this is a git repository".to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(with_stderr(
+            &format!(
+                "failed to create branch {branch}; ensure the branch name is valid and does not already exist"
+            ),
+            &output.stderr,
+        ))
+    }
+}
+
+/// Return the checked-out branch, or `None` on a detached HEAD.
+pub fn checked_out_branch() -> Result<Option<String>, String> {
+    let output = Command::new("git")
+        .args(["symbolic-ref", "--quiet", "--short", "HEAD"])
+        .output()
+        .map_err(|_| {
+            "failed to determine current branch; ensure this is a git repository".to_string()
+        })?;
+
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string()))
+}
+
+/// Point `branch`, which must not be checked out, at `target`.
+pub fn force_branch(branch: &str, target: &str) -> Result<(), String> {
+    let output = Command::new("git")
+        .args(["branch", "--force", branch, target])
+        .output()
+        .map_err(|_| "failed to run `git branch`; ensure this is a git repository".to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(with_stderr(
+            &format!("failed to move branch {branch} to {target}"),
+            &output.stderr,
+        ))
+    }
+}
+
+/// Point the checked-out branch at `target`, keeping uncommitted changes.
+pub fn reset_keep(target: &str) -> Result<(), String> {
+    let output = Command::new("git")
+        .args(["reset", "--keep", target])
+        .output()
+        .map_err(|_| "failed to run `git reset`; ensure this is a git repository".to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(with_stderr(
+            &format!("failed to reset the current branch to {target}"),
+            &output.stderr,
+        ))
+    }
+}
+
 /// Check out an existing local branch.
 pub fn checkout_branch(branch: &str) -> Result<(), String> {
     let output = Command::new("git")
@@ -395,6 +810,240 @@ pub fn checkout_branch(branch: &str) -> Result<(), String> {
     }
 }
 
+/// Rename the local branch `from` to `to`, carrying its `branch.<name>.*` config along.
+pub fn rename_branch(from: &str, to: &str) -> Result<(), String> {
+    let output = Command::new("git")
+        .args(["branch", "-m", from, to])
+        .output()
+        .map_err(|_| "failed to run `git branch`; ensure this is a git repository".to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(with_stderr(
+            &format!("failed to rename branch {from} to {to}"),
+            &output.stderr,
+        ))
+    }
+}
+
+/// Delete the local branch `branch` (and its `branch.<name>.*` config),
+/// whether or not it is merged.
+pub fn delete_branch(branch: &str) -> Result<(), String> {
+    let output = Command::new("git")
+        .args(["branch", "-D", branch])
+        .output()
+        .map_err(|_| "failed to run `git branch`; ensure this is a git repository".to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(with_stderr(
+            &format!("failed to delete branch {branch}"),
+            &output.stderr,
+        ))
+    }
+}
+
+/// List every local branch with its head, sorted by branch name.
+pub fn local_branch_heads() -> Result<Vec<(String, String)>, String> {
+    let output = Command::new("git")
+        .args([
+            "for-each-ref",
+            "--format=%(refname:strip=2) %(objectname)",
+            "refs/heads",
+        ])
+        .output()
+        .map_err(|_| {
+            "failed to run `git for-each-ref`; ensure this is a git repository".to_string()
+        })?;
+    if !output.status.success() {
+        return Err(with_stderr("failed to list local branches", &output.stderr));
+    }
+
+    let mut heads = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(branch, head)| (branch.to_string(), head.to_string()))
+        .collect::<Vec<_>>();
+    heads.sort();
+    Ok(heads)
+}
+
+/// Namespace holding branch heads saved before a sync rewrites them.
+const BACKUP_NAMESPACE: &str = "refs/stck/backup/";
+
+fn backup_ref(id: &str, branch: &str) -> String {
+    format!("{BACKUP_NAMESPACE}{id}/{branch}")
+}
+
+/// Keep the saved `(branch, head)` pairs of backup `id` reachable under
+/// `refs/stck/backup/<id>/` so garbage collection cannot drop them.
+pub fn create_backup_refs(id: &str, heads: &[(String, String)]) -> Result<(), String> {
+    let commands = heads
+        .iter()
+        .map(|(branch, sha)| format!("create {} {sha}", backup_ref(id, branch)))
+        .collect::<Vec<_>>();
+    update_refs(&commands)
+        .map_err(|message| format!("failed to save branch backup {id}; {message}"))
+}
+
+/// Delete the refs of backup `id`.
+pub fn delete_backup_refs(id: &str, heads: &[(String, String)]) -> Result<(), String> {
+    let commands = heads
+        .iter()
+        .map(|(branch, sha)| format!("delete {} {sha}", backup_ref(id, branch)))
+        .collect::<Vec<_>>();
+    update_refs(&commands)
+        .map_err(|message| format!("failed to delete branch backup {id}; {message}"))
+}
+
+/// A branch `stck undo` moves back to its saved head.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchRestore {
+    /// Branch to move.
+    pub branch: String,
+    /// Head saved before the operation.
+    pub saved: String,
+    /// Head the branch must still be at, as the operation left it.
+    pub expected: String,
+}
+
+/// Move every branch back to its saved head and drop backup `id`, all in one
+/// ref transaction.
+///
+/// Each update carries the expected head as its old value, so the whole
+/// transaction fails if any branch moved in the meantime. The index and
+/// working tree of a checked-out branch are left untouched; callers reset
+/// them afterwards.
+pub fn restore_backup(id: &str, restores: &[BranchRestore]) -> Result<(), String> {
+    let mut commands = restores
+        .iter()
+        .map(|restore| {
+            format!(
+                "update refs/heads/{} {} {}",
+                restore.branch, restore.saved, restore.expected
+            )
+        })
+        .collect::<Vec<_>>();
+    commands.extend(restores.iter().map(|restore| {
+        format!(
+            "delete {} {}",
+            backup_ref(id, &restore.branch),
+            restore.saved
+        )
+    }));
+    update_refs(&commands)
+        .map_err(|message| format!("failed to restore branch backup {id}; {message}"))
+}
+
+/// Apply `git update-ref --stdin` commands atomically: all of them or none.
+fn update_refs(commands: &[String]) -> Result<(), String> {
+    let mut child = Command::new("git")
+        .args(["update-ref", "--stdin"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|_| {
+            "failed to run `git update-ref`; ensure this is a git repository".to_string()
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let mut input = commands.join("\n");
+        input.push('\n');
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|_| "failed to pass ref updates to `git update-ref`".to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|_| {
+        "failed to run `git update-ref`; ensure this is a git repository".to_string()
+    })?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(with_stderr(
+            "`git update-ref` rejected the updates",
+            &output.stderr,
+        ))
+    }
+}
+
+/// Abort the native rebase in progress, returning to the branch it started on.
+pub fn abort_rebase() -> Result<(), String> {
+    let output = Command::new("git")
+        .args(["rebase", "--abort"])
+        .output()
+        .map_err(|_| "failed to run `git rebase`; ensure this is a git repository".to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(with_stderr(
+            "failed to abort the rebase in progress",
+            &output.stderr,
+        ))
+    }
+}
+
+/// Make the index and working tree match `HEAD`, discarding any differences.
+///
+/// Only call this on a clean working tree whose branch ref was moved underneath
+/// it, so nothing the user wrote is lost.
+pub fn reset_hard_to_head() -> Result<(), String> {
+    let output = Command::new("git")
+        .args(["reset", "--hard", "--quiet", "HEAD"])
+        .output()
+        .map_err(|_| "failed to run `git reset`; ensure this is a git repository".to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(with_stderr(
+            "failed to update the working tree to the restored branch",
+            &output.stderr,
+        ))
+    }
+}
+
+/// A commit listed by [`branch_commits`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitSummary {
+    /// Full object SHA.
+    pub sha: String,
+    /// First line of the commit message.
+    pub subject: String,
+}
+
+/// List the commits on `branch` after the commit `base`, oldest first.
+pub fn branch_commits(base: &str, branch: &str) -> Result<Vec<CommitSummary>, String> {
+    let output = Command::new("git")
+        .args([
+            "log",
+            "--reverse",
+            "--format=%H%x09%s",
+            &format!("{base}..refs/heads/{branch}"),
+        ])
+        .output()
+        .map_err(|_| "failed to run `git log`".to_string())?;
+
+    if !output.status.success() {
+        return Err(with_stderr(
+            &format!("failed to list the commits of {branch}"),
+            &output.stderr,
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(sha, subject)| CommitSummary {
+            sha: sha.to_string(),
+            subject: subject.to_string(),
+        })
+        .collect())
+}
+
 /// Return whether `head` contains commits not present on `base`.
 pub fn has_commits_between(base: &str, head: &str) -> Result<bool, String> {
     let output = Command::new("git")
@@ -419,6 +1068,38 @@ pub fn has_commits_between(base: &str, head: &str) -> Result<bool, String> {
     Ok(count > 0)
 }
 
+/// Return the subject of the oldest commit on `branch` that is not on `base`.
+///
+/// `base` is resolved through its remote ref first, like parent discovery.
+pub fn first_commit_subject(
+    remotes: &Remotes,
+    base: &str,
+    branch: &str,
+) -> Result<Option<String>, String> {
+    let base_ref = resolve_branch_ref_remote_first(remotes, base)?;
+    let output = Command::new("git")
+        .args([
+            "log",
+            "--reverse",
+            "--format=%s",
+            &format!("{base_ref}..refs/heads/{branch}"),
+        ])
+        .output()
+        .map_err(|_| "failed to run `git log`".to_string())?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "failed to read commits of {branch} on top of {base}; ensure both branches exist"
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .find(|subject| !subject.is_empty())
+        .map(str::to_string))
+}
+
 fn rev_parse(reference: &str) -> Result<String, String> {
     let output = Command::new("git")
         .args(["rev-parse", "--verify", reference])
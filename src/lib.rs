@@ -11,8 +11,12 @@
 
 mod cli;
 mod commands;
+mod config;
 mod env;
+mod forge;
+mod gitea;
 mod github;
+mod gitlab;
 mod gitops;
 mod stack;
 mod sync_state;
@@ -1,27 +1,158 @@
 //! Pure stack-planning helpers derived from GitHub PR metadata.
 
 use crate::github::{PrState, PullRequest};
+use crate::gitops::LocalParent;
 use serde::{Deserialize, Serialize};
 
+/// A discovered stack of pull requests, shaped as a tree rooted on the default branch.
+///
+/// Pull requests are stored in topological order: every parent precedes its
+/// children, and siblings keep the order they were inserted in. A linear stack
+/// is the special case where every node's parent is the node right before it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StackTree {
+    prs: Vec<PullRequest>,
+    parents: Vec<Option<usize>>,
+}
+
+impl StackTree {
+    /// Test-only builder for a linear stack where each PR's intended parent is
+    /// the previous PR, regardless of its base ref.
+    #[cfg(test)]
+    pub fn linear(prs: Vec<PullRequest>) -> Self {
+        let parents = (0..prs.len()).map(|index| index.checked_sub(1)).collect();
+        Self { prs, parents }
+    }
+
+    /// Append `pr` under the node at `parent`, or as a root when `None`, and
+    /// return its index.
+    ///
+    /// Callers must push parents before their children.
+    pub fn push(&mut self, pr: PullRequest, parent: Option<usize>) -> usize {
+        debug_assert!(parent.is_none_or(|parent| parent < self.prs.len()));
+        self.prs.push(pr);
+        self.parents.push(parent);
+        self.prs.len() - 1
+    }
+
+    /// Return the stack's pull requests in topological order.
+    pub fn prs(&self) -> &[PullRequest] {
+        &self.prs
+    }
+
+    /// Return the number of pull requests in the stack.
+    pub fn len(&self) -> usize {
+        self.prs.len()
+    }
+
+    /// Return the position of the PR whose head is `branch`.
+    pub fn find(&self, branch: &str) -> Option<usize> {
+        self.prs.iter().position(|pr| pr.head_ref_name == branch)
+    }
+
+    /// Return the intended parent PR of the PR at `index`.
+    pub fn parent(&self, index: usize) -> Option<&PullRequest> {
+        self.parents[index].map(|parent| &self.prs[parent])
+    }
+
+    /// Return the number of ancestors above the PR at `index`.
+    pub fn depth(&self, index: usize) -> usize {
+        let mut depth = 0;
+        let mut cursor = self.parents[index];
+        while let Some(parent) = cursor {
+            depth += 1;
+            cursor = self.parents[parent];
+        }
+        depth
+    }
+
+    /// Return whether the stack is a single chain without branching.
+    pub fn is_linear(&self) -> bool {
+        self.parents
+            .iter()
+            .enumerate()
+            .all(|(index, parent)| *parent == index.checked_sub(1))
+    }
+
+    /// Return whether the PR at `ancestor` is `index` itself or one of its ancestors.
+    fn contains(&self, ancestor: usize, index: usize) -> bool {
+        let mut cursor = Some(index);
+        while let Some(node) = cursor {
+            if node == ancestor {
+                return true;
+            }
+            cursor = self.parents[node];
+        }
+        false
+    }
+
+    /// Return the open PRs below the PR at `index` with only merged PRs in between.
+    fn open_child_indices(&self, index: usize) -> Vec<usize> {
+        (0..self.prs.len())
+            .filter(|child| {
+                if self.prs[*child].state == PrState::Merged {
+                    return false;
+                }
+                let mut cursor = self.parents[*child];
+                while let Some(parent) = cursor {
+                    if parent == index {
+                        return true;
+                    }
+                    if self.prs[parent].state != PrState::Merged {
+                        return false;
+                    }
+                    cursor = self.parents[parent];
+                }
+                false
+            })
+            .collect()
+    }
+
+    /// Return the nearest ancestor of the PR at `index` that is not merged.
+    fn open_parent_index(&self, index: usize) -> Option<usize> {
+        let mut cursor = self.parents[index];
+        while let Some(parent) = cursor {
+            if self.prs[parent].state != PrState::Merged {
+                return Some(parent);
+            }
+            cursor = self.parents[parent];
+        }
+        None
+    }
+}
+
+/// Version of the `stck status --json` document schema.
+///
+/// Adding fields keeps the version; renaming, removing, or changing the meaning
+/// of an existing field requires a bump so downstream tools can detect it.
+///
+/// Version 2 made `number` nullable and added the `LOCAL` state for branches
+/// that have no PR yet.
+pub const STATUS_JSON_SCHEMA_VERSION: u32 = 2;
+
 /// Per-branch status information rendered by `stck status`.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct StatusLine {
     /// The PR head branch name.
     pub branch: String,
-    /// The GitHub pull request number.
-    pub number: u64,
+    /// The GitHub pull request number, or `None` for a local-only branch.
+    pub number: Option<u64>,
     /// The current GitHub state of the PR.
     pub state: PrState,
     /// The PR's current base branch name.
     pub base: String,
     /// The PR's current head branch name.
     pub head: String,
+    /// The base the PR should target given its parent in the stack tree.
+    pub expected_base: String,
+    /// Number of stack ancestors above this branch; the stack root is `0`.
+    pub depth: usize,
     /// Derived status flags such as `needs_sync` or `base_mismatch`.
     pub flags: Vec<&'static str>,
 }
 
 /// Aggregated counts for actionable status flags across a stack.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct StatusSummary {
     /// Number of branches that require a sync/rebase operation.
     pub needs_sync: usize,
@@ -40,6 +171,59 @@ pub struct StatusReport {
     pub summary: StatusSummary,
 }
 
+/// Machine-readable status document printed by `stck status --json`.
+#[derive(Debug, Serialize)]
+pub struct StatusDocument<'a> {
+    /// Schema version; see [`STATUS_JSON_SCHEMA_VERSION`].
+    pub schema_version: u32,
+    /// The GitHub repository in `owner/name` form.
+    pub repository: &'a str,
+    /// The repository's default branch.
+    pub default_branch: &'a str,
+    /// The currently checked-out branch.
+    pub current_branch: &'a str,
+    /// Per-branch entries in the stack's topological order.
+    pub branches: Vec<StatusDocumentBranch<'a>>,
+    /// Aggregate flag counts for the same stack.
+    pub summary: &'a StatusSummary,
+}
+
+/// A single branch entry in a [`StatusDocument`].
+#[derive(Debug, Serialize)]
+pub struct StatusDocumentBranch<'a> {
+    /// Status details for the branch.
+    #[serde(flatten)]
+    pub line: &'a StatusLine,
+    /// Whether this branch is currently checked out.
+    pub current: bool,
+}
+
+impl<'a> StatusDocument<'a> {
+    /// Wrap a status report in the versioned JSON document shape.
+    pub fn new(
+        report: &'a StatusReport,
+        repository: &'a str,
+        default_branch: &'a str,
+        current_branch: &'a str,
+    ) -> Self {
+        Self {
+            schema_version: STATUS_JSON_SCHEMA_VERSION,
+            repository,
+            default_branch,
+            current_branch,
+            branches: report
+                .lines
+                .iter()
+                .map(|line| StatusDocumentBranch {
+                    line,
+                    current: line.branch == current_branch,
+                })
+                .collect(),
+            summary: &report.summary,
+        }
+    }
+}
+
 /// A single rebase operation required to restack a branch locally.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SyncStep {
@@ -49,6 +233,12 @@ pub struct SyncStep {
     pub old_base_ref: String,
     /// The branch or ref that the branch should end up based on.
     pub new_base_ref: String,
+    /// Recorded fork point to rebase from instead of resolving `old_base_ref`.
+    ///
+    /// Only set for local-only branches, whose parent may have no remote ref
+    /// to compute a reliable merge-base against.
+    #[serde(default)]
+    pub fork_point: Option<String>,
 }
 
 /// A single PR base retarget operation required after pushing rewritten branches.
@@ -60,26 +250,32 @@ pub struct RetargetStep {
     pub new_base_ref: String,
 }
 
+/// Rebase steps and PR retargets that give stack branches new parents.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReparentPlan {
+    /// Rebase steps, parents before children.
+    pub steps: Vec<SyncStep>,
+    /// PR base changes for branches whose PR does not target their new parent.
+    pub retargets: Vec<RetargetStep>,
+}
+
 /// Build the status view for a discovered stack.
 ///
 /// This function only reasons about GitHub metadata and stack shape. Local
 /// branch divergence from `origin` and default-branch ancestry checks are added
-/// by higher-level command code.
-pub fn build_status_report(stack: &[PullRequest], default_branch: &str) -> StatusReport {
+/// by higher-level command code. Lines follow the tree's topological order.
+pub fn build_status_report(stack: &StackTree, default_branch: &str) -> StatusReport {
     let mut lines = Vec::with_capacity(stack.len());
     let mut needs_sync = 0usize;
     let mut needs_push = 0usize;
     let mut base_mismatch = 0usize;
 
-    for (index, pr) in stack.iter().enumerate() {
-        let expected_base = if index == 0 {
-            default_branch
-        } else {
-            stack[index - 1].head_ref_name.as_str()
-        };
+    for (index, pr) in stack.prs().iter().enumerate() {
+        let parent = stack.parent(index);
+        let expected_base = parent.map_or(default_branch, |parent| parent.head_ref_name.as_str());
 
         let has_base_mismatch = pr.base_ref_name != expected_base;
-        let parent_is_merged = index > 0 && stack[index - 1].state == PrState::Merged;
+        let parent_is_merged = parent.is_some_and(|parent| parent.state == PrState::Merged);
 
         let has_needs_push = false;
         let is_actionable = pr.state != PrState::Merged;
@@ -101,10 +297,12 @@ pub fn build_status_report(stack: &[PullRequest], default_branch: &str) -> Statu
 
         lines.push(StatusLine {
             branch: pr.head_ref_name.clone(),
-            number: pr.number,
+            number: (pr.state != PrState::Local).then_some(pr.number),
             state: pr.state,
             base: pr.base_ref_name.clone(),
             head: pr.head_ref_name.clone(),
+            expected_base: expected_base.to_string(),
+            depth: stack.depth(index),
             flags,
         });
     }
@@ -119,82 +317,492 @@ pub fn build_status_report(stack: &[PullRequest], default_branch: &str) -> Statu
     }
 }
 
-/// Return the first open branch whose base already points at the default branch.
+/// Return the open branches whose base already points at the default branch.
 ///
-/// When present, this branch is the one that should be checked for default
-/// branch drift against `origin/<default_branch>`.
-pub fn first_open_branch_rooted_on_default<'a>(
-    stack: &'a [PullRequest],
+/// These are the open PRs without an open ancestor in the tree. Each one should
+/// be checked for default branch drift against `origin/<default_branch>`.
+pub fn open_branches_rooted_on_default<'a>(
+    stack: &'a StackTree,
     default_branch: &str,
+) -> Vec<&'a PullRequest> {
+    stack
+        .prs()
+        .iter()
+        .enumerate()
+        .filter(|(index, pr)| {
+            pr.state != PrState::Merged
+                && stack.open_parent_index(*index).is_none()
+                && pr.base_ref_name == default_branch
+        })
+        .map(|(_, pr)| pr)
+        .collect()
+}
+
+/// Return the open PR that `stck land` should merge for `current_branch`.
+///
+/// This is the default-rooted open root above (or at) `current_branch`. When
+/// the current branch is merged or missing from the tree, the first open root
+/// is used instead. `None` means no open PR currently targets the default branch.
+pub fn land_candidate<'a>(
+    stack: &'a StackTree,
+    default_branch: &str,
+    current_branch: &str,
 ) -> Option<&'a PullRequest> {
-    let first_open = stack.iter().find(|pr| pr.state != PrState::Merged)?;
+    let roots = open_branches_rooted_on_default(stack, default_branch);
+    match stack.find(current_branch) {
+        Some(index) if stack.prs()[index].state != PrState::Merged => {
+            roots.into_iter().find(|root| {
+                stack
+                    .find(&root.head_ref_name)
+                    .is_some_and(|root_index| stack.contains(root_index, index))
+            })
+        }
+        _ => roots.into_iter().next(),
+    }
+}
+
+/// Return the open PRs below `branch` with only merged PRs in between.
+///
+/// For an open `branch`, these are the PRs that must be retargeted when it merges.
+pub fn open_children<'a>(stack: &'a StackTree, branch: &str) -> Vec<&'a PullRequest> {
+    let Some(parent) = stack.find(branch) else {
+        return Vec::new();
+    };
+    stack
+        .open_child_indices(parent)
+        .into_iter()
+        .map(|index| &stack.prs()[index])
+        .collect()
+}
+
+/// Return the nearest open PR above `branch`, skipping merged PRs, or `None`
+/// when `branch` sits on the default branch.
+pub fn open_parent<'a>(stack: &'a StackTree, branch: &str) -> Option<&'a PullRequest> {
+    let index = stack.find(branch)?;
+    stack
+        .open_parent_index(index)
+        .map(|parent| &stack.prs()[parent])
+}
+
+/// A move requested by the stack navigation commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Navigation {
+    /// Move the given number of branches toward the tip.
+    Up(usize),
+    /// Move the given number of branches toward the default branch.
+    Down(usize),
+    /// Move to the tip of the stack.
+    Top,
+    /// Move to the lowest open branch of the stack.
+    Bottom,
+}
+
+/// Resolve the branch reached by moving from `current_branch` through the stack.
+///
+/// Merged PRs are skipped in both directions. Moving up is only possible while
+/// the path is unambiguous; a branch with several open children is reported
+/// as an error so the caller can pick one explicitly. Walking past either end
+/// of the stack with `Up` or `Down` is an error, while `Top` and `Bottom`
+/// return `current_branch` when it is already at that end.
+pub fn navigation_target(
+    stack: &StackTree,
+    current_branch: &str,
+    navigation: Navigation,
+) -> Result<String, String> {
+    let mut index = stack
+        .find(current_branch)
+        .ok_or_else(|| format!("branch {current_branch} is not part of the discovered stack"))?;
+
+    match navigation {
+        Navigation::Up(steps) => {
+            for moved in 0..steps {
+                let Some(child) = single_open_child(stack, index)? else {
+                    return Err(end_of_stack_error(
+                        "up",
+                        "top",
+                        steps,
+                        moved,
+                        current_branch,
+                        &stack.prs()[index].head_ref_name,
+                    ));
+                };
+                index = child;
+            }
+        }
+        Navigation::Down(steps) => {
+            for moved in 0..steps {
+                let Some(parent) = stack.open_parent_index(index) else {
+                    return Err(end_of_stack_error(
+                        "down",
+                        "bottom",
+                        steps,
+                        moved,
+                        current_branch,
+                        &stack.prs()[index].head_ref_name,
+                    ));
+                };
+                index = parent;
+            }
+        }
+        Navigation::Top => {
+            while let Some(child) = single_open_child(stack, index)? {
+                index = child;
+            }
+        }
+        Navigation::Bottom => {
+            while let Some(parent) = stack.open_parent_index(index) {
+                index = parent;
+            }
+        }
+    }
+
+    Ok(stack.prs()[index].head_ref_name.clone())
+}
 
-    if first_open.base_ref_name == default_branch {
-        Some(first_open)
+fn single_open_child(stack: &StackTree, index: usize) -> Result<Option<usize>, String> {
+    let children = stack.open_child_indices(index);
+    if children.len() > 1 {
+        let names = children
+            .iter()
+            .map(|child| stack.prs()[*child].head_ref_name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(format!(
+            "{} has several open child branches ({names}); check one out with `git checkout <branch>`",
+            stack.prs()[index].head_ref_name
+        ));
+    }
+    Ok(children.first().copied())
+}
+
+fn end_of_stack_error(
+    direction: &str,
+    end: &str,
+    steps: usize,
+    moved: usize,
+    current_branch: &str,
+    reached: &str,
+) -> String {
+    if moved == 0 {
+        format!("already at the {end} of the stack ({current_branch}); cannot move {direction}")
     } else {
-        None
+        format!(
+            "cannot move {direction} {steps} branches from {current_branch}; the {end} of the stack is {reached}, {moved} branch(es) {direction}"
+        )
+    }
+}
+
+/// Build the rebase steps that restack `landed_branch`'s subtree after it merges.
+///
+/// The plan is computed before the merge, while open children still record
+/// the landed branch as their base, so each rebase range excludes the commits
+/// that were just merged into the default branch.
+pub fn build_land_sync_plan(
+    stack: &StackTree,
+    default_branch: &str,
+    landed_branch: &str,
+) -> Vec<SyncStep> {
+    let Some(landed) = stack.find(landed_branch) else {
+        return Vec::new();
+    };
+    let mut after_merge = stack.clone();
+    after_merge.prs[landed].state = PrState::Merged;
+
+    build_sync_plan(&after_merge, default_branch)
+        .into_iter()
+        .filter(|step| {
+            after_merge
+                .find(&step.branch)
+                .is_some_and(|index| after_merge.contains(landed, index))
+        })
+        .collect()
+}
+
+/// Order the locally recorded stack around `current_branch` for `stck restack`.
+///
+/// Recorded parents are followed up from `current_branch` until the default
+/// branch or a branch without a recorded parent. Every recorded descendant of
+/// that root is returned with parents before children and siblings sorted by
+/// branch name. Branches recorded directly on the default branch are roots and
+/// are not returned; moving them is left to `stck sync`.
+pub fn local_restack_order<'a>(
+    current_branch: &str,
+    default_branch: &str,
+    local_parents: &'a [LocalParent],
+) -> Vec<&'a LocalParent> {
+    let mut root = current_branch.to_string();
+    let mut seen = vec![root.clone()];
+    while let Some(local) = local_parents.iter().find(|local| local.branch == root) {
+        if local.parent == default_branch || seen.contains(&local.parent) {
+            break;
+        }
+        root = local.parent.clone();
+        seen.push(root.clone());
+    }
+    if root == default_branch {
+        return Vec::new();
+    }
+
+    // Depth-first preorder keeps parents first and each subtree together.
+    let mut order = Vec::new();
+    let mut visited = vec![root.clone()];
+    let mut pending = children_of(&root, local_parents);
+    while let Some(local) = pending.pop() {
+        if visited.contains(&local.branch) {
+            continue;
+        }
+        visited.push(local.branch.clone());
+        order.push(local);
+        pending.extend(children_of(&local.branch, local_parents));
     }
+    order
+}
+
+/// Recorded children of `parent`, in reverse name order for a pop-based walk.
+fn children_of<'a>(parent: &str, local_parents: &'a [LocalParent]) -> Vec<&'a LocalParent> {
+    let mut children = local_parents
+        .iter()
+        .filter(|local| local.parent == parent)
+        .collect::<Vec<_>>();
+    children.sort_by(|left, right| right.branch.cmp(&left.branch));
+    children
 }
 
 /// Build the default sync plan for a stack.
-pub fn build_sync_plan(stack: &[PullRequest], default_branch: &str) -> Vec<SyncStep> {
-    build_sync_plan_with_options(stack, default_branch, false)
+pub fn build_sync_plan(stack: &StackTree, default_branch: &str) -> Vec<SyncStep> {
+    build_sync_plan_with_options(stack, default_branch, &[])
 }
 
-/// Build the sequence of rebase steps needed to restore a linear open stack.
+/// Build the sequence of rebase steps needed to restore an open stack tree.
 ///
-/// Merged PRs are skipped when choosing the effective parent chain. Once an
-/// open branch needs rewriting, every later open descendant is also rewritten
-/// so the local branch ancestry stays consistent with the intended stack order.
+/// Merged PRs are skipped when choosing each branch's effective parent. Once an
+/// open branch needs rewriting, every open descendant in its subtree is also
+/// rewritten so local branch ancestry stays consistent with the intended tree.
+/// Steps follow the tree's topological order, so parents are always rebased
+/// before their children. Open branches without an open ancestor that appear
+/// in `force_rewrite_roots` are rewritten even when their base is unchanged.
 pub fn build_sync_plan_with_options(
-    stack: &[PullRequest],
+    stack: &StackTree,
     default_branch: &str,
-    force_rewrite_first_open: bool,
+    force_rewrite_roots: &[String],
 ) -> Vec<SyncStep> {
     let mut steps = Vec::new();
-    let mut previous_open_branch: Option<&str> = None;
-    let mut previous_open_rewritten = false;
-    let mut seen_first_open = false;
+    let mut rewritten = vec![false; stack.len()];
 
-    for pr in stack {
+    for (index, pr) in stack.prs().iter().enumerate() {
         if pr.state == PrState::Merged {
             continue;
         }
 
-        let is_first_open = !seen_first_open;
-        seen_first_open = true;
-
-        let target_base = previous_open_branch.unwrap_or(default_branch);
+        let open_parent = stack.open_parent_index(index);
+        let target_base = open_parent.map_or(default_branch, |parent| {
+            stack.prs()[parent].head_ref_name.as_str()
+        });
         let base_changed = pr.base_ref_name != target_base;
-        let forced_rewrite = is_first_open && force_rewrite_first_open;
-        let needs_rebase = base_changed || previous_open_rewritten || forced_rewrite;
+        let parent_rewritten = open_parent.is_some_and(|parent| rewritten[parent]);
+        let forced_rewrite = open_parent.is_none()
+            && force_rewrite_roots
+                .iter()
+                .any(|branch| branch == &pr.head_ref_name);
+        let needs_rebase = base_changed || parent_rewritten || forced_rewrite;
 
         if needs_rebase {
             steps.push(SyncStep {
                 branch: pr.head_ref_name.clone(),
                 old_base_ref: pr.base_ref_name.clone(),
                 new_base_ref: target_base.to_string(),
+                fork_point: None,
             });
         }
 
-        previous_open_branch = Some(pr.head_ref_name.as_str());
-        previous_open_rewritten = needs_rebase;
+        rewritten[index] = needs_rebase;
     }
 
     steps
 }
 
+/// Plan the rebases and retargets that move each branch in `moves` onto a new parent.
+///
+/// `moves` pairs a branch with its new parent, which may be the default
+/// branch or a branch outside the stack, such as one an edit is about to
+/// create. Every other open branch keeps its parent but is rewritten when its
+/// parent is. Each step's `old_base_ref` is the branch's current parent, so the
+/// caller can pin the rebase range before anything moves. Steps follow the
+/// reshaped tree with parents first; moving a branch onto its own descendant
+/// is an error.
+pub fn build_reparent_plan(
+    stack: &StackTree,
+    default_branch: &str,
+    moves: &[(String, String)],
+) -> Result<ReparentPlan, String> {
+    let open = (0..stack.len())
+        .filter(|index| stack.prs()[*index].state != PrState::Merged)
+        .collect::<Vec<_>>();
+    let old_parent = |index: usize| {
+        stack
+            .open_parent_index(index)
+            .map_or(default_branch, |parent| {
+                stack.prs()[parent].head_ref_name.as_str()
+            })
+    };
+    let new_parent = |index: usize| {
+        let branch = &stack.prs()[index].head_ref_name;
+        moves
+            .iter()
+            .find(|(moved, _)| moved == branch)
+            .map_or(old_parent(index), |(_, parent)| parent.as_str())
+    };
+    let open_index = |branch: &str| {
+        open.iter()
+            .copied()
+            .find(|index| stack.prs()[*index].head_ref_name == branch)
+    };
+
+    for (branch, parent) in moves {
+        let mut cursor = open_index(parent);
+        // Bounded walk: a cycle that does not pass through `branch` is caught
+        // when its own moved branch is checked.
+        for _ in 0..open.len() {
+            let Some(index) = cursor else {
+                break;
+            };
+            if stack.prs()[index].head_ref_name == *branch {
+                return Err(format!(
+                    "cannot move {branch} onto {parent}: {parent} is stacked on {branch}"
+                ));
+            }
+            cursor = open_index(new_parent(index));
+        }
+    }
+
+    // Repeatedly take, in stack order, every branch whose new parent is
+    // already placed or lies outside the stack.
+    let mut order = Vec::with_capacity(open.len());
+    while order.len() < open.len() {
+        for index in &open {
+            if order.contains(index) {
+                continue;
+            }
+            if open_index(new_parent(*index)).is_none_or(|parent| order.contains(&parent)) {
+                order.push(*index);
+            }
+        }
+    }
+
+    let mut plan = ReparentPlan::default();
+    for index in order {
+        let pr = &stack.prs()[index];
+        let (old, new) = (old_parent(index), new_parent(index));
+        let parent_rewritten = plan.steps.iter().any(|step| step.branch == new);
+        if old != new || parent_rewritten {
+            plan.steps.push(SyncStep {
+                branch: pr.head_ref_name.clone(),
+                old_base_ref: old.to_string(),
+                new_base_ref: new.to_string(),
+                fork_point: None,
+            });
+        }
+        if pr.state != PrState::Local && pr.base_ref_name != new {
+            plan.retargets.push(RetargetStep {
+                branch: pr.head_ref_name.clone(),
+                new_base_ref: new.to_string(),
+            });
+        }
+    }
+    Ok(plan)
+}
+
+/// Where `stck move` places a branch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveTarget {
+    /// Stack the branch directly on this branch (or the default branch).
+    Onto(String),
+    /// Put the branch between this branch and its parent.
+    Before(String),
+}
+
+/// Plan moving `branch` to `target` within the stack.
+///
+/// Only the branch's own commits move: its children are handed to its old
+/// parent, so the rest of the sequence closes the gap it leaves.
+pub fn build_move_plan(
+    stack: &StackTree,
+    default_branch: &str,
+    branch: &str,
+    target: &MoveTarget,
+) -> Result<ReparentPlan, String> {
+    let open_index = |name: &str| {
+        stack
+            .find(name)
+            .filter(|index| stack.prs()[*index].state != PrState::Merged)
+    };
+    let parent_of = |index: usize| {
+        stack
+            .open_parent_index(index)
+            .map_or(default_branch, |parent| {
+                stack.prs()[parent].head_ref_name.as_str()
+            })
+    };
+    let index = open_index(branch)
+        .ok_or_else(|| format!("{branch} is not an open branch of this stack"))?;
+    let old_parent = parent_of(index);
+
+    let mut moves = stack
+        .open_child_indices(index)
+        .into_iter()
+        .map(|child| {
+            (
+                stack.prs()[child].head_ref_name.clone(),
+                old_parent.to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    match target {
+        MoveTarget::Onto(onto) => {
+            if onto == branch {
+                return Err(format!("cannot move {branch} onto itself"));
+            }
+            if onto != default_branch && open_index(onto).is_none() {
+                return Err(format!(
+                    "{onto} is neither {default_branch} nor an open branch of this stack"
+                ));
+            }
+            if onto == old_parent {
+                return Err(format!("{branch} is already stacked on {onto}"));
+            }
+            moves.push((branch.to_string(), onto.clone()));
+        }
+        MoveTarget::Before(before) => {
+            if before == branch {
+                return Err(format!("cannot move {branch} before itself"));
+            }
+            let before_index = open_index(before)
+                .ok_or_else(|| format!("{before} is not an open branch of this stack"))?;
+            let before_parent = parent_of(before_index);
+            if before_parent == branch {
+                return Err(format!("{branch} is already directly below {before}"));
+            }
+            moves.push((branch.to_string(), before_parent.to_string()));
+            moves.push((before.clone(), branch.to_string()));
+        }
+    }
+    build_reparent_plan(stack, default_branch, &moves)
+}
+
 /// List the open PR branches that should be pushed during `stck push`.
-pub fn build_push_branches(stack: &[PullRequest]) -> Vec<String> {
+///
+/// Local-only branches stay unpublished until they are submitted.
+pub fn build_push_branches(stack: &StackTree) -> Vec<String> {
     stack
+        .prs()
         .iter()
-        .filter(|pr| pr.state != PrState::Merged)
+        .filter(|pr| pr.state != PrState::Merged && pr.state != PrState::Local)
         .map(|pr| pr.head_ref_name.clone())
         .collect()
 }
 
 /// Convert the sync plan for a stack into the PR retarget operations needed after push.
-pub fn build_push_retargets(stack: &[PullRequest], default_branch: &str) -> Vec<RetargetStep> {
+pub fn build_push_retargets(stack: &StackTree, default_branch: &str) -> Vec<RetargetStep> {
     build_sync_plan(stack, default_branch)
         .into_iter()
         .map(|step| RetargetStep {
@@ -207,29 +815,35 @@ pub fn build_push_retargets(stack: &[PullRequest], default_branch: &str) -> Vec<
 /// Remove retarget steps that are already satisfied by the current PR metadata.
 ///
 /// Missing PRs are kept in the result so the caller can surface the mismatch
-/// instead of silently discarding it.
+/// instead of silently discarding it. Local-only branches have no PR to
+/// retarget and are dropped.
 pub fn filter_pending_retargets(
     retargets: Vec<RetargetStep>,
-    stack: &[PullRequest],
+    stack: &StackTree,
 ) -> Vec<RetargetStep> {
     retargets
         .into_iter()
-        .filter(
-            |retarget| match stack.iter().find(|pr| pr.head_ref_name == retarget.branch) {
-                Some(pr) => pr.base_ref_name != retarget.new_base_ref,
-                None => true,
-            },
-        )
+        .filter(|retarget| match stack.find(&retarget.branch) {
+            Some(index) => {
+                let pr = &stack.prs()[index];
+                pr.state != PrState::Local && pr.base_ref_name != retarget.new_base_ref
+            }
+            None => true,
+        })
         .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        build_push_branches, build_push_retargets, build_status_report, build_sync_plan,
-        filter_pending_retargets, first_open_branch_rooted_on_default, RetargetStep, SyncStep,
+        build_land_sync_plan, build_move_plan, build_push_branches, build_push_retargets,
+        build_reparent_plan, build_status_report, build_sync_plan, build_sync_plan_with_options,
+        filter_pending_retargets, land_candidate, local_restack_order, navigation_target,
+        open_branches_rooted_on_default, open_children, open_parent, MoveTarget, Navigation,
+        RetargetStep, StackTree, StatusDocument, SyncStep, STATUS_JSON_SCHEMA_VERSION,
     };
     use crate::github::{PrState, PullRequest};
+    use crate::gitops::LocalParent;
 
     fn pr(number: u64, head: &str, base: &str, state: PrState) -> PullRequest {
         PullRequest {
@@ -242,10 +856,10 @@ mod tests {
 
     #[test]
     fn reports_no_flags_for_aligned_open_stack() {
-        let stack = vec![
+        let stack = StackTree::linear(vec![
             pr(100, "feature-a", "main", PrState::Open),
             pr(101, "feature-b", "feature-a", PrState::Open),
-        ];
+        ]);
 
         let report = build_status_report(&stack, "main");
 
@@ -258,10 +872,10 @@ mod tests {
 
     #[test]
     fn reports_needs_sync_when_parent_is_merged() {
-        let stack = vec![
+        let stack = StackTree::linear(vec![
             pr(100, "feature-a", "main", PrState::Merged),
             pr(101, "feature-b", "feature-a", PrState::Open),
-        ];
+        ]);
 
         let report = build_status_report(&stack, "main");
 
@@ -272,32 +886,32 @@ mod tests {
 
     #[test]
     fn detects_first_open_branch_rooted_on_default() {
-        let stack = vec![
+        let stack = StackTree::linear(vec![
             pr(100, "feature-a", "main", PrState::Open),
             pr(101, "feature-b", "feature-a", PrState::Open),
-        ];
+        ]);
 
-        let first = first_open_branch_rooted_on_default(&stack, "main")
-            .expect("first open branch rooted on default should exist");
-        assert_eq!(first.head_ref_name, "feature-a");
+        let roots = open_branches_rooted_on_default(&stack, "main");
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].head_ref_name, "feature-a");
     }
 
     #[test]
     fn returns_none_when_first_open_branch_not_rooted_on_default() {
-        let stack = vec![
+        let stack = StackTree::linear(vec![
             pr(100, "feature-a", "main", PrState::Merged),
             pr(101, "feature-b", "feature-a", PrState::Open),
-        ];
+        ]);
 
-        assert!(first_open_branch_rooted_on_default(&stack, "main").is_none());
+        assert!(open_branches_rooted_on_default(&stack, "main").is_empty());
     }
 
     #[test]
     fn reports_base_mismatch_and_needs_sync_together() {
-        let stack = vec![
+        let stack = StackTree::linear(vec![
             pr(100, "feature-a", "main", PrState::Open),
             pr(101, "feature-b", "main", PrState::Open),
-        ];
+        ]);
 
         let report = build_status_report(&stack, "main");
 
@@ -308,10 +922,10 @@ mod tests {
 
     #[test]
     fn sync_plan_is_empty_when_open_stack_is_aligned() {
-        let stack = vec![
+        let stack = StackTree::linear(vec![
             pr(100, "feature-a", "main", PrState::Open),
             pr(101, "feature-b", "feature-a", PrState::Open),
-        ];
+        ]);
 
         let plan = build_sync_plan(&stack, "main");
         assert!(plan.is_empty());
@@ -319,11 +933,11 @@ mod tests {
 
     #[test]
     fn sync_plan_restacks_child_of_merged_parent_and_descendants() {
-        let stack = vec![
+        let stack = StackTree::linear(vec![
             pr(100, "feature-a", "main", PrState::Merged),
             pr(101, "feature-b", "feature-a", PrState::Open),
             pr(102, "feature-c", "feature-b", PrState::Open),
-        ];
+        ]);
 
         let plan = build_sync_plan(&stack, "main");
         assert_eq!(
@@ -333,11 +947,13 @@ mod tests {
                     branch: "feature-b".to_string(),
                     old_base_ref: "feature-a".to_string(),
                     new_base_ref: "main".to_string(),
+                    fork_point: None,
                 },
                 SyncStep {
                     branch: "feature-c".to_string(),
                     old_base_ref: "feature-b".to_string(),
                     new_base_ref: "feature-b".to_string(),
+                    fork_point: None,
                 },
             ]
         );
@@ -345,10 +961,10 @@ mod tests {
 
     #[test]
     fn sync_plan_restacks_on_base_mismatch() {
-        let stack = vec![
+        let stack = StackTree::linear(vec![
             pr(100, "feature-a", "main", PrState::Open),
             pr(101, "feature-b", "main", PrState::Open),
-        ];
+        ]);
 
         let plan = build_sync_plan(&stack, "main");
         assert_eq!(
@@ -357,17 +973,18 @@ mod tests {
                 branch: "feature-b".to_string(),
                 old_base_ref: "main".to_string(),
                 new_base_ref: "feature-a".to_string(),
+                fork_point: None,
             }]
         );
     }
 
     #[test]
     fn push_branches_include_only_open_pr_branches() {
-        let stack = vec![
+        let stack = StackTree::linear(vec![
             pr(100, "feature-a", "main", PrState::Merged),
             pr(101, "feature-b", "feature-a", PrState::Open),
             pr(102, "feature-c", "feature-b", PrState::Open),
-        ];
+        ]);
 
         let branches = build_push_branches(&stack);
         assert_eq!(
@@ -378,11 +995,11 @@ mod tests {
 
     #[test]
     fn push_retargets_follow_sync_plan_targets() {
-        let stack = vec![
+        let stack = StackTree::linear(vec![
             pr(100, "feature-a", "main", PrState::Merged),
             pr(101, "feature-b", "feature-a", PrState::Open),
             pr(102, "feature-c", "feature-b", PrState::Open),
-        ];
+        ]);
 
         let retargets = build_push_retargets(&stack, "main");
         assert_eq!(
@@ -402,12 +1019,12 @@ mod tests {
 
     #[test]
     fn sync_plan_skips_consecutive_merged_ancestors() {
-        let stack = vec![
+        let stack = StackTree::linear(vec![
             pr(100, "feature-a", "main", PrState::Merged),
             pr(101, "feature-b", "feature-a", PrState::Merged),
             pr(102, "feature-c", "feature-b", PrState::Open),
             pr(103, "feature-d", "feature-c", PrState::Open),
-        ];
+        ]);
 
         let plan = build_sync_plan(&stack, "main");
         assert_eq!(
@@ -417,11 +1034,13 @@ mod tests {
                     branch: "feature-c".to_string(),
                     old_base_ref: "feature-b".to_string(),
                     new_base_ref: "main".to_string(),
+                    fork_point: None,
                 },
                 SyncStep {
                     branch: "feature-d".to_string(),
                     old_base_ref: "feature-c".to_string(),
                     new_base_ref: "feature-c".to_string(),
+                    fork_point: None,
                 },
             ]
         );
@@ -429,11 +1048,11 @@ mod tests {
 
     #[test]
     fn push_branches_skip_consecutive_merged_ancestors() {
-        let stack = vec![
+        let stack = StackTree::linear(vec![
             pr(100, "feature-a", "main", PrState::Merged),
             pr(101, "feature-b", "feature-a", PrState::Merged),
             pr(102, "feature-c", "feature-b", PrState::Open),
-        ];
+        ]);
 
         let branches = build_push_branches(&stack);
         assert_eq!(branches, vec!["feature-c".to_string()]);
@@ -441,11 +1060,11 @@ mod tests {
 
     #[test]
     fn push_retargets_with_consecutive_merged_ancestors() {
-        let stack = vec![
+        let stack = StackTree::linear(vec![
             pr(100, "feature-a", "main", PrState::Merged),
             pr(101, "feature-b", "feature-a", PrState::Merged),
             pr(102, "feature-c", "feature-b", PrState::Open),
-        ];
+        ]);
 
         let retargets = build_push_retargets(&stack, "main");
         assert_eq!(
@@ -459,10 +1078,10 @@ mod tests {
 
     #[test]
     fn filter_pending_retargets_skips_branches_already_on_target_base() {
-        let stack = vec![
+        let stack = StackTree::linear(vec![
             pr(101, "feature-b", "main", PrState::Open),
             pr(102, "feature-c", "feature-b", PrState::Open),
-        ];
+        ]);
         let retargets = vec![
             RetargetStep {
                 branch: "feature-b".to_string(),
@@ -489,13 +1108,79 @@ mod tests {
         );
     }
 
+    fn local(branch: &str, parent: &str) -> LocalParent {
+        LocalParent {
+            branch: branch.to_string(),
+            parent: parent.to_string(),
+            fork_point: None,
+        }
+    }
+
+    fn branches(order: Vec<&LocalParent>) -> Vec<&str> {
+        order.iter().map(|local| local.branch.as_str()).collect()
+    }
+
+    #[test]
+    fn local_restack_order_covers_the_whole_recorded_tree_parents_first() {
+        let parents = vec![
+            local("feature-c", "feature-b"),
+            local("feature-a", "main"),
+            local("feature-b2", "feature-a"),
+            local("feature-b", "feature-a"),
+            local("other", "main"),
+        ];
+
+        let order = local_restack_order("feature-c", "main", &parents);
+
+        assert_eq!(
+            branches(order),
+            vec!["feature-b", "feature-c", "feature-b2"]
+        );
+    }
+
+    #[test]
+    fn local_restack_order_restacks_onto_an_unrecorded_parent() {
+        let parents = vec![local("feature-b", "feature-a")];
+
+        let order = local_restack_order("feature-b", "main", &parents);
+
+        assert_eq!(branches(order), vec!["feature-b"]);
+    }
+
+    #[test]
+    fn local_restack_order_is_empty_on_the_default_branch() {
+        let parents = vec![local("feature-a", "main")];
+
+        assert!(local_restack_order("main", "main", &parents).is_empty());
+        assert!(local_restack_order("feature-a", "main", &parents).is_empty());
+    }
+
+    #[test]
+    fn local_branches_are_reported_but_never_pushed_or_retargeted() {
+        let stack = StackTree::linear(vec![
+            pr(101, "feature-a", "main", PrState::Open),
+            pr(0, "feature-b", "feature-a", PrState::Local),
+        ]);
+        let retargets = vec![RetargetStep {
+            branch: "feature-b".to_string(),
+            new_base_ref: "main".to_string(),
+        }];
+
+        let report = build_status_report(&stack, "main");
+
+        assert_eq!(report.lines[0].number, Some(101));
+        assert_eq!(report.lines[1].number, None);
+        assert_eq!(build_push_branches(&stack), vec!["feature-a".to_string()]);
+        assert!(filter_pending_retargets(retargets, &stack).is_empty());
+    }
+
     #[test]
     fn status_reports_needs_sync_with_consecutive_merged_ancestors() {
-        let stack = vec![
+        let stack = StackTree::linear(vec![
             pr(100, "feature-a", "main", PrState::Merged),
             pr(101, "feature-b", "feature-a", PrState::Merged),
             pr(102, "feature-c", "feature-b", PrState::Open),
-        ];
+        ]);
 
         let report = build_status_report(&stack, "main");
 
@@ -505,4 +1190,468 @@ mod tests {
         assert_eq!(report.lines[1].flags, Vec::<&str>::new());
         assert_eq!(report.lines[2].flags, vec!["needs_sync"]);
     }
+
+    fn branching_stack(root_state: PrState) -> StackTree {
+        let mut stack = StackTree::default();
+        let root = stack.push(pr(100, "feature-a", "main", root_state), None);
+        let child = stack.push(pr(101, "feature-b", "feature-a", PrState::Open), Some(root));
+        stack.push(
+            pr(102, "feature-b-child", "feature-b", PrState::Open),
+            Some(child),
+        );
+        stack.push(pr(103, "feature-c", "feature-a", PrState::Open), Some(root));
+        stack
+    }
+
+    #[test]
+    fn tree_links_children_by_base_ref() {
+        let stack = branching_stack(PrState::Open);
+
+        assert!(!stack.is_linear());
+        assert_eq!(stack.parent(1).map(|pr| pr.number), Some(100));
+        assert_eq!(stack.parent(2).map(|pr| pr.number), Some(101));
+        assert_eq!(stack.parent(3).map(|pr| pr.number), Some(100));
+        assert_eq!(stack.depth(2), 2);
+        assert_eq!(stack.depth(3), 1);
+        assert!(StackTree::linear(stack.prs()[..3].to_vec()).is_linear());
+    }
+
+    #[test]
+    fn status_report_uses_tree_parents_for_expected_bases() {
+        let report = build_status_report(&branching_stack(PrState::Merged), "main");
+
+        assert_eq!(report.summary.needs_sync, 2);
+        assert_eq!(report.summary.base_mismatch, 0);
+        assert_eq!(report.lines[1].flags, vec!["needs_sync"]);
+        assert_eq!(report.lines[2].flags, Vec::<&str>::new());
+        assert_eq!(report.lines[3].flags, vec!["needs_sync"]);
+    }
+
+    #[test]
+    fn open_roots_include_every_retargeted_child_of_a_merged_root() {
+        let mut stack = StackTree::default();
+        let root = stack.push(pr(100, "feature-a", "main", PrState::Merged), None);
+        stack.push(pr(101, "feature-b", "main", PrState::Open), Some(root));
+        stack.push(pr(103, "feature-c", "main", PrState::Open), Some(root));
+
+        let roots = open_branches_rooted_on_default(&stack, "main")
+            .into_iter()
+            .map(|pr| pr.head_ref_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(roots, vec!["feature-b", "feature-c"]);
+    }
+
+    #[test]
+    fn sync_plan_restacks_every_subtree_of_a_merged_parent() {
+        let plan = build_sync_plan(&branching_stack(PrState::Merged), "main");
+
+        assert_eq!(
+            plan,
+            vec![
+                SyncStep {
+                    branch: "feature-b".to_string(),
+                    old_base_ref: "feature-a".to_string(),
+                    new_base_ref: "main".to_string(),
+                    fork_point: None,
+                },
+                SyncStep {
+                    branch: "feature-b-child".to_string(),
+                    old_base_ref: "feature-b".to_string(),
+                    new_base_ref: "feature-b".to_string(),
+                    fork_point: None,
+                },
+                SyncStep {
+                    branch: "feature-c".to_string(),
+                    old_base_ref: "feature-a".to_string(),
+                    new_base_ref: "main".to_string(),
+                    fork_point: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn forced_root_rewrite_restacks_only_its_subtree() {
+        let mut stack = StackTree::default();
+        let root = stack.push(pr(100, "feature-a", "main", PrState::Open), None);
+        stack.push(pr(101, "feature-b", "feature-a", PrState::Open), Some(root));
+        stack.push(pr(102, "feature-x", "main", PrState::Open), None);
+
+        let plan = build_sync_plan_with_options(&stack, "main", &["feature-a".to_string()]);
+        let branches = plan
+            .iter()
+            .map(|step| step.branch.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(branches, vec!["feature-a", "feature-b"]);
+    }
+
+    #[test]
+    fn status_document_serializes_versioned_branch_entries() {
+        let stack = StackTree::linear(vec![
+            pr(100, "feature-a", "main", PrState::Merged),
+            pr(101, "feature-b", "feature-a", PrState::Open),
+        ]);
+        let report = build_status_report(&stack, "main");
+
+        let document = StatusDocument::new(&report, "example/stck", "main", "feature-b");
+        let value = serde_json::to_value(&document).expect("status document should serialize");
+
+        assert_eq!(value["schema_version"], STATUS_JSON_SCHEMA_VERSION);
+        assert_eq!(value["repository"], "example/stck");
+        assert_eq!(value["default_branch"], "main");
+        assert_eq!(value["branches"][0]["state"], "MERGED");
+        assert_eq!(value["branches"][0]["current"], false);
+        assert_eq!(value["branches"][1]["branch"], "feature-b");
+        assert_eq!(value["branches"][1]["number"], 101);
+        assert_eq!(value["branches"][1]["base"], "feature-a");
+        assert_eq!(value["branches"][1]["expected_base"], "feature-a");
+        assert_eq!(value["branches"][1]["depth"], 1);
+        assert_eq!(value["branches"][1]["flags"][0], "needs_sync");
+        assert_eq!(value["branches"][1]["current"], true);
+        assert_eq!(value["summary"]["needs_sync"], 1);
+    }
+
+    #[test]
+    fn land_candidate_is_the_open_root_above_the_current_branch() {
+        let stack = branching_stack(PrState::Open);
+        assert_eq!(
+            land_candidate(&stack, "main", "feature-b-child").map(|pr| pr.number),
+            Some(100)
+        );
+
+        let stack = branching_stack(PrState::Merged);
+        let mut retargeted = StackTree::default();
+        for (index, pr) in stack.prs().iter().enumerate() {
+            let mut pr = pr.clone();
+            if pr.base_ref_name == "feature-a" {
+                pr.base_ref_name = "main".to_string();
+            }
+            retargeted.push(pr, stack.parents[index]);
+        }
+        assert_eq!(
+            land_candidate(&retargeted, "main", "feature-c").map(|pr| pr.number),
+            Some(103)
+        );
+        assert_eq!(
+            land_candidate(&retargeted, "main", "feature-a").map(|pr| pr.number),
+            Some(101)
+        );
+        assert_eq!(land_candidate(&stack, "main", "feature-b"), None);
+    }
+
+    #[test]
+    fn open_children_skip_merged_intermediate_parents() {
+        let stack = StackTree::linear(vec![
+            pr(100, "feature-a", "main", PrState::Open),
+            pr(101, "feature-b", "feature-a", PrState::Merged),
+            pr(102, "feature-c", "feature-b", PrState::Open),
+        ]);
+
+        let children = open_children(&stack, "feature-a")
+            .into_iter()
+            .map(|pr| pr.number)
+            .collect::<Vec<_>>();
+        assert_eq!(children, vec![102]);
+        assert!(open_children(&stack, "feature-c").is_empty());
+    }
+
+    #[test]
+    fn open_parent_skips_merged_intermediate_parents() {
+        let stack = StackTree::linear(vec![
+            pr(100, "feature-a", "main", PrState::Open),
+            pr(101, "feature-b", "feature-a", PrState::Merged),
+            pr(102, "feature-c", "feature-b", PrState::Open),
+        ]);
+
+        assert_eq!(
+            open_parent(&stack, "feature-c").map(|pr| pr.number),
+            Some(100)
+        );
+        assert!(open_parent(&stack, "feature-a").is_none());
+    }
+
+    #[test]
+    fn land_sync_plan_restacks_the_landed_subtree_from_the_landed_branch() {
+        let stack = StackTree::linear(vec![
+            pr(100, "feature-a", "main", PrState::Open),
+            pr(101, "feature-b", "feature-a", PrState::Open),
+            pr(102, "feature-c", "feature-b", PrState::Open),
+        ]);
+
+        let steps = build_land_sync_plan(&stack, "main", "feature-a");
+
+        assert_eq!(
+            steps,
+            vec![
+                SyncStep {
+                    branch: "feature-b".to_string(),
+                    old_base_ref: "feature-a".to_string(),
+                    new_base_ref: "main".to_string(),
+                    fork_point: None,
+                },
+                SyncStep {
+                    branch: "feature-c".to_string(),
+                    old_base_ref: "feature-b".to_string(),
+                    new_base_ref: "feature-b".to_string(),
+                    fork_point: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn navigation_skips_merged_branches_in_both_directions() {
+        let stack = StackTree::linear(vec![
+            pr(100, "feature-a", "main", PrState::Open),
+            pr(101, "feature-b", "feature-a", PrState::Merged),
+            pr(102, "feature-c", "feature-b", PrState::Open),
+            pr(103, "feature-d", "feature-c", PrState::Open),
+        ]);
+
+        assert_eq!(
+            navigation_target(&stack, "feature-a", Navigation::Up(1)),
+            Ok("feature-c".to_string())
+        );
+        assert_eq!(
+            navigation_target(&stack, "feature-d", Navigation::Down(2)),
+            Ok("feature-a".to_string())
+        );
+        assert_eq!(
+            navigation_target(&stack, "feature-c", Navigation::Top),
+            Ok("feature-d".to_string())
+        );
+        assert_eq!(
+            navigation_target(&stack, "feature-d", Navigation::Bottom),
+            Ok("feature-a".to_string())
+        );
+        assert_eq!(
+            navigation_target(&stack, "feature-a", Navigation::Bottom),
+            Ok("feature-a".to_string())
+        );
+    }
+
+    #[test]
+    fn navigation_reports_stack_ends() {
+        let stack = StackTree::linear(vec![
+            pr(100, "feature-a", "main", PrState::Open),
+            pr(101, "feature-b", "feature-a", PrState::Open),
+        ]);
+
+        assert_eq!(
+            navigation_target(&stack, "feature-b", Navigation::Up(1)),
+            Err("already at the top of the stack (feature-b); cannot move up".to_string())
+        );
+        assert_eq!(
+            navigation_target(&stack, "feature-b", Navigation::Down(3)),
+            Err("cannot move down 3 branches from feature-b; the bottom of the stack is feature-a, 1 branch(es) down".to_string())
+        );
+    }
+
+    #[test]
+    fn navigation_up_requires_a_single_open_child() {
+        let stack = branching_stack(PrState::Open);
+
+        assert_eq!(
+            navigation_target(&stack, "feature-a", Navigation::Top),
+            Err("feature-a has several open child branches (feature-b, feature-c); check one out with `git checkout <branch>`".to_string())
+        );
+        assert_eq!(
+            navigation_target(&stack, "feature-b", Navigation::Up(1)),
+            Ok("feature-b-child".to_string())
+        );
+    }
+
+    fn moves(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(branch, parent)| (branch.to_string(), parent.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn reparent_plan_moves_children_onto_a_branch_outside_the_stack() {
+        let stack = StackTree::linear(vec![
+            pr(100, "feature-a", "main", PrState::Open),
+            pr(101, "feature-b", "feature-a", PrState::Open),
+            pr(0, "feature-c", "feature-b", PrState::Local),
+        ]);
+
+        let plan = build_reparent_plan(&stack, "main", &moves(&[("feature-b", "feature-fix")]))
+            .expect("plan should build");
+
+        assert_eq!(
+            plan.steps,
+            vec![
+                SyncStep {
+                    branch: "feature-b".to_string(),
+                    old_base_ref: "feature-a".to_string(),
+                    new_base_ref: "feature-fix".to_string(),
+                    fork_point: None,
+                },
+                SyncStep {
+                    branch: "feature-c".to_string(),
+                    old_base_ref: "feature-b".to_string(),
+                    new_base_ref: "feature-b".to_string(),
+                    fork_point: None,
+                },
+            ]
+        );
+        assert_eq!(
+            plan.retargets,
+            vec![RetargetStep {
+                branch: "feature-b".to_string(),
+                new_base_ref: "feature-fix".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn reparent_plan_orders_a_branch_moved_below_its_old_ancestors_first() {
+        let stack = StackTree::linear(vec![
+            pr(100, "feature-a", "main", PrState::Open),
+            pr(101, "feature-b", "feature-a", PrState::Open),
+            pr(102, "feature-c", "feature-b", PrState::Open),
+        ]);
+
+        let plan = build_reparent_plan(
+            &stack,
+            "main",
+            &moves(&[("feature-c", "main"), ("feature-a", "feature-c")]),
+        )
+        .expect("plan should build");
+
+        let order = plan
+            .steps
+            .iter()
+            .map(|step| (step.branch.as_str(), step.new_base_ref.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            vec![
+                ("feature-c", "main"),
+                ("feature-a", "feature-c"),
+                ("feature-b", "feature-a"),
+            ]
+        );
+        assert_eq!(plan.steps[0].old_base_ref, "feature-b");
+        assert_eq!(plan.retargets.len(), 2);
+    }
+
+    #[test]
+    fn reparent_plan_rejects_moving_a_branch_onto_its_descendant() {
+        let stack = StackTree::linear(vec![
+            pr(100, "feature-a", "main", PrState::Open),
+            pr(101, "feature-b", "feature-a", PrState::Open),
+        ]);
+
+        assert_eq!(
+            build_reparent_plan(&stack, "main", &moves(&[("feature-a", "feature-b")])),
+            Err(
+                "cannot move feature-a onto feature-b: feature-b is stacked on feature-a"
+                    .to_string()
+            )
+        );
+    }
+
+    fn steps(plan: &super::ReparentPlan) -> Vec<(&str, &str, &str)> {
+        plan.steps
+            .iter()
+            .map(|step| {
+                (
+                    step.branch.as_str(),
+                    step.old_base_ref.as_str(),
+                    step.new_base_ref.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn move_plan_hands_the_moved_branch_children_to_its_old_parent() {
+        let stack = StackTree::linear(vec![
+            pr(100, "feature-a", "main", PrState::Open),
+            pr(101, "feature-b", "feature-a", PrState::Open),
+            pr(102, "feature-c", "feature-b", PrState::Open),
+        ]);
+
+        let plan = build_move_plan(
+            &stack,
+            "main",
+            "feature-b",
+            &MoveTarget::Onto("main".to_string()),
+        )
+        .expect("plan should build");
+
+        assert_eq!(
+            steps(&plan),
+            vec![
+                ("feature-b", "feature-a", "main"),
+                ("feature-c", "feature-b", "feature-a"),
+            ]
+        );
+        assert_eq!(plan.retargets.len(), 2);
+    }
+
+    #[test]
+    fn move_plan_puts_a_branch_before_another() {
+        let stack = StackTree::linear(vec![
+            pr(100, "feature-a", "main", PrState::Open),
+            pr(101, "feature-b", "feature-a", PrState::Open),
+            pr(102, "feature-c", "feature-b", PrState::Open),
+        ]);
+
+        let plan = build_move_plan(
+            &stack,
+            "main",
+            "feature-c",
+            &MoveTarget::Before("feature-a".to_string()),
+        )
+        .expect("plan should build");
+
+        assert_eq!(
+            steps(&plan),
+            vec![
+                ("feature-c", "feature-b", "main"),
+                ("feature-a", "main", "feature-c"),
+                ("feature-b", "feature-a", "feature-a"),
+            ]
+        );
+    }
+
+    #[test]
+    fn move_plan_rejects_no_op_and_unknown_targets() {
+        let stack = StackTree::linear(vec![
+            pr(100, "feature-a", "main", PrState::Open),
+            pr(101, "feature-b", "feature-a", PrState::Open),
+        ]);
+
+        assert_eq!(
+            build_move_plan(
+                &stack,
+                "main",
+                "feature-b",
+                &MoveTarget::Onto("feature-a".to_string())
+            ),
+            Err("feature-b is already stacked on feature-a".to_string())
+        );
+        assert_eq!(
+            build_move_plan(
+                &stack,
+                "main",
+                "feature-a",
+                &MoveTarget::Before("feature-b".to_string())
+            ),
+            Err("feature-a is already directly below feature-b".to_string())
+        );
+        assert_eq!(
+            build_move_plan(
+                &stack,
+                "main",
+                "feature-b",
+                &MoveTarget::Onto("feature-x".to_string())
+            ),
+            Err("feature-x is neither main nor an open branch of this stack".to_string())
+        );
+    }
 }
@@ -1,11 +1,17 @@
-//! Persistence for resumable `sync` and `push` workflows under `.git/stck/`.
+//! Persistence for resumable `sync`, `push`, `land`, and stack-edit workflows under `.git/stck/`,
+//! plus the append-only journal of finished operations browsed by `stck log`.
 
-use crate::github::PullRequest;
+use crate::forge::NewPullRequest;
+use crate::github::{MergeMethod, PullRequest};
 use crate::gitops;
 use crate::stack::{RetargetStep, SyncStep};
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::cell::RefCell;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Saved progress for an in-flight `stck sync` operation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +28,49 @@ pub struct SyncState {
     /// Repository and PR stack the sync plan was computed for.
     #[serde(default)]
     pub(crate) plan_scope: Option<SyncPlanScope>,
+    /// Whether the plan came from `stck restack` and rebases onto local refs only.
+    #[serde(default)]
+    pub offline: bool,
+    /// Id of the [`SyncBackup`] saved before the first rebase, if any.
+    #[serde(default)]
+    pub backup_id: Option<String>,
+}
+
+/// Branch state saved before a sync, restack, or land restack rewrote it,
+/// restored by `stck undo`.
+///
+/// The saved heads are also kept reachable under `refs/stck/backup/<id>/`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncBackup {
+    /// Backup id, the time it was taken in milliseconds since the Unix epoch.
+    pub id: String,
+    /// Branches the operation planned to rewrite, sorted by name.
+    pub branches: Vec<BackupBranch>,
+}
+
+/// One branch of a [`SyncBackup`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupBranch {
+    /// Branch name.
+    pub branch: String,
+    /// Head before the operation.
+    pub before: String,
+    /// Head the operation's rebase left the branch at, once that step completed.
+    #[serde(default)]
+    pub after: Option<String>,
+    /// Recorded `branch.<name>.stck-parent` before the operation.
+    #[serde(default)]
+    pub parent: Option<String>,
+    /// Recorded `branch.<name>.stck-fork-point` before the operation.
+    #[serde(default)]
+    pub fork_point: Option<String>,
+}
+
+impl BackupBranch {
+    /// Head the branch must still be at for `stck undo` to restore it safely.
+    pub fn expected_head(&self) -> &str {
+        self.after.as_deref().unwrap_or(&self.before)
+    }
 }
 
 /// Saved progress for an in-flight `stck push` operation.
@@ -40,6 +89,142 @@ pub struct PushState {
     pub completed_retargets: usize,
 }
 
+/// Saved progress for an in-flight `stck land` operation.
+///
+/// Once the merge succeeds the saved state is replaced by a [`SyncState`] for
+/// the restack, so later failures resume through `stck sync --continue`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LandState {
+    /// Branch whose PR is being merged.
+    pub branch: String,
+    /// Merge strategy passed to `gh pr merge`.
+    pub merge_method: MergeMethod,
+    /// Local and remote head the PR must still point at when it is merged.
+    pub head_sha: String,
+    /// Branch to check out after the remaining stack is restacked.
+    pub return_branch: String,
+    /// Child PR retargets that must land before the merge closes their base.
+    pub retargets: Vec<RetargetStep>,
+    /// Number of retarget operations that completed successfully.
+    pub completed_retargets: usize,
+    /// Whether the PR has already been merged on GitHub.
+    pub merged: bool,
+    /// Rebase steps that restack the landed branch's subtree after the merge.
+    pub steps: Vec<SyncStep>,
+}
+
+/// Saved progress for an in-flight stack edit such as `stck insert`.
+///
+/// Edits reshape the stack, so their PR retargets cannot be derived from the
+/// forge afterwards; the whole plan is computed up front and replayed from
+/// `completed_actions` when the edit is resumed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditState {
+    /// Subcommand that planned the edit, e.g. `insert`.
+    pub command: String,
+    /// Branch to check out once every action has run.
+    pub return_branch: String,
+    /// Ordered actions that carry out the edit.
+    pub actions: Vec<EditAction>,
+    /// Number of actions that completed successfully.
+    pub completed_actions: usize,
+    /// Branch head recorded when a rebase action failed, used to detect a
+    /// finished rebase on `--continue`.
+    #[serde(default)]
+    pub failed_rebase_head: Option<String>,
+}
+
+impl EditState {
+    /// Describe the saved edit for commands refused while it is unfinished.
+    pub fn in_progress_error(&self) -> String {
+        format!(
+            "a `stck {0}` is in progress; run `stck {0} --continue` to finish it or `stck {0} --reset` to discard it",
+            self.command
+        )
+    }
+}
+
+/// A single step of a stack edit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum EditAction {
+    /// Create `branch` at `start_point` without checking it out.
+    CreateBranch {
+        /// Branch to create.
+        branch: String,
+        /// Commit the new branch points at.
+        start_point: String,
+    },
+    /// Record `parent` as the stack parent of the local branch `branch`.
+    RecordParent {
+        /// Branch whose parent is recorded.
+        branch: String,
+        /// Branch it is stacked on.
+        parent: String,
+    },
+    /// Rename a local branch and repoint the branches recorded as stacked on it.
+    RenameBranch {
+        /// Current name of the branch.
+        from: String,
+        /// New name of the branch.
+        to: String,
+    },
+    /// Drop the recorded stack parent of `branch`.
+    ForgetParent {
+        /// Branch whose parent is forgotten.
+        branch: String,
+    },
+    /// Point an existing branch at `target`.
+    ResetBranch {
+        /// Branch to move.
+        branch: String,
+        /// Commit (or branch, resolved when the step runs) the branch points at afterwards.
+        target: String,
+    },
+    /// Check out an existing branch.
+    Checkout {
+        /// Branch to check out.
+        branch: String,
+    },
+    /// Delete a local branch that is not checked out.
+    DeleteBranch {
+        /// Branch to delete.
+        branch: String,
+    },
+    /// Rebase a branch onto its new parent from a fork point captured at plan time.
+    Rebase(SyncStep),
+    /// Push a new branch and set its upstream.
+    Publish {
+        /// Branch to push.
+        branch: String,
+    },
+    /// Force-push a rewritten branch, expecting the remote tip captured at plan time.
+    Push {
+        /// Branch to push.
+        branch: String,
+        /// Remote tip the push lease expects.
+        expected_remote_head: String,
+    },
+    /// Delete a branch from the push remote, expecting the tip captured at plan time.
+    DeleteRemoteBranch {
+        /// Branch to delete.
+        branch: String,
+        /// Remote tip the delete lease expects.
+        expected_remote_head: String,
+    },
+    /// Open a PR.
+    CreatePr(NewPullRequest),
+    /// Comment on and close a PR without merging it.
+    ClosePr {
+        /// Head branch of the PR.
+        branch: String,
+        /// Comment explaining why the PR was closed.
+        comment: String,
+    },
+    /// Change the base of a PR.
+    Retarget(RetargetStep),
+}
+
 /// Cached retarget plan produced by the most recent successful sync run.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LastSyncPlan {
@@ -119,11 +304,274 @@ impl LastSyncPlan {
     }
 }
 
+const LAND_IN_PROGRESS: &str =
+    "land operation state is in progress; run `stck land` to finish landing the PR";
+
+/// The operation saved in `.git/stck/last-plan.json`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "kind", rename_all = "snake_case")]
-enum LastPlanState {
+pub(crate) enum LastPlanState {
+    /// An in-flight `stck sync` or `stck restack`.
     Sync(SyncState),
+    /// An in-flight `stck push`.
     Push(PushState),
+    /// An in-flight `stck land`.
+    Land(LandState),
+    /// An in-flight stack edit.
+    Edit(EditState),
+}
+
+/// A PR change made by an operation, recorded in the journal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PrMutation {
+    /// Opened a PR for `head` against `base`.
+    Create {
+        /// Head branch of the new PR.
+        head: String,
+        /// Base branch of the new PR.
+        base: String,
+    },
+    /// Changed the base of the PR for `branch`.
+    Retarget {
+        /// Head branch of the PR.
+        branch: String,
+        /// Base branch it now targets.
+        new_base: String,
+    },
+    /// Rewrote the description of the PR for `branch`.
+    UpdateBody {
+        /// Head branch of the PR.
+        branch: String,
+    },
+    /// Closed the PR for `branch` without merging it.
+    Close {
+        /// Head branch of the PR.
+        branch: String,
+    },
+    /// Merged the PR for `branch`.
+    Merge {
+        /// Head branch of the PR.
+        branch: String,
+        /// Merge strategy used.
+        method: MergeMethod,
+    },
+}
+
+impl std::fmt::Display for PrMutation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrMutation::Create { head, base } => write!(f, "opened PR for {head} against {base}"),
+            PrMutation::Retarget { branch, new_base } => {
+                write!(f, "retargeted PR for {branch} to {new_base}")
+            }
+            PrMutation::UpdateBody { branch } => {
+                write!(f, "updated the description of the PR for {branch}")
+            }
+            PrMutation::Close { branch } => write!(f, "closed PR for {branch}"),
+            PrMutation::Merge { branch, method } => {
+                write!(f, "merged PR for {branch} ({method})")
+            }
+        }
+    }
+}
+
+/// A local branch whose head an operation changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchChange {
+    /// Branch name.
+    pub branch: String,
+    /// Head before the operation, or `None` when the branch was created.
+    pub before: Option<String>,
+    /// Head after the operation, or `None` when the branch was deleted.
+    pub after: Option<String>,
+}
+
+/// How a journaled operation ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// The command exited successfully.
+    Succeeded,
+    /// The command failed or stopped for a conflict; see its saved state.
+    Failed,
+}
+
+/// One stck command run, appended to `.git/stck/journal.jsonl` when it exits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Seconds since the Unix epoch when the command started.
+    pub timestamp: u64,
+    /// Arguments the command ran with, e.g. `sync --continue`.
+    pub command: String,
+    /// First operation state the command saved, which holds its full plan.
+    #[serde(default)]
+    pub(crate) plan: Option<LastPlanState>,
+    /// Local branches whose heads changed, sorted by name.
+    #[serde(default)]
+    pub branches: Vec<BranchChange>,
+    /// PR changes, in the order they were made.
+    #[serde(default)]
+    pub pr_mutations: Vec<PrMutation>,
+    /// How the command ended.
+    pub outcome: Outcome,
+}
+
+/// The journal entry of the running command and the branch heads it started from.
+#[derive(Debug)]
+struct Recording {
+    entry: JournalEntry,
+    heads_before: Vec<(String, String)>,
+}
+
+/// Handle that records the running command into the journal.
+///
+/// Clones share one recording, so a context copied mid-command still records
+/// into the same entry. The default handle records nothing.
+#[derive(Debug, Clone, Default)]
+pub struct Journal {
+    recording: Rc<RefCell<Option<Recording>>>,
+}
+
+impl Journal {
+    /// Start journaling the running command.
+    ///
+    /// The journal is best effort: outside a repository, or if branch heads
+    /// cannot be read, the command simply runs unrecorded.
+    pub fn begin(command: String) -> Self {
+        let Ok(heads_before) = gitops::local_branch_heads() else {
+            return Self::default();
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let recording = Recording {
+            entry: JournalEntry {
+                timestamp,
+                command,
+                plan: None,
+                branches: Vec::new(),
+                pr_mutations: Vec::new(),
+                outcome: Outcome::Failed,
+            },
+            heads_before,
+        };
+        Self {
+            recording: Rc::new(RefCell::new(Some(recording))),
+        }
+    }
+
+    /// Note a PR change made by the running command.
+    pub fn record_pr_mutation(&self, mutation: PrMutation) {
+        if let Some(recording) = self.recording.borrow_mut().as_mut() {
+            recording.entry.pr_mutations.push(mutation);
+        }
+    }
+
+    /// Note an operation state saved by the running command.
+    ///
+    /// The first save of a command holds its whole plan; later ones only
+    /// record progress.
+    fn record_plan(&self, state: &LastPlanState) {
+        if let Some(recording) = self.recording.borrow_mut().as_mut() {
+            recording.entry.plan.get_or_insert_with(|| state.clone());
+        }
+    }
+
+    /// Finish journaling the running command and append its entry.
+    ///
+    /// A journal write failure is reported as a warning; it never changes the
+    /// command's own result.
+    pub fn finish(&self, outcome: Outcome) {
+        let Some(Recording {
+            mut entry,
+            heads_before,
+        }) = self.recording.borrow_mut().take()
+        else {
+            return;
+        };
+        entry.outcome = outcome;
+        let result = gitops::local_branch_heads().and_then(|heads_after| {
+            entry.branches = branch_changes(&heads_before, &heads_after);
+            append_journal_entry(&entry)
+        });
+        if let Err(message) = result {
+            eprintln!("warning: failed to record the operation in the stck journal: {message}");
+        }
+    }
+}
+
+/// Load every journal entry, oldest first.
+pub fn load_journal() -> Result<Vec<JournalEntry>, String> {
+    let path = journal_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let raw = fs::read_to_string(&path)
+        .map_err(|_| format!("failed to read journal at {}", path.display()))?;
+    raw.lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(index, line)| {
+            serde_json::from_str::<JournalEntry>(line).map_err(|_| {
+                format!(
+                    "failed to parse entry {} of the journal at {}",
+                    index + 1,
+                    path.display()
+                )
+            })
+        })
+        .collect()
+}
+
+fn append_journal_entry(entry: &JournalEntry) -> Result<(), String> {
+    let path = journal_path()?;
+    let parent = path
+        .parent()
+        .ok_or_else(|| "failed to compute parent directory for journal".to_string())?;
+    fs::create_dir_all(parent)
+        .map_err(|_| format!("failed to create state directory {}", parent.display()))?;
+
+    let mut line = serde_json::to_string(entry)
+        .map_err(|_| "failed to serialize journal entry".to_string())?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|_| format!("failed to append to journal at {}", path.display()))
+}
+
+/// Compare two sorted `(branch, head)` snapshots.
+fn branch_changes(before: &[(String, String)], after: &[(String, String)]) -> Vec<BranchChange> {
+    let head_in = |heads: &[(String, String)], branch: &str| {
+        heads
+            .iter()
+            .find(|(name, _)| name == branch)
+            .map(|(_, head)| head.clone())
+    };
+    let mut branches = before
+        .iter()
+        .chain(after)
+        .map(|(branch, _)| branch.clone())
+        .collect::<Vec<_>>();
+    branches.sort();
+    branches.dedup();
+    branches
+        .into_iter()
+        .filter_map(|branch| {
+            let before = head_in(before, &branch);
+            let after = head_in(after, &branch);
+            (before != after).then_some(BranchChange {
+                branch,
+                before,
+                after,
+            })
+        })
+        .collect()
 }
 
 /// Load the current saved sync state, if one exists.
@@ -143,12 +591,14 @@ pub fn load_sync() -> Result<Option<SyncState>, String> {
             "push operation state is in progress; run `stck push` before starting a new sync"
                 .to_string(),
         ),
+        LastPlanState::Land(_) => Err(LAND_IN_PROGRESS.to_string()),
+        LastPlanState::Edit(edit) => Err(edit.in_progress_error()),
     }
 }
 
 /// Persist sync progress for later `stck sync --continue` or `--reset` flows.
-pub fn save_sync(state: &SyncState) -> Result<(), String> {
-    save_raw_state(LastPlanState::Sync(state.clone()))
+pub fn save_sync(journal: &Journal, state: &SyncState) -> Result<(), String> {
+    save_raw_state(journal, LastPlanState::Sync(state.clone()))
 }
 
 /// Load the current saved push state, if one exists.
@@ -168,15 +618,93 @@ pub fn load_push() -> Result<Option<PushState>, String> {
             "sync operation state is in progress; run `stck sync --continue` before running push"
                 .to_string(),
         ),
+        LastPlanState::Land(_) => Err(LAND_IN_PROGRESS.to_string()),
+        LastPlanState::Edit(edit) => Err(edit.in_progress_error()),
     }
 }
 
 /// Persist push progress for later resume attempts.
-pub fn save_push(state: &PushState) -> Result<(), String> {
-    save_raw_state(LastPlanState::Push(state.clone()))
+pub fn save_push(journal: &Journal, state: &PushState) -> Result<(), String> {
+    save_raw_state(journal, LastPlanState::Push(state.clone()))
 }
 
-/// Remove any saved sync or push state file.
+/// Load the current saved land state, if one exists.
+///
+/// Saved sync or push state is reported as an error so an interrupted restack
+/// is finished before another PR is landed.
+pub fn load_land() -> Result<Option<LandState>, String> {
+    let path = state_file_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let state = load_raw_state(&path)?;
+    match state {
+        LastPlanState::Land(land) => Ok(Some(land)),
+        LastPlanState::Sync(_) => Err(
+            "sync operation state is in progress; run `stck sync --continue` before landing"
+                .to_string(),
+        ),
+        LastPlanState::Push(_) => {
+            Err("push operation state is in progress; run `stck push` before landing".to_string())
+        }
+        LastPlanState::Edit(edit) => Err(edit.in_progress_error()),
+    }
+}
+
+/// Persist land progress for later resume attempts.
+pub fn save_land(journal: &Journal, state: &LandState) -> Result<(), String> {
+    save_raw_state(journal, LastPlanState::Land(state.clone()))
+}
+
+/// Load the current saved stack-edit state, if one exists.
+///
+/// Saved sync, push, or land state is reported as an error so the stack is
+/// not reshaped while another workflow is half done.
+pub fn load_edit() -> Result<Option<EditState>, String> {
+    let path = state_file_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let state = load_raw_state(&path)?;
+    match state {
+        LastPlanState::Edit(edit) => Ok(Some(edit)),
+        LastPlanState::Sync(_) => Err(
+            "sync operation state is in progress; run `stck sync --continue` before editing the stack"
+                .to_string(),
+        ),
+        LastPlanState::Push(_) => Err(
+            "push operation state is in progress; run `stck push` before editing the stack"
+                .to_string(),
+        ),
+        LastPlanState::Land(_) => Err(LAND_IN_PROGRESS.to_string()),
+    }
+}
+
+/// Persist stack-edit progress for later `--continue` runs.
+pub fn save_edit(journal: &Journal, state: &EditState) -> Result<(), String> {
+    save_raw_state(journal, LastPlanState::Edit(state.clone()))
+}
+
+/// Check that the saved operation, if any, is one `stck undo` may discard.
+///
+/// Sync and push state belong to the rewrite being undone; land and
+/// stack-edit state are reported as errors so they are finished first.
+pub fn ensure_undoable() -> Result<(), String> {
+    let path = state_file_path()?;
+    if !path.exists() {
+        return Ok(());
+    }
+
+    match load_raw_state(&path)? {
+        LastPlanState::Sync(_) | LastPlanState::Push(_) => Ok(()),
+        LastPlanState::Land(_) => Err(LAND_IN_PROGRESS.to_string()),
+        LastPlanState::Edit(edit) => Err(edit.in_progress_error()),
+    }
+}
+
+/// Remove any saved sync, push, land, or stack-edit state file.
 pub fn clear() -> Result<(), String> {
     let path = state_file_path()?;
     if !path.exists() {
@@ -223,11 +751,90 @@ pub fn clear_last_sync_plan() -> Result<(), String> {
     fs::remove_file(&path).map_err(|_| format!("failed to remove sync state at {}", path.display()))
 }
 
-/// Return the path to the shared sync/push state file under `.git/stck/`.
+/// Persist a branch backup under `.git/stck/backups/`.
+pub fn save_backup(backup: &SyncBackup) -> Result<(), String> {
+    let path = backups_dir()?.join(format!("{}.json", backup.id));
+    let parent = path
+        .parent()
+        .ok_or_else(|| "failed to compute parent directory for backup".to_string())?;
+    fs::create_dir_all(parent)
+        .map_err(|_| format!("failed to create state directory {}", parent.display()))?;
+
+    let raw = serde_json::to_vec_pretty(backup)
+        .map_err(|_| "failed to serialize branch backup".to_string())?;
+    fs::write(&path, raw).map_err(|_| format!("failed to write backup at {}", path.display()))
+}
+
+/// Load every saved branch backup, newest first.
+///
+/// Backup ids are millisecond timestamps, so they order numerically.
+pub fn load_backups() -> Result<Vec<SyncBackup>, String> {
+    let dir = backups_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries =
+        fs::read_dir(&dir).map_err(|_| format!("failed to read backups at {}", dir.display()))?;
+    let mut backups = Vec::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|_| format!("failed to read backups at {}", dir.display()))?
+            .path();
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+        let raw =
+            fs::read(&path).map_err(|_| format!("failed to read backup at {}", path.display()))?;
+        let backup = serde_json::from_slice::<SyncBackup>(&raw)
+            .map_err(|_| format!("failed to parse backup at {}", path.display()))?;
+        backups.push(backup);
+    }
+    backups.sort_by_key(|backup: &SyncBackup| {
+        std::cmp::Reverse(backup.id.parse::<u128>().unwrap_or(0))
+    });
+    Ok(backups)
+}
+
+/// Note the head a completed rebase step left `branch` at in backup `id`.
+pub fn record_backup_head(id: &str, branch: &str, head: &str) -> Result<(), String> {
+    let Some(mut backup) = load_backups()?.into_iter().find(|backup| backup.id == id) else {
+        return Ok(());
+    };
+    if let Some(saved) = backup
+        .branches
+        .iter_mut()
+        .find(|saved| saved.branch == branch)
+    {
+        saved.after = Some(head.to_string());
+    }
+    save_backup(&backup)
+}
+
+/// Remove the saved file of backup `id`.
+pub fn delete_backup(id: &str) -> Result<(), String> {
+    let path = backups_dir()?.join(format!("{id}.json"));
+    if !path.exists() {
+        return Ok(());
+    }
+
+    fs::remove_file(&path).map_err(|_| format!("failed to remove backup at {}", path.display()))
+}
+
+fn backups_dir() -> Result<PathBuf, String> {
+    Ok(gitops::git_dir()?.join("stck").join("backups"))
+}
+
+/// Return the path to the shared operation state file under `.git/stck/`.
 pub fn state_file_path() -> Result<PathBuf, String> {
     Ok(gitops::git_dir()?.join("stck").join("last-plan.json"))
 }
 
+/// Return the path to the operation journal under `.git/stck/`.
+pub fn journal_path() -> Result<PathBuf, String> {
+    Ok(gitops::git_dir()?.join("stck").join("journal.jsonl"))
+}
+
 /// Return the path to the cached last-sync plan file under `.git/stck/`.
 pub fn last_sync_plan_path() -> Result<PathBuf, String> {
     Ok(gitops::git_dir()?.join("stck").join("last-sync-plan.json"))
@@ -239,7 +846,9 @@ fn load_raw_state(path: &PathBuf) -> Result<LastPlanState, String> {
         .map_err(|_| format!("failed to parse state at {}", path.display()))
 }
 
-fn save_raw_state(state: LastPlanState) -> Result<(), String> {
+fn save_raw_state(journal: &Journal, state: LastPlanState) -> Result<(), String> {
+    journal.record_plan(&state);
+
     let path = state_file_path()?;
     let parent = path
         .parent()
@@ -255,7 +864,7 @@ fn save_raw_state(state: LastPlanState) -> Result<(), String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::github::PrState;
+    use crate::github::{MergeMethod, PrState};
     use crate::stack::{RetargetStep, SyncStep};
 
     fn stack() -> Vec<PullRequest> {
@@ -300,17 +909,21 @@ mod tests {
                     branch: "feature-b".to_string(),
                     old_base_ref: "feature-a".to_string(),
                     new_base_ref: "main".to_string(),
+                    fork_point: None,
                 },
                 SyncStep {
                     branch: "feature-c".to_string(),
                     old_base_ref: "feature-b".to_string(),
                     new_base_ref: "feature-b".to_string(),
+                    fork_point: None,
                 },
             ],
             completed_steps: 1,
             failed_step: Some(1),
             failed_step_branch_head: Some("abcd1234".to_string()),
             plan_scope: Some(scope()),
+            offline: false,
+            backup_id: None,
         };
 
         let wrapped = LastPlanState::Sync(state.clone());
@@ -328,7 +941,7 @@ mod tests {
                 assert_eq!(s.failed_step_branch_head, Some("abcd1234".to_string()));
                 assert_eq!(s.plan_scope, Some(scope()));
             }
-            LastPlanState::Push(_) => panic!("expected Sync variant"),
+            _ => panic!("expected Sync variant"),
         }
     }
 
@@ -359,7 +972,90 @@ mod tests {
                 assert_eq!(p.retargets[0].branch, "feature-b");
                 assert_eq!(p.completed_retargets, 0);
             }
-            LastPlanState::Sync(_) => panic!("expected Push variant"),
+            _ => panic!("expected Push variant"),
+        }
+    }
+
+    #[test]
+    fn land_state_round_trip() {
+        let state = LandState {
+            branch: "feature-b".to_string(),
+            merge_method: MergeMethod::Squash,
+            head_sha: "bbbb1234".to_string(),
+            return_branch: "feature-c".to_string(),
+            retargets: vec![RetargetStep {
+                branch: "feature-c".to_string(),
+                new_base_ref: "main".to_string(),
+            }],
+            completed_retargets: 1,
+            merged: false,
+            steps: vec![SyncStep {
+                branch: "feature-c".to_string(),
+                old_base_ref: "feature-b".to_string(),
+                new_base_ref: "main".to_string(),
+                fork_point: None,
+            }],
+        };
+
+        let json = serde_json::to_string(&LastPlanState::Land(state)).expect("serialize land");
+        assert!(json.contains(r#""kind":"land""#));
+        assert!(json.contains(r#""merge_method":"squash""#));
+
+        match serde_json::from_str::<LastPlanState>(&json).expect("deserialize land") {
+            LastPlanState::Land(l) => {
+                assert_eq!(l.branch, "feature-b");
+                assert_eq!(l.merge_method, MergeMethod::Squash);
+                assert_eq!(l.return_branch, "feature-c");
+                assert_eq!(l.completed_retargets, 1);
+                assert!(!l.merged);
+                assert_eq!(l.steps[0].old_base_ref, "feature-b");
+            }
+            _ => panic!("expected Land variant"),
+        }
+    }
+
+    #[test]
+    fn edit_state_round_trip() {
+        let state = EditState {
+            command: "insert".to_string(),
+            return_branch: "feature-fix".to_string(),
+            actions: vec![
+                EditAction::CreateBranch {
+                    branch: "feature-fix".to_string(),
+                    start_point: "aaaa1234".to_string(),
+                },
+                EditAction::Rebase(SyncStep {
+                    branch: "feature-b".to_string(),
+                    old_base_ref: "feature-a".to_string(),
+                    new_base_ref: "feature-fix".to_string(),
+                    fork_point: Some("aaaa1234".to_string()),
+                }),
+                EditAction::Retarget(RetargetStep {
+                    branch: "feature-b".to_string(),
+                    new_base_ref: "feature-fix".to_string(),
+                }),
+            ],
+            completed_actions: 1,
+            failed_rebase_head: Some("bbbb1234".to_string()),
+        };
+
+        let json = serde_json::to_string(&LastPlanState::Edit(state)).expect("serialize edit");
+        assert!(json.contains(r#""kind":"edit""#));
+        assert!(json.contains(r#""action":"create_branch""#));
+        assert!(json.contains(r#""action":"rebase""#));
+
+        match serde_json::from_str::<LastPlanState>(&json).expect("deserialize edit") {
+            LastPlanState::Edit(e) => {
+                assert_eq!(e.command, "insert");
+                assert_eq!(e.actions.len(), 3);
+                assert_eq!(e.completed_actions, 1);
+                assert_eq!(e.failed_rebase_head, Some("bbbb1234".to_string()));
+                assert!(matches!(
+                    &e.actions[1],
+                    EditAction::Rebase(step) if step.fork_point.as_deref() == Some("aaaa1234")
+                ));
+            }
+            _ => panic!("expected Edit variant"),
         }
     }
 
@@ -399,6 +1095,8 @@ mod tests {
             failed_step: None,
             failed_step_branch_head: None,
             plan_scope: Some(scope()),
+            offline: false,
+            backup_id: None,
         });
         let push = LastPlanState::Push(PushState {
             push_branches: vec![],
@@ -432,11 +1130,14 @@ mod tests {
                 branch: "feature-b".to_string(),
                 old_base_ref: "feature-a".to_string(),
                 new_base_ref: "main".to_string(),
+                fork_point: None,
             }],
             completed_steps: 1,
             failed_step: None,
             failed_step_branch_head: None,
             plan_scope: Some(scope()),
+            offline: false,
+            backup_id: None,
         };
 
         let wrapped = LastPlanState::Sync(state);
@@ -450,7 +1151,7 @@ mod tests {
                 assert_eq!(s.failed_step, None);
                 assert_eq!(s.failed_step_branch_head, None);
             }
-            LastPlanState::Push(_) => panic!("expected Sync variant"),
+            _ => panic!("expected Sync variant"),
         }
     }
 
@@ -486,4 +1187,72 @@ mod tests {
         assert_eq!(plan.scope, None);
         assert!(!plan.matches("example/stck", "main", &stack()));
     }
+
+    #[test]
+    fn branch_changes_report_moved_created_and_deleted_branches() {
+        let head = |branch: &str, sha: &str| (branch.to_string(), sha.to_string());
+        let before = vec![
+            head("feature-a", "aaaa"),
+            head("feature-b", "bbbb"),
+            head("main", "mmmm"),
+        ];
+        let after = vec![
+            head("feature-a", "aaa2"),
+            head("feature-c", "cccc"),
+            head("main", "mmmm"),
+        ];
+
+        assert_eq!(
+            branch_changes(&before, &after),
+            vec![
+                BranchChange {
+                    branch: "feature-a".to_string(),
+                    before: Some("aaaa".to_string()),
+                    after: Some("aaa2".to_string()),
+                },
+                BranchChange {
+                    branch: "feature-b".to_string(),
+                    before: Some("bbbb".to_string()),
+                    after: None,
+                },
+                BranchChange {
+                    branch: "feature-c".to_string(),
+                    before: None,
+                    after: Some("cccc".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn journal_entry_round_trips_with_its_plan_and_pr_mutations() {
+        let entry = JournalEntry {
+            timestamp: 1_792_236_192,
+            command: "push".to_string(),
+            plan: Some(LastPlanState::Push(PushState {
+                push_branches: vec!["feature-b".to_string()],
+                completed_pushes: 0,
+                sync_push_leases: Vec::new(),
+                retargets: Vec::new(),
+                completed_retargets: 0,
+            })),
+            branches: Vec::new(),
+            pr_mutations: vec![PrMutation::Merge {
+                branch: "feature-b".to_string(),
+                method: MergeMethod::Squash,
+            }],
+            outcome: Outcome::Succeeded,
+        };
+
+        let line = serde_json::to_string(&entry).expect("serialize should succeed");
+        assert!(!line.contains('\n'), "journal entries must fit on one line");
+        let restored: JournalEntry =
+            serde_json::from_str(&line).expect("deserialize should succeed");
+
+        assert!(
+            matches!(restored.plan, Some(LastPlanState::Push(ref push)) if push.push_branches == ["feature-b"])
+        );
+        assert_eq!(restored.pr_mutations, entry.pr_mutations);
+        assert_eq!(restored.outcome, Outcome::Succeeded);
+    }
 }
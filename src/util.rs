@@ -1,4 +1,24 @@
-//! Small formatting helpers shared across subprocess-backed modules.
+//! Small helpers shared across subprocess-backed modules.
+
+use std::process::Command;
+
+/// Check that `command` is installed by running `<command> --version`.
+pub fn ensure_command_available(command: &str) -> Result<(), String> {
+    let output = Command::new(command)
+        .arg("--version")
+        .output()
+        .map_err(|_| {
+            format!("required command `{command}` was not found in PATH; install it and retry")
+        })?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "failed to execute `{command} --version`; ensure `{command}` is installed and runnable"
+        ))
+    }
+}
 
 /// Append trimmed stderr output to a base error message when detail is available.
 pub fn with_stderr(base: &str, stderr: &[u8]) -> String {
@@ -10,6 +30,49 @@ pub fn with_stderr(base: &str, stderr: &[u8]) -> String {
     }
 }
 
+/// Render `value` as a single POSIX shell word for echoed commands.
+///
+/// Plain branch-like values are shown as is; anything else is single-quoted.
+pub fn shell_word(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value.chars().all(|character| {
+            character.is_ascii_alphanumeric()
+                || matches!(character, '-' | '_' | '.' | '/' | ',' | ':' | '@')
+        });
+    if plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Format seconds since the Unix epoch as `YYYY-MM-DD HH:MM:SS UTC`.
+pub fn format_utc(timestamp: u64) -> String {
+    let days = timestamp / 86_400;
+    let seconds = timestamp % 86_400;
+    // Civil-from-days conversion for the proleptic Gregorian calendar.
+    let shifted = days + 719_468;
+    let era = shifted / 146_097;
+    let day_of_era = shifted % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -34,4 +97,23 @@ mod tests {
             "something failed; stderr: fatal: bad object"
         );
     }
+
+    #[test]
+    fn leaves_plain_shell_words_unquoted() {
+        assert_eq!(shell_word("feature/login-v2"), "feature/login-v2");
+    }
+
+    #[test]
+    fn single_quotes_words_with_spaces_and_quotes() {
+        assert_eq!(shell_word("Add login"), "'Add login'");
+        assert_eq!(shell_word("Don't panic"), "'Don'\\''t panic'");
+        assert_eq!(shell_word(""), "''");
+    }
+
+    #[test]
+    fn formats_unix_timestamps_as_utc_dates() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_utc(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_utc(1_792_236_192), "2026-10-17 11:23:12 UTC");
+    }
 }
@@ -39,3 +39,25 @@ fn version_prints_package_version() {
         .success()
         .stdout(predicate::str::contains(env!("CARGO_PKG_VERSION")));
 }
+
+#[test]
+fn move_requires_exactly_one_target() {
+    let mut missing = stck_cmd();
+    missing.args(["move", "feature-b"]);
+    missing.assert().code(2).stderr(predicate::str::contains(
+        "<--onto <BRANCH>|--before <BRANCH>>",
+    ));
+
+    let mut both = stck_cmd();
+    both.args([
+        "move",
+        "feature-b",
+        "--onto",
+        "main",
+        "--before",
+        "feature-a",
+    ]);
+    both.assert().code(2).stderr(predicate::str::contains(
+        "the argument '--onto <BRANCH>' cannot be used with '--before <BRANCH>'",
+    ));
+}
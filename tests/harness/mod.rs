@@ -2,10 +2,15 @@
 
 use assert_cmd::cargo::cargo_bin_cmd;
 use assert_cmd::Command;
+use serde_json::{json, Value};
 use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::process::{Command as StdCommand, Output};
+use std::sync::{Arc, Mutex};
+use std::thread;
 use tempfile::TempDir;
 
 pub fn stck_cmd() -> Command {
@@ -83,6 +88,19 @@ if [[ "${1:-}" == "for-each-ref" && "${2:-}" == "--format=%(refname:strip=3)" &&
   exit 0
 fi
 
+if [[ "${1:-}" == "for-each-ref" && "${3:-}" == "refs/stck/backup/" ]]; then
+  exit 0
+fi
+
+if [[ "${1:-}" == "update-ref" && "${2:-}" == "--stdin" ]]; then
+  if [[ -n "${STCK_TEST_LOG:-}" ]]; then
+    sed 's/^/git update-ref /' >> "${STCK_TEST_LOG}"
+  else
+    cat > /dev/null
+  fi
+  exit 0
+fi
+
 if [[ "${1:-}" == "rev-parse" && "${2:-}" == "--verify" ]]; then
   ref="${3:-}"
 
@@ -420,6 +438,10 @@ if [[ "${1:-}" == "auth" && "${2:-}" == "status" ]]; then
 fi
 
 if [[ "${1:-}" == "repo" && "${2:-}" == "view" ]]; then
+  if [[ "$*" == *"squashMergeAllowed"* ]]; then
+    printf '%b\n' "${STCK_TEST_MERGE_METHODS:-true\ttrue\ttrue}"
+    exit 0
+  fi
   if [[ "${STCK_TEST_DEFAULT_BRANCH_FAIL:-0}" == "1" ]]; then
     exit 1
   fi
@@ -562,6 +584,11 @@ if [[ "${1:-}" == "pr" && "${2:-}" == "view" ]]; then
     exit 1
   fi
 
+  if [[ "${all_args}" == *"--json body"* ]]; then
+    printf '%s\n' "${STCK_TEST_PR_BODY:-}"
+    exit 0
+  fi
+
   if [[ "${all_args}" == *"headRefName"* ]]; then
     if [[ "${STCK_TEST_MISSING_CURRENT_PR:-0}" == "1" && "${branch}" == "feature-branch" ]]; then
       echo "no pull requests found for branch ${branch}" >&2
@@ -630,6 +657,17 @@ if [[ "${1:-}" == "pr" && "${2:-}" == "edit" ]]; then
   exit 0
 fi
 
+if [[ "${1:-}" == "pr" && "${2:-}" == "merge" ]]; then
+  if [[ -n "${STCK_TEST_LOG:-}" ]]; then
+    echo "$*" >> "${STCK_TEST_LOG}"
+  fi
+  if [[ "${STCK_TEST_PR_MERGE_FAIL:-0}" == "1" ]]; then
+    echo "Pull request is not mergeable" >&2
+    exit 1
+  fi
+  exit 0
+fi
+
 if [[ "${1:-}" == "pr" && "${2:-}" == "create" ]]; then
   base=""
   head=""
@@ -653,6 +691,12 @@ if [[ "${1:-}" == "pr" && "${2:-}" == "create" ]]; then
   exit 0
 fi
 
+if [[ "${1:-}" == "api" && "${2:-}" == "graphql" ]]; then
+  # Reject batched lookups so discovery uses the per-branch stubs above.
+  echo '{"errors":[{"message":"graphql is not stubbed","extensions":{"code":"undefinedField"}}]}'
+  exit 1
+fi
+
 exit 0
 "#,
     );
@@ -669,6 +713,7 @@ pub fn stck_cmd_for_temp(temp: &TempDir) -> Command {
     let mut cmd = stck_cmd();
     cmd.env("PATH", full_path);
     cmd.env("STCK_TEST_GIT_DIR", git_dir.as_os_str());
+    cmd.env("XDG_CONFIG_HOME", temp.path().join("xdg-config"));
     cmd
 }
 
@@ -692,6 +737,7 @@ pub struct RealGitRepo {
     bin_dir: PathBuf,
     gh_responses: PathBuf,
     gh_log: PathBuf,
+    glab_log: PathBuf,
     global_git_config: PathBuf,
 }
 
@@ -703,11 +749,13 @@ impl RealGitRepo {
         let bin_dir = temp.path().join("bin");
         let gh_responses = temp.path().join("gh-responses");
         let gh_log = temp.path().join("gh.log");
+        let glab_log = temp.path().join("glab.log");
         let global_git_config = temp.path().join("global.gitconfig");
 
         fs::create_dir_all(&bin_dir).expect("real-git bin dir should be created");
         fs::create_dir_all(&gh_responses).expect("real-git gh response dir should be created");
         fs::write(&gh_log, "").expect("real-git gh log should be initialized");
+        fs::write(&glab_log, "").expect("real-git glab log should be initialized");
         fs::write(&global_git_config, "")
             .expect("isolated global git config should be initialized");
 
@@ -728,10 +776,40 @@ if [[ "${1:-}" == "auth" && "${2:-}" == "status" ]]; then
 fi
 
 if [[ "${1:-}" == "repo" && "${2:-}" == "view" ]]; then
+  if [[ "$*" == *"squashMergeAllowed"* ]]; then
+    printf 'false\ttrue\ttrue\n'
+    exit 0
+  fi
   printf 'example/stck\tmain\n'
   exit 0
 fi
 
+if [[ "${1:-}" == "pr" && "${2:-}" == "merge" ]]; then
+  # Squash the PR head onto origin's main, then publish the post-merge PR
+  # metadata staged by the test.
+  branch="${3:-}"
+  safe_branch="${branch//\//__}"
+  tree="$(git --git-dir "${STCK_REAL_ORIGIN}" rev-parse "refs/heads/${branch}^{tree}")"
+  commit="$(git -c user.name=github -c user.email=noreply@github.com --git-dir "${STCK_REAL_ORIGIN}" commit-tree "${tree}" -p refs/heads/main -m "Squash ${branch}")"
+  git --git-dir "${STCK_REAL_ORIGIN}" update-ref refs/heads/main "${commit}"
+  staged="${STCK_REAL_GH_RESPONSES}/after-merge-${safe_branch}"
+  if [[ -d "${staged}" ]]; then
+    cp "${staged}"/* "${STCK_REAL_GH_RESPONSES}/"
+  fi
+  exit 0
+fi
+
+if [[ "${1:-}" == "pr" && "${2:-}" == "view" && "$*" == *"--json body"* ]]; then
+  branch="${3:-}"
+  safe_branch="${branch//\//__}"
+  body="${STCK_REAL_GH_RESPONSES}/pr-body-${safe_branch}.md"
+  if [[ -f "${body}" ]]; then
+    cat "${body}"
+  fi
+  echo
+  exit 0
+fi
+
 if [[ "${1:-}" == "pr" && "${2:-}" == "view" ]]; then
   branch="${3:-}"
   safe_branch="${branch//\//__}"
@@ -775,15 +853,81 @@ if [[ "${1:-}" == "pr" && "${2:-}" == "list" ]]; then
   exit 0
 fi
 
+if [[ "${1:-}" == "pr" && "${2:-}" == "edit" && "${4:-}" == "--body" ]]; then
+  safe_branch="${3//\//__}"
+  printf '%s' "${5:-}" > "${STCK_REAL_GH_RESPONSES}/pr-body-${safe_branch}.md"
+  exit 0
+fi
+
 if [[ "${1:-}" == "pr" && ( "${2:-}" == "create" || "${2:-}" == "edit" ) ]]; then
   exit 0
 fi
 
+if [[ "${1:-}" == "api" && "${2:-}" == "graphql" ]]; then
+  response="${STCK_REAL_GH_RESPONSES}/graphql.json"
+  if [[ -f "${STCK_REAL_GH_RESPONSES}/graphql.fail" ]]; then
+    cat "${STCK_REAL_GH_RESPONSES}/graphql.fail" >&2
+    exit 1
+  fi
+  if [[ -f "${response}" ]]; then
+    cat "${response}"
+    exit 0
+  fi
+  echo '{"errors":[{"message":"graphql is not stubbed","extensions":{"code":"undefinedField"}}]}'
+  echo "gh: graphql is not stubbed" >&2
+  exit 1
+fi
+
 echo "unsupported gh invocation: $*" >&2
 exit 1
 "#,
         );
 
+        write_stub(
+            &bin_dir.join("glab"),
+            r#"#!/usr/bin/env bash
+set -euo pipefail
+
+printf '%s\n' "$*" >> "${STCK_REAL_GLAB_LOG}"
+
+if [[ "${1:-}" == "--version" ]]; then
+  echo "glab 1.40.0"
+  exit 0
+fi
+
+if [[ "${1:-}" == "auth" && "${2:-}" == "status" ]]; then
+  exit 0
+fi
+
+if [[ "${1:-}" == "api" && "${2:-}" == "projects/:id" ]]; then
+  printf '{"path_with_namespace":"example/stck","default_branch":"main","merge_method":"merge","squash_option":"default_off"}\n'
+  exit 0
+fi
+
+if [[ "${1:-}" == "api" && "${2:-}" == projects/:id/merge_requests\?* ]]; then
+  query="${2#*\?}"
+  filter="${query%%=*}"
+  value="${query#*=}"
+  value="${value%%&*}"
+  safe_value="${value//%2F/__}"
+  response="${STCK_REAL_GH_RESPONSES}/mr-list-${filter}-${safe_value}.json"
+  if [[ -f "${response}" ]]; then
+    cat "${response}"
+  else
+    echo "[]"
+  fi
+  exit 0
+fi
+
+if [[ "${1:-}" == "mr" && ( "${2:-}" == "create" || "${2:-}" == "update" || "${2:-}" == "merge" ) ]]; then
+  exit 0
+fi
+
+echo "unsupported glab invocation: $*" >&2
+exit 1
+"#,
+        );
+
         let remote_arg = remote.to_string_lossy().into_owned();
         let worktree_arg = worktree.to_string_lossy().into_owned();
         assert_git_success(
@@ -811,6 +955,7 @@ exit 1
             bin_dir,
             gh_responses,
             gh_log,
+            glab_log,
             global_git_config,
         };
 
@@ -848,12 +993,25 @@ exit 1
         cmd.env("GIT_CONFIG_GLOBAL", &self.global_git_config);
         cmd.env("GIT_CONFIG_NOSYSTEM", "1");
         cmd.env("STCK_REAL_GH_LOG", &self.gh_log);
+        cmd.env("STCK_REAL_GLAB_LOG", &self.glab_log);
         cmd.env("STCK_REAL_GH_RESPONSES", &self.gh_responses);
+        cmd.env("STCK_REAL_ORIGIN", &self.remote);
+        cmd.env("XDG_CONFIG_HOME", self.user_config_dir());
         cmd.env_remove("GIT_DIR");
         cmd.env_remove("GIT_WORK_TREE");
         cmd
     }
 
+    /// Directory used as `XDG_CONFIG_HOME`, so tests never read a real user config.
+    pub fn user_config_dir(&self) -> PathBuf {
+        self._temp.path().join("xdg-config")
+    }
+
+    pub fn write_repo_file(&self, relative_path: &str, contents: &str) {
+        fs::write(self.worktree.join(relative_path), contents)
+            .expect("repository file should be written");
+    }
+
     pub fn create_branch(&self, branch: &str) {
         self.git_success(&["checkout", "-b", branch]);
     }
@@ -876,10 +1034,46 @@ exit 1
         self.git_success(&["commit", "-m", message]);
     }
 
+    /// Rewrite the tip commit so descendants no longer share it.
+    pub fn amend_file(&self, relative_path: &str, contents: &str) {
+        fs::write(self.worktree.join(relative_path), contents)
+            .expect("amended file should be written");
+        self.git_success(&["add", "--", relative_path]);
+        self.git_success(&["commit", "--amend", "--no-edit"]);
+    }
+
     pub fn push(&self, branch: &str) {
         self.git_success(&["push", "-u", "origin", branch]);
     }
 
+    /// Force the remote `branch` to `source`, as another clone pushing would.
+    pub fn force_push_remote(&self, source: &str, branch: &str) {
+        self.git_success(&[
+            "push",
+            "--force",
+            "origin",
+            &format!("{source}:refs/heads/{branch}"),
+        ]);
+    }
+
+    /// Point `origin` at a missing repository so any fetch or push fails,
+    /// keeping the fetched refs and `origin/HEAD` a clone would have.
+    pub fn disconnect_origin(&self) {
+        self.git_success(&["remote", "set-head", "origin", "main"]);
+        let missing = self._temp.path().join("missing.git");
+        self.git_success(&["remote", "set-url", "origin", &missing.to_string_lossy()]);
+    }
+
+    /// Undo [`Self::disconnect_origin`].
+    pub fn reconnect_origin(&self) {
+        self.git_success(&[
+            "remote",
+            "set-url",
+            "origin",
+            &self.remote.to_string_lossy(),
+        ]);
+    }
+
     pub fn local_sha(&self, reference: &str) -> String {
         self.git_stdout(&["rev-parse", reference])
     }
@@ -899,6 +1093,69 @@ exit 1
         trimmed_stdout(output.stdout)
     }
 
+    pub fn local_branch_exists(&self, branch: &str) -> bool {
+        self.git_output(&[
+            "show-ref",
+            "--verify",
+            "--quiet",
+            &format!("refs/heads/{branch}"),
+        ])
+        .status
+        .success()
+    }
+
+    pub fn remote_branch_exists(&self, branch: &str) -> bool {
+        self.remote_branch_exists_in(&self.remote, branch)
+    }
+
+    pub fn remote_branch_exists_in(&self, remote: &Path, branch: &str) -> bool {
+        let remote_arg = remote.to_string_lossy().into_owned();
+        run_git(
+            self._temp.path(),
+            &self.global_git_config,
+            &[
+                "--git-dir",
+                &remote_arg,
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("refs/heads/{branch}"),
+            ],
+        )
+        .status
+        .success()
+    }
+
+    /// Add a bare repository at `<owner>/stck.git` as remote `name`, seeded
+    /// with `main`, so its URL names `owner` like a fork would.
+    pub fn add_fork_remote(&self, name: &str, owner: &str) -> PathBuf {
+        let fork = self._temp.path().join(owner).join("stck.git");
+        let fork_arg = fork.to_string_lossy().into_owned();
+        assert_git_success(
+            self._temp.path(),
+            &self.global_git_config,
+            &["init", "--bare", &fork_arg],
+        );
+        self.git_success(&["remote", "add", name, &fork_arg]);
+        self.git_success(&["push", name, "main"]);
+        self.git_success(&["fetch", name]);
+        fork
+    }
+
+    pub fn git_config(&self, key: &str) -> String {
+        self.git_stdout(&["config", "--get", key])
+    }
+
+    pub fn git_config_set(&self, key: &str, value: &str) {
+        self.git_success(&["config", key, value]);
+    }
+
+    pub fn commit_count(&self, range: &str) -> usize {
+        self.git_stdout(&["rev-list", "--count", range])
+            .parse()
+            .expect("rev-list count should be numeric")
+    }
+
     pub fn current_branch(&self) -> String {
         self.git_stdout(&["branch", "--show-current"])
     }
@@ -952,56 +1209,142 @@ exit 1
     }
 
     pub fn write_pr_response(&self, branch: &str, json: &str) {
-        let branch = branch.replace('/', "__");
-        fs::write(
-            self.gh_responses.join(format!("pr-view-{branch}.json")),
-            json,
-        )
-        .expect("gh PR response should be written");
-        fs::write(
-            self.gh_responses
-                .join(format!("pr-list-head-all-{branch}.json")),
-            format!("[{json}]"),
-        )
-        .expect("gh all-state head response should be written");
-        if json.contains(r#""state":"OPEN""#) {
-            fs::write(
-                self.gh_responses
-                    .join(format!("pr-list-head-open-{branch}.json")),
-                format!("[{json}]"),
-            )
-            .expect("gh open-state head response should be written");
-        }
+        write_pr_response_in(&self.gh_responses, branch, json);
     }
 
     pub fn write_children_response(&self, base: &str, json: &str) {
-        let base = base.replace('/', "__");
+        write_children_response_in(&self.gh_responses, base, json);
+    }
+
+    /// Stage a PR response that replaces the current one once `gh pr merge`
+    /// lands `merged_branch`.
+    pub fn write_pr_response_after_merge(&self, merged_branch: &str, branch: &str, json: &str) {
+        write_pr_response_in(&self.after_merge_dir(merged_branch), branch, json);
+    }
+
+    /// Stage a children response that replaces the current one once
+    /// `gh pr merge` lands `merged_branch`.
+    pub fn write_children_response_after_merge(&self, merged_branch: &str, base: &str, json: &str) {
+        write_children_response_in(&self.after_merge_dir(merged_branch), base, json);
+    }
+
+    fn after_merge_dir(&self, merged_branch: &str) -> PathBuf {
+        let dir = self
+            .gh_responses
+            .join(format!("after-merge-{}", merged_branch.replace('/', "__")));
+        fs::create_dir_all(&dir).expect("gh after-merge response dir should be created");
+        dir
+    }
+
+    pub fn write_open_pr_head_response(&self, head: &str, json: &str) {
+        let head = head.replace('/', "__");
         fs::write(
-            self.gh_responses.join(format!("pr-list-base-{base}.json")),
+            self.gh_responses
+                .join(format!("pr-list-head-open-{head}.json")),
             json,
         )
-        .expect("gh children response should be written");
+        .expect("gh head response should be written");
     }
 
-    pub fn write_open_pr_head_response(&self, head: &str, json: &str) {
+    pub fn write_all_pr_head_response(&self, head: &str, json: &str) {
         let head = head.replace('/', "__");
         fs::write(
             self.gh_responses
-                .join(format!("pr-list-head-open-{head}.json")),
+                .join(format!("pr-list-head-all-{head}.json")),
             json,
         )
         .expect("gh head response should be written");
     }
 
+    /// Stage the description returned for the PR whose head is `branch`.
+    pub fn write_pr_body(&self, branch: &str, body: &str) {
+        let branch = branch.replace('/', "__");
+        fs::write(self.gh_responses.join(format!("pr-body-{branch}.md")), body)
+            .expect("gh PR body should be written");
+    }
+
+    /// Return the description last written for the PR whose head is `branch`.
+    pub fn pr_body(&self, branch: &str) -> Option<String> {
+        let branch = branch.replace('/', "__");
+        fs::read_to_string(self.gh_responses.join(format!("pr-body-{branch}.md"))).ok()
+    }
+
+    /// Make every `gh api graphql` call fail with `stderr` and no response
+    /// body, as a network or authentication failure would.
+    pub fn fail_graphql(&self, stderr: &str) {
+        fs::write(self.gh_responses.join("graphql.fail"), stderr)
+            .expect("gh graphql failure should be written");
+    }
+
+    /// Stage the response for every `gh api graphql` call.
+    pub fn write_graphql_response(&self, json: &str) {
+        fs::write(self.gh_responses.join("graphql.json"), json)
+            .expect("gh graphql response should be written");
+    }
+
     pub fn write_open_prs_response(&self, json: &str) {
         fs::write(self.gh_responses.join("pr-list-open.json"), json)
             .expect("gh open PR response should be written");
     }
 
+    /// Write a forge fixture and return the path for `STCK_FORGE_FIXTURE`.
+    pub fn write_forge_fixture(&self, json: &str) -> PathBuf {
+        let path = self._temp.path().join("forge-fixture.json");
+        fs::write(&path, json).expect("forge fixture should be written");
+        path
+    }
+
+    /// Build `main <- branches[0] <- branches[1] ...` with one commit per
+    /// branch, push each one, and write a fixture with an open PR per branch.
+    pub fn write_fixture_stack(&self, branches: &[&str]) -> PathBuf {
+        let mut prs = Vec::new();
+        let mut base = "main";
+        for (index, branch) in branches.iter().enumerate() {
+            self.create_branch(branch);
+            self.commit_file(
+                &format!("{branch}.txt"),
+                &format!("{branch}\n"),
+                &format!("Add {branch}"),
+            );
+            self.push(branch);
+            prs.push(format!(
+                r#"{{"number":{},"headRefName":"{branch}","baseRefName":"{base}","state":"OPEN","title":"Add {branch}","body":"Body of {branch}"}}"#,
+                index + 1
+            ));
+            base = branch;
+        }
+        self.write_forge_fixture(&format!(
+            r#"{{"repository":"example/fixture","default_branch":"main","pull_requests":[{}]}}"#,
+            prs.join(",")
+        ))
+    }
+
+    pub fn read_forge_fixture(&self) -> serde_json::Value {
+        let raw = fs::read_to_string(self._temp.path().join("forge-fixture.json"))
+            .expect("forge fixture should be readable");
+        serde_json::from_str(&raw).expect("forge fixture should be valid JSON")
+    }
+
     pub fn gh_log(&self) -> String {
         fs::read_to_string(&self.gh_log).expect("gh log should be readable")
     }
 
+    /// Stage the `glab api` merge request list for `source_branch` or
+    /// `target_branch` equal to `branch`.
+    pub fn write_mr_list_response(&self, filter: &str, branch: &str, json: &str) {
+        let branch = branch.replace('/', "__");
+        fs::write(
+            self.gh_responses
+                .join(format!("mr-list-{filter}-{branch}.json")),
+            json,
+        )
+        .expect("glab merge request response should be written");
+    }
+
+    pub fn glab_log(&self) -> String {
+        fs::read_to_string(&self.glab_log).expect("glab log should be readable")
+    }
+
     fn git_stdout(&self, args: &[&str]) -> String {
         trimmed_stdout(self.git_success(args).stdout)
     }
@@ -1024,6 +1367,30 @@ exit 1
     }
 }
 
+fn write_pr_response_in(dir: &Path, branch: &str, json: &str) {
+    let branch = branch.replace('/', "__");
+    fs::write(dir.join(format!("pr-view-{branch}.json")), json)
+        .expect("gh PR response should be written");
+    fs::write(
+        dir.join(format!("pr-list-head-all-{branch}.json")),
+        format!("[{json}]"),
+    )
+    .expect("gh all-state head response should be written");
+    let open_response = dir.join(format!("pr-list-head-open-{branch}.json"));
+    if json.contains(r#""state":"OPEN""#) {
+        fs::write(open_response, format!("[{json}]"))
+            .expect("gh open-state head response should be written");
+    } else {
+        fs::write(open_response, "[]").expect("gh open-state head response should be written");
+    }
+}
+
+fn write_children_response_in(dir: &Path, base: &str, json: &str) {
+    let base = base.replace('/', "__");
+    fs::write(dir.join(format!("pr-list-base-{base}.json")), json)
+        .expect("gh children response should be written");
+}
+
 fn assert_git_success(cwd: &Path, global_git_config: &Path, args: &[&str]) -> Output {
     let output = run_git(cwd, global_git_config, args);
     if !output.status.success() {
@@ -1055,3 +1422,227 @@ fn trimmed_stdout(stdout: Vec<u8>) -> String {
         .trim()
         .to_string()
 }
+
+/// Token the mock Gitea server expects in the `Authorization` header.
+pub const MOCK_GITEA_TOKEN: &str = "test-token";
+
+/// A local HTTP server emulating the subset of the Gitea/Forgejo REST API that
+/// `stck` uses. PRs live in memory and every request is recorded as
+/// `METHOD path body`.
+pub struct MockGiteaServer {
+    url: String,
+    pulls: Arc<Mutex<Vec<Value>>>,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockGiteaServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("mock server should bind");
+        let url = format!(
+            "http://{}",
+            listener.local_addr().expect("mock server address")
+        );
+        let pulls = Arc::new(Mutex::new(Vec::new()));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let server_pulls = Arc::clone(&pulls);
+        let server_requests = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                handle_gitea_request(stream, &server_pulls, &server_requests);
+            }
+        });
+
+        Self {
+            url,
+            pulls,
+            requests,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn add_pull(&self, head: &str, base: &str, state: &str, merged: bool) {
+        let mut pulls = self.pulls.lock().expect("mock pulls lock");
+        let number = pulls.len() as u64 + 1;
+        pulls.push(gitea_pull(number, head, base, state, merged));
+    }
+
+    pub fn pulls(&self) -> Vec<Value> {
+        self.pulls.lock().expect("mock pulls lock").clone()
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().expect("mock requests lock").clone()
+    }
+}
+
+fn gitea_pull(number: u64, head: &str, base: &str, state: &str, merged: bool) -> Value {
+    json!({
+        "number": number,
+        "state": state,
+        "merged": merged,
+        "head": {"ref": head, "repo": {"full_name": "example/stck"}},
+        "base": {"ref": base, "repo": {"full_name": "example/stck"}},
+    })
+}
+
+fn handle_gitea_request(
+    mut stream: TcpStream,
+    pulls: &Mutex<Vec<Value>>,
+    requests: &Mutex<Vec<String>>,
+) {
+    let mut reader = BufReader::new(stream.try_clone().expect("mock stream clone"));
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    let mut authorized = false;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).is_err() || line.trim().is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':').unwrap_or((&line, ""));
+        let value = value.trim();
+        match name.to_ascii_lowercase().as_str() {
+            "content-length" => content_length = value.parse().unwrap_or(0),
+            "authorization" => authorized = value == format!("token {MOCK_GITEA_TOKEN}"),
+            _ => {}
+        }
+    }
+    let mut body = vec![0; content_length];
+    let _ = reader.read_exact(&mut body);
+    let body = String::from_utf8_lossy(&body).into_owned();
+    requests
+        .lock()
+        .expect("mock requests lock")
+        .push(format!("{method} {target} {body}").trim_end().to_string());
+
+    let (status, response) = if authorized {
+        route_gitea_request(&method, &target, &body, pulls)
+    } else {
+        (401, json!({"message": "token is required"}))
+    };
+    let response = response.to_string();
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+        response.len()
+    );
+}
+
+fn route_gitea_request(
+    method: &str,
+    target: &str,
+    body: &str,
+    pulls: &Mutex<Vec<Value>>,
+) -> (u16, Value) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let Some(rest) = path.strip_prefix("/api/v1/repos/") else {
+        return (404, json!({"message": "not found"}));
+    };
+    // Skip `owner/repo`; the mock serves a single repository.
+    let segments = rest.split('/').skip(2).collect::<Vec<_>>();
+    let body = serde_json::from_str::<Value>(body).unwrap_or(Value::Null);
+    let mut pulls = pulls.lock().expect("mock pulls lock");
+
+    match (method, segments.as_slice()) {
+        ("GET", []) => (
+            200,
+            json!({
+                "full_name": "example/stck",
+                "default_branch": "main",
+                "allow_merge_commits": false,
+                "allow_squash_merge": true,
+                "allow_rebase": true,
+            }),
+        ),
+        ("GET", ["pulls"]) => {
+            let state = query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("state="))
+                .unwrap_or("open");
+            if query.split('&').any(|pair| pair == "page=1") {
+                let listed = pulls
+                    .iter()
+                    .filter(|pull| state == "all" || pull["state"] == state)
+                    .cloned()
+                    .collect();
+                (200, Value::Array(listed))
+            } else {
+                (200, json!([]))
+            }
+        }
+        ("POST", ["pulls"]) => {
+            let mut pull = gitea_pull(
+                pulls.len() as u64 + 1,
+                body["head"].as_str().unwrap_or_default(),
+                body["base"].as_str().unwrap_or_default(),
+                "open",
+                false,
+            );
+            for field in ["title", "body", "assignees", "labels"] {
+                pull[field] = body[field].clone();
+            }
+            pulls.push(pull.clone());
+            (201, pull)
+        }
+        ("POST", ["pulls", number, "requested_reviewers"]) => match find_pull(&mut pulls, number) {
+            Some(pull) => {
+                pull["requested_reviewers"] = body["reviewers"].clone();
+                (201, json!([]))
+            }
+            None => (404, json!({"message": "pull request does not exist"})),
+        },
+        ("GET", ["labels"]) => {
+            if query.split('&').any(|pair| pair == "page=1") {
+                (
+                    200,
+                    json!([{"id": 7, "name": "stacked"}, {"id": 9, "name": "needs-review"}]),
+                )
+            } else {
+                (200, json!([]))
+            }
+        }
+        ("GET", ["pulls", number]) => match find_pull(&mut pulls, number) {
+            Some(pull) => (200, pull.clone()),
+            None => (404, json!({"message": "pull request does not exist"})),
+        },
+        ("PATCH", ["pulls", number]) => match find_pull(&mut pulls, number) {
+            Some(pull) => {
+                if let Some(base) = body.get("base") {
+                    pull["base"]["ref"] = base.clone();
+                }
+                if let Some(description) = body.get("body") {
+                    pull["body"] = description.clone();
+                }
+                (201, pull.clone())
+            }
+            None => (404, json!({"message": "pull request does not exist"})),
+        },
+        ("POST", ["pulls", number, "merge"]) => match find_pull(&mut pulls, number) {
+            Some(pull) => {
+                pull["state"] = json!("closed");
+                pull["merged"] = json!(true);
+                (200, Value::Null)
+            }
+            None => (404, json!({"message": "pull request does not exist"})),
+        },
+        _ => (404, json!({"message": "not found"})),
+    }
+}
+
+fn find_pull<'a>(pulls: &'a mut [Value], number: &str) -> Option<&'a mut Value> {
+    let number = number.parse::<u64>().ok()?;
+    pulls
+        .iter_mut()
+        .find(|pull| pull["number"].as_u64() == Some(number))
+}
@@ -0,0 +1,129 @@
+mod harness;
+
+use harness::{log_path, stck_cmd_for_temp, stck_cmd_with_stubbed_tools};
+use predicates::prelude::*;
+use std::fs;
+
+#[test]
+fn land_retargets_child_before_merging_then_restacks() {
+    let (temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    let log_path = log_path(&temp, "stck-land.log");
+    cmd.env("STCK_TEST_LOG", log_path.as_os_str());
+    cmd.env("STCK_TEST_SYNC_NOOP", "1");
+    cmd.arg("land");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Landing feature-base (merge method: merge).",
+        ))
+        .stdout(predicate::str::contains(
+            "$ gh pr merge feature-base --merge --match-head-commit 1111111111111111111111111111111111111111",
+        ))
+        .stdout(predicate::str::contains("Landed feature-base into main."))
+        .stdout(predicate::str::contains(
+            "Step 1/2: rebasing feature-branch onto main (from feature-base)",
+        ))
+        .stdout(predicate::str::contains("$ git checkout feature-branch"))
+        .stdout(predicate::str::contains("Push succeeded."));
+
+    let log = fs::read_to_string(&log_path).expect("land log should exist");
+    let retarget_idx = log
+        .find("pr edit feature-branch --base main")
+        .expect("child retarget missing");
+    let merge_idx = log.find("pr merge feature-base").expect("merge missing");
+    let rebase_idx = log
+        .find("rebase --onto refs/remotes/origin/main 1111111111111111111111111111111111111111 feature-branch")
+        .expect("restack rebase missing");
+    assert!(
+        retarget_idx < merge_idx,
+        "child PR should be retargeted before the merge"
+    );
+    assert!(merge_idx < rebase_idx, "restack should run after the merge");
+}
+
+#[test]
+fn land_uses_first_allowed_merge_method() {
+    let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    cmd.env("STCK_TEST_SYNC_NOOP", "1");
+    cmd.env("STCK_TEST_MERGE_METHODS", "false\\ttrue\\ttrue");
+    cmd.arg("land");
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "$ gh pr merge feature-base --squash",
+    ));
+}
+
+#[test]
+fn land_rejects_merge_method_disabled_in_repository() {
+    let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    cmd.env("STCK_TEST_SYNC_NOOP", "1");
+    cmd.env("STCK_TEST_MERGE_METHODS", "false\\ttrue\\tfalse");
+    cmd.args(["land", "--method", "rebase"]);
+
+    cmd.assert().code(1).stderr(predicate::str::contains(
+        "error: merge method `rebase` is not allowed in example/stck; allowed: squash",
+    ));
+}
+
+#[test]
+fn land_requires_bottom_pr_to_target_default_branch() {
+    let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    cmd.arg("land");
+
+    cmd.assert().code(1).stderr(predicate::str::contains(
+        "error: no open PR in this stack targets main; run `stck sync` and `stck push` first",
+    ));
+}
+
+#[test]
+fn land_rejects_default_branch() {
+    let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    cmd.env("STCK_TEST_CURRENT_BRANCH", "main");
+    cmd.arg("land");
+
+    cmd.assert().code(1).stderr(predicate::str::contains(
+        "error: cannot land from default branch main; checkout a branch in the stack and retry",
+    ));
+}
+
+#[test]
+fn land_resumes_after_merge_failure_without_repeating_retargets() {
+    let (temp, mut first) = stck_cmd_with_stubbed_tools();
+    let log_path = log_path(&temp, "stck-land-resume.log");
+    first.env("STCK_TEST_LOG", log_path.as_os_str());
+    first.env("STCK_TEST_SYNC_NOOP", "1");
+    first.env("STCK_TEST_PR_MERGE_FAIL", "1");
+    first.arg("land");
+
+    first
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "error: failed to merge PR for branch feature-base; fix the GitHub error and rerun `stck land`",
+        ))
+        .stderr(predicate::str::contains("Pull request is not mergeable"));
+
+    let mut sync = stck_cmd_for_temp(&temp);
+    sync.env("STCK_TEST_SYNC_NOOP", "1");
+    sync.arg("sync");
+    sync.assert().code(1).stderr(predicate::str::contains(
+        "error: land operation state is in progress; run `stck land` to finish landing the PR",
+    ));
+
+    let mut resume = stck_cmd_for_temp(&temp);
+    resume.env("STCK_TEST_LOG", log_path.as_os_str());
+    resume.env("STCK_TEST_SYNC_NOOP", "1");
+    resume.arg("land");
+
+    resume
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "Resuming previous land operation for feature-base from saved state.\n$ gh pr merge feature-base",
+        ))
+        .stdout(predicate::str::contains("Landed feature-base into main."));
+
+    let log = fs::read_to_string(&log_path).expect("land log should exist");
+    assert_eq!(log.matches("pr merge feature-base").count(), 2);
+}
@@ -0,0 +1,95 @@
+mod harness;
+
+use harness::{log_path, stck_cmd_with_stubbed_tools};
+use predicates::prelude::*;
+use std::fs;
+
+#[test]
+fn up_checks_out_the_child_branch() {
+    let (temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    let log_path = log_path(&temp, "stck-up.log");
+    cmd.env("STCK_TEST_LOG", log_path.as_os_str());
+    cmd.arg("up");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("$ git checkout feature-child"));
+
+    let log = fs::read_to_string(&log_path).expect("navigation log should exist");
+    assert!(log.contains("checkout feature-child"));
+}
+
+#[test]
+fn top_checks_out_the_stack_tip() {
+    let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    cmd.env("STCK_TEST_CURRENT_BRANCH", "feature-base");
+    cmd.arg("top");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("$ git checkout feature-child"));
+}
+
+#[test]
+fn down_skips_merged_parents_and_errors_at_the_bottom() {
+    let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    cmd.arg("down");
+
+    cmd.assert().code(1).stderr(predicate::str::contains(
+        "error: already at the bottom of the stack (feature-branch); cannot move down",
+    ));
+}
+
+#[test]
+fn bottom_reports_when_already_on_the_lowest_open_branch() {
+    let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    cmd.arg("bottom");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Already on feature-branch."))
+        .stdout(predicate::str::contains("$ git checkout").not());
+}
+
+#[test]
+fn down_with_steps_walks_several_branches() {
+    let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    cmd.env("STCK_TEST_CURRENT_BRANCH", "feature-child");
+    cmd.env("STCK_TEST_SYNC_NOOP", "1");
+    cmd.args(["down", "--steps", "2"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("$ git checkout feature-base"));
+}
+
+#[test]
+fn up_with_too_many_steps_reports_the_stack_end() {
+    let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    cmd.args(["up", "--steps", "2"]);
+
+    cmd.assert().code(1).stderr(predicate::str::contains(
+        "error: cannot move up 2 branches from feature-branch; the top of the stack is feature-child, 1 branch(es) up",
+    ));
+}
+
+#[test]
+fn up_rejects_zero_steps() {
+    let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    cmd.args(["up", "--steps", "0"]);
+
+    cmd.assert().code(2).stderr(predicate::str::contains(
+        "invalid value '0' for '--steps <STEPS>'",
+    ));
+}
+
+#[test]
+fn up_asks_for_an_explicit_branch_when_the_stack_forks() {
+    let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    cmd.env("STCK_TEST_NON_LINEAR", "1");
+    cmd.arg("up");
+
+    cmd.assert().code(1).stderr(predicate::str::contains(
+        "error: feature-branch has several open child branches (feature-child-a, feature-child-b); check one out with `git checkout <branch>`",
+    ));
+}
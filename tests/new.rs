@@ -291,3 +291,13 @@ fn new_fails_when_pr_presence_check_errors() {
         "new should not create PRs when PR presence check fails"
     );
 }
+
+#[test]
+fn new_rejects_pr_options_for_local_branches() {
+    let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    cmd.args(["new", "feature-x", "--local", "--draft"]);
+
+    cmd.assert().code(2).stderr(predicate::str::contains(
+        "the argument '--local' cannot be used with '--draft'",
+    ));
+}
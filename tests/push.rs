@@ -84,7 +84,8 @@ fn push_stops_before_retarget_when_a_push_fails() {
 
     let log = fs::read_to_string(&log_path).expect("push log should exist");
     assert!(
-        !log.contains("pr edit"),
+        !log.lines()
+            .any(|line| line.starts_with("pr edit") && line.contains(" --base ")),
         "retarget should not run when a push fails"
     );
 }
@@ -104,9 +105,15 @@ fn push_resumes_after_partial_retarget_failure() {
     first.env("STCK_TEST_RETARGET_FAIL_ONCE_BRANCH", "feature-child");
     first.arg("push");
 
-    first.assert().code(1).stderr(predicate::str::contains(
-        "error: failed to retarget PR base for branch feature-child to feature-branch; fix the GitHub error and rerun `stck push`",
-    ));
+    first
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "error: failed to retarget PR base for branch feature-child to feature-branch; fix the GitHub error and rerun `stck push`",
+        ))
+        .stderr(predicate::str::contains(
+            "Fix the forge error and rerun `stck push` to resume.",
+        ));
 
     let state_path = temp
         .path()
@@ -236,11 +243,16 @@ fn push_discards_cached_sync_plan_when_stack_metadata_changes() {
         .stdout(predicate::str::contains(
             "Push succeeded. Pushed 2 branch(es) and applied 0 PR base update(s) in this run.",
         ))
-        .stdout(predicate::str::contains("$ gh pr edit").not());
+        .stdout(
+            predicate::str::is_match(r"\$ gh pr edit \S+ --base")
+                .expect("retarget pattern should compile")
+                .not(),
+        );
 
     let log = fs::read_to_string(&log_path).expect("push log should exist");
     assert!(
-        !log.contains("pr edit"),
+        !log.lines()
+            .any(|line| line.starts_with("pr edit") && line.contains(" --base ")),
         "push should recompute retargets instead of reusing a cached plan for changed PR metadata"
     );
     assert!(
@@ -294,12 +306,17 @@ fn push_resume_clears_stale_state_when_remaining_retargets_are_already_satisfied
         .stdout(predicate::str::contains(
             "Push succeeded. Pushed 0 branch(es) and applied 0 PR base update(s) in this run.",
         ))
-        .stdout(predicate::str::contains("$ gh pr edit").not());
+        .stdout(
+            predicate::str::is_match(r"\$ gh pr edit \S+ --base")
+                .expect("retarget pattern should compile")
+                .not(),
+        );
 
     if log_path.exists() {
         let log = fs::read_to_string(&log_path).expect("push log should be readable");
         assert!(
-            !log.contains("pr edit"),
+            !log.lines()
+                .any(|line| line.starts_with("pr edit") && line.contains(" --base ")),
             "resume should skip retarget calls when saved retargets are already satisfied"
         );
     }
@@ -422,6 +439,59 @@ fn sync_then_push_after_squash_merge_produces_correct_retargets() {
         ));
 }
 
+#[test]
+fn push_dry_run_lists_retargets_without_pushing_or_editing_prs() {
+    let (temp, mut sync) = stck_cmd_with_stubbed_tools();
+    let log_path = log_path(&temp, "sync-push-dry-run.log");
+
+    sync.env("STCK_TEST_LOG", log_path.as_os_str());
+    sync.env(
+        "STCK_TEST_NEEDS_PUSH_BRANCHES",
+        "feature-branch,feature-child",
+    );
+    sync.arg("sync");
+    sync.assert().success();
+
+    let mut push = stck_cmd_for_temp(&temp);
+    push.env("STCK_TEST_LOG", log_path.as_os_str());
+    push.env(
+        "STCK_TEST_NEEDS_PUSH_BRANCHES",
+        "feature-branch,feature-child",
+    );
+    push.env(
+        "STCK_TEST_NOT_ANCESTOR_PAIRS",
+        "feature-branch:feature-branch,feature-child:feature-child",
+    );
+    push.args(["push", "--dry-run"]);
+
+    push.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Push 1/2: feature-branch (force-with-lease, expected origin/feature-branch at ffffffffffffffffffffffffffffffffffffffff)",
+        ))
+        .stdout(predicate::str::contains(
+            "Retarget PR 1/1: feature-branch -> main",
+        ))
+        .stdout(predicate::str::contains(
+            "$ gh pr edit feature-branch --base main",
+        ));
+
+    let log = fs::read_to_string(&log_path).expect("push log should exist");
+    assert!(
+        !log.contains("push --force-with-lease") && !log.contains("pr edit"),
+        "a dry run must not push branches or retarget PRs"
+    );
+    assert!(
+        !temp
+            .path()
+            .join("git-dir")
+            .join("stck")
+            .join("last-plan.json")
+            .exists(),
+        "a dry run must not save push state"
+    );
+}
+
 #[test]
 fn push_aborts_when_a_remote_branch_changes_after_sync() {
     let (temp, mut sync) = stck_cmd_with_stubbed_tools();
@@ -1,6 +1,6 @@
 mod harness;
 
-use harness::RealGitRepo;
+use harness::{MockGiteaServer, RealGitRepo, MOCK_GITEA_TOKEN};
 use predicates::prelude::*;
 
 #[test]
@@ -63,6 +63,36 @@ fn submit_pushes_a_real_branch_before_creating_its_pr() {
     ));
 }
 
+#[test]
+fn submit_forwards_pr_options_and_titles_the_pr_after_its_first_commit() {
+    let repo = RealGitRepo::new();
+    repo.create_branch("feature-login");
+    repo.commit_file("login.txt", "form\n", "Add login form");
+    repo.commit_file("login.txt", "form v2\n", "Polish login form");
+    let notes = tempfile::tempdir().expect("body file dir should be created");
+    let body_file = notes.path().join("body.md");
+    std::fs::write(&body_file, "Adds the login form.\n").expect("body file should be written");
+
+    repo.stck_cmd()
+        .args(["submit", "--base", "main", "--draft", "--reviewer", "octocat"])
+        .args(["--reviewer", "hubot", "--assignee", "me", "--label", "stacked"])
+        .arg("--body-file")
+        .arg(&body_file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "$ gh pr create --base main --head feature-login --title 'Add login form' --body \"<stack context>\" --draft --reviewer octocat --reviewer hubot --assignee me --label stacked",
+        ));
+
+    let gh_log = repo.gh_log();
+    assert!(gh_log.contains(
+        "pr create --base main --head feature-login --title Add login form --body Adds the login form.\n\n<!-- stck:stack:start -->"
+    ));
+    assert!(gh_log.contains(
+        "<!-- stck:stack:end --> --draft --reviewer octocat --reviewer hubot --assignee me --label stacked"
+    ));
+}
+
 #[test]
 fn submit_discovers_a_remote_parent_without_its_local_branch() {
     let repo = RealGitRepo::new();
@@ -283,7 +313,11 @@ fn push_does_not_reuse_a_real_cached_plan_for_another_stack() {
         .stdout(predicate::str::contains(
             "Push succeeded. Pushed 0 branch(es) and applied 0 PR base update(s) in this run.",
         ))
-        .stdout(predicate::str::contains("$ gh pr edit").not());
+        .stdout(
+            predicate::str::is_match(r"\$ gh pr edit \S+ --base")
+                .expect("retarget pattern should compile")
+                .not(),
+        );
 
     let gh_log = repo.gh_log();
     assert!(
@@ -405,3 +439,2031 @@ fn sync_reset_recomputes_after_a_real_git_rebase_failure() {
     );
     assert!(repo.is_ancestor("refs/remotes/origin/main", "refs/heads/feature-reset"));
 }
+
+#[test]
+fn sync_restacks_both_children_of_a_real_branching_stack() {
+    let repo = RealGitRepo::new();
+
+    repo.create_branch("feature-base");
+    repo.commit_file("base.txt", "base\n", "Add base feature");
+    repo.push("feature-base");
+
+    repo.create_branch("feature-left");
+    repo.commit_file("left.txt", "left\n", "Add left feature");
+    repo.push("feature-left");
+
+    repo.checkout("feature-base");
+    repo.create_branch("feature-right");
+    repo.commit_file("right.txt", "right\n", "Add right feature");
+    repo.push("feature-right");
+
+    repo.checkout("main");
+    repo.commit_file("main.txt", "main advanced\n", "Advance main");
+    repo.push("main");
+    repo.checkout("feature-left");
+
+    repo.write_pr_response(
+        "feature-base",
+        r#"{"number":501,"headRefName":"feature-base","baseRefName":"main","state":"OPEN"}"#,
+    );
+    repo.write_pr_response(
+        "feature-left",
+        r#"{"number":502,"headRefName":"feature-left","baseRefName":"feature-base","state":"OPEN"}"#,
+    );
+    repo.write_pr_response(
+        "feature-right",
+        r#"{"number":503,"headRefName":"feature-right","baseRefName":"feature-base","state":"OPEN"}"#,
+    );
+    repo.write_children_response(
+        "feature-base",
+        r#"[{"number":502,"headRefName":"feature-left","baseRefName":"feature-base","state":"OPEN"},{"number":503,"headRefName":"feature-right","baseRefName":"feature-base","state":"OPEN"}]"#,
+    );
+
+    let mut status = repo.stck_cmd();
+    status.arg("status");
+    status
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Stack: main (tree)"))
+        .stdout(predicate::str::contains(
+            "*   feature-left PR #502 OPEN base=feature-base",
+        ))
+        .stdout(predicate::str::contains(
+            "    feature-right PR #503 OPEN base=feature-base",
+        ));
+
+    let mut sync = repo.stck_cmd();
+    sync.arg("sync");
+    sync.assert().success().stdout(predicate::str::contains(
+        "Sync succeeded locally. Run `stck push` to update remotes + PR bases.",
+    ));
+
+    assert_eq!(repo.current_branch(), "feature-left");
+    assert!(repo.is_ancestor("refs/remotes/origin/main", "refs/heads/feature-base"));
+    assert!(repo.is_ancestor("refs/heads/feature-base", "refs/heads/feature-left"));
+    assert!(repo.is_ancestor("refs/heads/feature-base", "refs/heads/feature-right"));
+    assert!(!repo.is_ancestor("refs/heads/feature-left", "refs/heads/feature-right"));
+}
+
+#[test]
+fn land_squash_merges_the_bottom_pr_and_restacks_its_child() {
+    let repo = RealGitRepo::new();
+
+    repo.create_branch("feature-a");
+    repo.commit_file("a.txt", "a\n", "Add feature a");
+    repo.commit_file("a.txt", "a, revised\n", "Revise feature a");
+    repo.push("feature-a");
+
+    repo.create_branch("feature-b");
+    repo.commit_file("b.txt", "b\n", "Add feature b");
+    repo.push("feature-b");
+    let old_child_sha = repo.remote_sha("feature-b");
+
+    repo.write_pr_response(
+        "feature-a",
+        r#"{"number":601,"headRefName":"feature-a","baseRefName":"main","state":"OPEN"}"#,
+    );
+    repo.write_pr_response(
+        "feature-b",
+        r#"{"number":602,"headRefName":"feature-b","baseRefName":"feature-a","state":"OPEN"}"#,
+    );
+    repo.write_children_response(
+        "feature-a",
+        r#"[{"number":602,"headRefName":"feature-b","baseRefName":"feature-a","state":"OPEN"}]"#,
+    );
+    repo.write_children_response("feature-b", "[]");
+    repo.write_pr_response_after_merge(
+        "feature-a",
+        "feature-a",
+        r#"{"number":601,"headRefName":"feature-a","baseRefName":"main","state":"MERGED"}"#,
+    );
+    repo.write_pr_response_after_merge(
+        "feature-a",
+        "feature-b",
+        r#"{"number":602,"headRefName":"feature-b","baseRefName":"main","state":"OPEN"}"#,
+    );
+    repo.write_children_response_after_merge("feature-a", "feature-a", "[]");
+
+    let mut cmd = repo.stck_cmd();
+    cmd.arg("land");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Landing feature-a (merge method: squash).",
+        ))
+        .stdout(predicate::str::contains(format!(
+            "$ git push --force-with-lease=refs/heads/feature-b:{old_child_sha} origin feature-b"
+        )))
+        .stdout(predicate::str::contains(
+            "Push succeeded. Pushed 1 branch(es) and applied 0 PR base update(s) in this run.",
+        ));
+
+    let gh_log = repo.gh_log();
+    let retarget_idx = gh_log
+        .find("pr edit feature-b --base main")
+        .expect("child PR should be retargeted");
+    let merge_idx = gh_log
+        .find("pr merge feature-a --squash")
+        .expect("bottom PR should be merged");
+    assert!(retarget_idx < merge_idx);
+
+    assert_eq!(repo.current_branch(), "feature-b");
+    assert!(!repo.sync_state_exists());
+    assert_eq!(
+        repo.local_sha("refs/heads/feature-b^"),
+        repo.remote_sha("main")
+    );
+    assert_eq!(
+        repo.remote_sha("feature-b"),
+        repo.local_sha("refs/heads/feature-b")
+    );
+}
+
+#[test]
+fn local_branches_stack_before_any_pr_exists() {
+    let repo = RealGitRepo::new();
+
+    let mut new_base = repo.stck_cmd();
+    new_base.args(["new", "feature-base", "--local"]);
+    new_base.assert().success().stdout(predicate::str::contains(
+        "Created local branch feature-base on top of main.",
+    ));
+    repo.commit_file("base.txt", "base\n", "Add base feature");
+
+    let mut new_child = repo.stck_cmd();
+    new_child.args(["new", "feature-child", "--local"]);
+    new_child
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Created local branch feature-child on top of feature-base.",
+        ));
+    repo.commit_file("child.txt", "child\n", "Add child feature");
+
+    let mut status = repo.stck_cmd();
+    status.arg("status");
+    status
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Stack: main <- feature-base <- feature-child",
+        ))
+        .stdout(predicate::str::contains("feature-base LOCAL base=main"))
+        .stdout(predicate::str::contains(
+            "* feature-child LOCAL base=feature-base",
+        ));
+
+    assert_eq!(
+        repo.git_config("branch.feature-child.stck-parent"),
+        "feature-base"
+    );
+    let gh_log = repo.gh_log();
+    assert!(!gh_log.contains("pr create"));
+    assert!(
+        !repo.remote_branch_exists("feature-base"),
+        "local branches must not be pushed"
+    );
+}
+
+#[test]
+fn sync_restacks_local_branches_from_their_recorded_fork_points() {
+    let repo = RealGitRepo::new();
+
+    repo.stck_cmd()
+        .args(["new", "feature-base", "--local"])
+        .assert()
+        .success();
+    repo.commit_file("base.txt", "base\n", "Add base feature");
+    repo.stck_cmd()
+        .args(["new", "feature-child", "--local"])
+        .assert()
+        .success();
+    repo.commit_file("child.txt", "child\n", "Add child feature");
+
+    // Rewrite the parent so the child no longer shares its commit; only the
+    // recorded fork point tells sync which commits belong to the child.
+    repo.checkout("feature-base");
+    repo.amend_file("base.txt", "base amended\n");
+
+    repo.checkout("main");
+    repo.commit_file("main.txt", "main advanced\n", "Advance main");
+    repo.push("main");
+    repo.checkout("feature-child");
+
+    let mut sync = repo.stck_cmd();
+    sync.arg("sync");
+    sync.assert().success().stdout(predicate::str::contains(
+        "Sync succeeded locally. Run `stck push` to update remotes + PR bases.",
+    ));
+
+    assert!(repo.is_ancestor("refs/remotes/origin/main", "refs/heads/feature-base"));
+    assert!(repo.is_ancestor("refs/heads/feature-base", "refs/heads/feature-child"));
+    assert_eq!(repo.commit_count("feature-base..feature-child"), 1);
+    assert_eq!(
+        repo.git_config("branch.feature-child.stck-fork-point"),
+        repo.local_sha("refs/heads/feature-base")
+    );
+    assert_eq!(
+        repo.git_config("branch.feature-base.stck-fork-point"),
+        repo.remote_sha("main")
+    );
+}
+
+#[test]
+fn restack_rebases_recorded_children_offline_after_the_parent_is_amended() {
+    let repo = RealGitRepo::new();
+    for (branch, file) in [
+        ("feature-a", "a.txt"),
+        ("feature-b", "b.txt"),
+        ("feature-c", "c.txt"),
+    ] {
+        repo.stck_cmd()
+            .args(["new", branch, "--local"])
+            .assert()
+            .success();
+        repo.commit_file(file, "content\n", &format!("Add {branch}"));
+    }
+    repo.checkout("feature-a");
+    repo.amend_file("a.txt", "amended\n");
+    repo.disconnect_origin();
+    let gh_log_before = repo.gh_log();
+
+    let mut restack = repo.stck_cmd();
+    restack.arg("restack");
+    restack
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Step 1/2: restacking feature-b onto feature-a",
+        ))
+        .stdout(predicate::str::contains(
+            "Step 2/2: restacking feature-c onto feature-b",
+        ))
+        .stdout(predicate::str::contains("Restack succeeded locally."));
+
+    assert_eq!(repo.current_branch(), "feature-a");
+    assert_eq!(repo.commit_count("feature-a..feature-b"), 1);
+    assert_eq!(repo.commit_count("feature-b..feature-c"), 1);
+    assert!(repo.is_ancestor("refs/heads/feature-a", "refs/heads/feature-b"));
+    assert!(repo.is_ancestor("refs/heads/feature-b", "refs/heads/feature-c"));
+    assert_eq!(
+        repo.git_config("branch.feature-b.stck-fork-point"),
+        repo.local_sha("refs/heads/feature-a")
+    );
+    assert_eq!(repo.gh_log(), gh_log_before, "restack must not call gh");
+
+    let mut again = repo.stck_cmd();
+    again.arg("restack");
+    again.assert().success().stdout(predicate::str::contains(
+        "Stack is already restacked on its recorded parents. Nothing to do.",
+    ));
+}
+
+#[test]
+fn restack_continues_after_a_real_git_conflict_is_resolved() {
+    let repo = RealGitRepo::new();
+    repo.stck_cmd()
+        .args(["new", "feature-a", "--local"])
+        .assert()
+        .success();
+    repo.commit_file("shared.txt", "parent\n", "Add shared file");
+    repo.stck_cmd()
+        .args(["new", "feature-b", "--local"])
+        .assert()
+        .success();
+    repo.commit_file("shared.txt", "child\n", "Change shared file on child");
+    repo.checkout("feature-a");
+    repo.amend_file("shared.txt", "parent amended\n");
+    repo.disconnect_origin();
+
+    let mut first = repo.stck_cmd();
+    first.arg("restack");
+    first
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("CONFLICT"))
+        .stderr(predicate::str::contains(
+            "Then run `stck restack --continue` to resume.",
+        ));
+    assert!(repo.sync_state_exists());
+
+    repo.resolve_rebase_conflict("shared.txt", "child\n");
+
+    let mut sync = repo.stck_cmd();
+    sync.arg("sync");
+    sync.assert()
+        .code(1)
+        .stderr(predicate::str::contains("a `stck restack` is in progress"));
+
+    let mut resume = repo.stck_cmd();
+    resume.args(["restack", "--continue"]);
+    resume
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restack succeeded locally."));
+
+    assert!(!repo.sync_state_exists());
+    assert!(repo.is_ancestor("refs/heads/feature-a", "refs/heads/feature-b"));
+    assert_eq!(repo.commit_count("feature-a..feature-b"), 1);
+}
+
+#[test]
+fn submit_and_status_run_against_a_fixture_forge_without_gh() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_forge_fixture(
+        r#"{"repository":"example/fixture","default_branch":"main","pull_requests":[]}"#,
+    );
+
+    repo.create_branch("feature-a");
+    repo.commit_file("a.txt", "a\n", "Add feature a");
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["submit", "--base", "main"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Created PR for feature-a targeting main.",
+        ));
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["new", "feature-b", "--local"])
+        .assert()
+        .success();
+    repo.commit_file("b.txt", "b\n", "Add feature b");
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .arg("submit")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Created PR for feature-b targeting feature-a.",
+        ));
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Stack: main <- feature-a <- feature-b",
+        ))
+        .stdout(predicate::str::contains("feature-a PR #1 OPEN base=main"))
+        .stdout(predicate::str::contains(
+            "* feature-b PR #2 OPEN base=feature-a",
+        ));
+
+    let fixture = repo.read_forge_fixture();
+    assert_eq!(fixture["pull_requests"][1]["headRefName"], "feature-b");
+    assert_eq!(fixture["pull_requests"][1]["baseRefName"], "feature-a");
+    assert!(
+        repo.gh_log().is_empty(),
+        "the fixture forge must not call gh"
+    );
+}
+
+#[test]
+fn submit_stack_opens_prs_for_every_local_branch_from_root_to_tip() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_forge_fixture(
+        r#"{"repository":"example/fixture","default_branch":"main","pull_requests":[]}"#,
+    );
+    for (branch, file) in [
+        ("feature-a", "a.txt"),
+        ("feature-b", "b.txt"),
+        ("feature-c", "c.txt"),
+    ] {
+        repo.stck_cmd()
+            .env("STCK_FORGE_FIXTURE", &fixture)
+            .args(["new", branch, "--local"])
+            .assert()
+            .success();
+        repo.commit_file(file, "content\n", &format!("Add {branch}"));
+    }
+    repo.checkout("feature-b");
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["submit", "--stack"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(
+                r"(?s)Created PR for feature-a targeting main\..*Created PR for feature-b targeting feature-a\..*Created PR for feature-c targeting feature-b\.",
+            )
+            .expect("submit order pattern should compile"),
+        )
+        .stdout(predicate::str::contains("Created 3 PR(s) for the stack."));
+
+    for branch in ["feature-a", "feature-b", "feature-c"] {
+        assert_eq!(
+            repo.local_sha(&format!("refs/heads/{branch}")),
+            repo.remote_sha(branch)
+        );
+    }
+    let fixture_json = repo.read_forge_fixture();
+    let heads = fixture_json["pull_requests"]
+        .as_array()
+        .expect("fixture PRs should be an array")
+        .iter()
+        .map(|pr| pr["headRefName"].as_str().unwrap_or_default().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(heads, vec!["feature-a", "feature-b", "feature-c"]);
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["submit", "--stack"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Every branch in the stack already has a PR.",
+        ));
+}
+
+#[test]
+fn submit_creates_a_gitlab_merge_request_with_glab() {
+    let repo = RealGitRepo::new();
+    repo.git_config_set("stck.forge", "gitlab");
+    repo.create_branch("feature-a");
+    repo.commit_file("a.txt", "a\n", "Add feature a");
+
+    repo.stck_cmd()
+        .args(["submit", "--base", "main"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "$ glab mr create --source-branch feature-a --target-branch main",
+        ))
+        .stdout(predicate::str::contains(
+            "Created PR for feature-a targeting main.",
+        ));
+
+    let glab_log = repo.glab_log();
+    assert!(glab_log.contains(
+        "api projects/:id/merge_requests?source_branch=feature-a&state=all&per_page=100"
+    ));
+    assert!(glab_log.contains("mr create --source-branch feature-a --target-branch main"));
+    assert!(glab_log.contains(
+        "This pull request is part of a stack.\n\n- **Position:** Root\n- **Base:** `main`"
+    ));
+    assert!(
+        repo.gh_log().is_empty(),
+        "the GitLab forge must not call gh"
+    );
+}
+
+#[test]
+fn sync_then_push_retargets_a_gitlab_merge_request_after_its_parent_merged() {
+    let repo = RealGitRepo::new();
+    repo.git_config_set("stck.forge", "gitlab");
+
+    repo.create_branch("feature-base");
+    repo.commit_file("base.txt", "base\n", "Add base feature");
+    repo.push("feature-base");
+    repo.create_branch("feature-child");
+    repo.commit_file("child.txt", "child\n", "Add child feature");
+    repo.push("feature-child");
+
+    repo.checkout("main");
+    repo.commit_file("base.txt", "base\n", "Squash feature-base");
+    repo.push("main");
+    repo.checkout("feature-child");
+
+    repo.write_mr_list_response(
+        "source_branch",
+        "feature-base",
+        r#"[{"iid":7,"source_branch":"feature-base","target_branch":"main","state":"merged","source_project_id":1,"target_project_id":1}]"#,
+    );
+    repo.write_mr_list_response(
+        "source_branch",
+        "feature-child",
+        r#"[{"iid":8,"source_branch":"feature-child","target_branch":"feature-base","state":"opened","source_project_id":1,"target_project_id":1}]"#,
+    );
+    repo.write_mr_list_response(
+        "target_branch",
+        "feature-base",
+        r#"[{"iid":8,"source_branch":"feature-child","target_branch":"feature-base","state":"opened","source_project_id":1,"target_project_id":1}]"#,
+    );
+
+    repo.stck_cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("feature-base PR #7 MERGED"))
+        .stdout(predicate::str::contains(
+            "* feature-child PR #8 OPEN base=feature-base",
+        ));
+
+    repo.stck_cmd().arg("sync").assert().success();
+    assert!(repo.is_ancestor("refs/remotes/origin/main", "refs/heads/feature-child"));
+
+    repo.stck_cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "$ glab mr update feature-child --target-branch main",
+        ));
+
+    assert!(repo
+        .glab_log()
+        .contains("mr update feature-child --target-branch main"));
+    assert!(
+        repo.gh_log().is_empty(),
+        "the GitLab forge must not call gh"
+    );
+}
+
+#[test]
+fn submit_and_status_run_against_a_gitea_api() {
+    let repo = RealGitRepo::new();
+    let server = MockGiteaServer::start();
+    repo.git_config_set("stck.forge", "gitea");
+    repo.git_config_set("stck.gitea-url", server.url());
+
+    repo.create_branch("feature-a");
+    repo.commit_file("a.txt", "a\n", "Add feature a");
+    repo.stck_cmd()
+        .env("GITEA_TOKEN", MOCK_GITEA_TOKEN)
+        .args(["submit", "--base", "main"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Created PR for feature-a targeting main.",
+        ));
+
+    repo.stck_cmd()
+        .env("GITEA_TOKEN", MOCK_GITEA_TOKEN)
+        .args(["new", "feature-b", "--local"])
+        .assert()
+        .success();
+    repo.commit_file("b.txt", "b\n", "Add feature b");
+    repo.stck_cmd()
+        .env("GITEA_TOKEN", MOCK_GITEA_TOKEN)
+        .arg("submit")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Created PR for feature-b targeting feature-a.",
+        ));
+
+    repo.stck_cmd()
+        .env("GITEA_TOKEN", MOCK_GITEA_TOKEN)
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Stack: main <- feature-a <- feature-b",
+        ))
+        .stdout(predicate::str::contains("feature-a PR #1 OPEN base=main"))
+        .stdout(predicate::str::contains(
+            "* feature-b PR #2 OPEN base=feature-a",
+        ));
+
+    let pulls = server.pulls();
+    assert_eq!(pulls[1]["head"]["ref"], "feature-b");
+    assert_eq!(pulls[1]["base"]["ref"], "feature-a");
+    assert!(server
+        .requests()
+        .iter()
+        .any(|request| request.starts_with("POST /api/v1/repos/")
+            && request.contains(r#""head":"feature-a""#)
+            && request.contains("This pull request is part of a stack.")));
+    assert!(repo.gh_log().is_empty(), "the Gitea forge must not call gh");
+}
+
+#[test]
+fn submit_maps_pr_options_onto_the_gitea_api() {
+    let repo = RealGitRepo::new();
+    let server = MockGiteaServer::start();
+    // The forge settings resolve through the config layers like any other key.
+    repo.commit_file(".stck.toml", "forge = \"gitea\"\n", "Configure stck");
+    repo.push("main");
+    repo.stck_cmd()
+        .args(["config", "set", "--user", "gitea-url", server.url()])
+        .assert()
+        .success();
+    repo.stck_cmd()
+        .args(["config", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("forge = gitea (.stck.toml)"))
+        .stdout(predicate::str::contains(format!(
+            "gitea-url = {} (user config)",
+            server.url()
+        )));
+    repo.create_branch("feature-a");
+    repo.commit_file("a.txt", "a\n", "Add feature a");
+
+    repo.stck_cmd()
+        .env("GITEA_TOKEN", MOCK_GITEA_TOKEN)
+        .args(["submit", "--base", "main", "--draft", "--label", "stacked"])
+        .args(["--reviewer", "octocat", "--assignee", "me"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "head=feature-a title='WIP: Add feature a' assignees=me labels=stacked reviewers=octocat",
+        ));
+
+    let pull = &server.pulls()[0];
+    assert_eq!(pull["title"], "WIP: Add feature a");
+    assert_eq!(pull["labels"], serde_json::json!([7]));
+    assert_eq!(pull["assignees"], serde_json::json!(["me"]));
+    assert_eq!(pull["requested_reviewers"], serde_json::json!(["octocat"]));
+
+    repo.create_branch("feature-b");
+    repo.commit_file("b.txt", "b\n", "Add feature b");
+    repo.stck_cmd()
+        .env("GITEA_TOKEN", MOCK_GITEA_TOKEN)
+        .args(["submit", "--label", "missing"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "failed to create PR for branch feature-b: label `missing` does not exist",
+        ));
+}
+
+#[test]
+fn sync_then_push_retargets_a_gitea_pr_after_its_parent_merged() {
+    let repo = RealGitRepo::new();
+    let server = MockGiteaServer::start();
+    repo.git_config_set("stck.forge", "gitea");
+    repo.git_config_set("stck.gitea-url", server.url());
+
+    repo.create_branch("feature-base");
+    repo.commit_file("base.txt", "base\n", "Add base feature");
+    repo.push("feature-base");
+    repo.create_branch("feature-child");
+    repo.commit_file("child.txt", "child\n", "Add child feature");
+    repo.push("feature-child");
+
+    repo.checkout("main");
+    repo.commit_file("base.txt", "base\n", "Squash feature-base");
+    repo.push("main");
+    repo.checkout("feature-child");
+
+    server.add_pull("feature-base", "main", "closed", true);
+    server.add_pull("feature-child", "feature-base", "open", false);
+
+    repo.stck_cmd()
+        .env("GITEA_TOKEN", MOCK_GITEA_TOKEN)
+        .arg("sync")
+        .assert()
+        .success();
+    assert!(repo.is_ancestor("refs/remotes/origin/main", "refs/heads/feature-child"));
+
+    repo.stck_cmd()
+        .env("GITEA_TOKEN", MOCK_GITEA_TOKEN)
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("base=main"));
+
+    assert_eq!(server.pulls()[1]["base"]["ref"], "main");
+    let requests = server.requests();
+    assert!(requests
+        .iter()
+        .any(|request| request.contains("PATCH /api/v1/repos/")
+            && request.ends_with(r#"/pulls/2 {"base":"main"}"#)));
+    assert!(
+        !requests.iter().any(|request| request.contains("state=all")),
+        "PR lookups must not list every PR: {requests:#?}"
+    );
+    // Each command lists open PRs once; retargeting updates the cached listing
+    // instead of fetching it again.
+    let open_listings = requests
+        .iter()
+        .filter(|request| request.starts_with("GET ") && request.contains("pulls?state=open"))
+        .count();
+    assert_eq!(open_listings, 2, "{requests:#?}");
+}
+
+#[test]
+fn gitea_forge_requires_an_access_token() {
+    let repo = RealGitRepo::new();
+    let server = MockGiteaServer::start();
+    repo.git_config_set("stck.forge", "gitea");
+    repo.git_config_set("stck.gitea-url", server.url());
+
+    repo.stck_cmd()
+        .env_remove("GITEA_TOKEN")
+        .env_remove("FORGEJO_TOKEN")
+        .arg("status")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "error: no Gitea/Forgejo access token found; set GITEA_TOKEN and retry",
+        ));
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn status_discovers_a_stack_with_one_batched_graphql_query() {
+    let repo = RealGitRepo::new();
+    repo.create_branch("feature-a");
+    repo.commit_file("a.txt", "a\n", "Add feature a");
+    repo.push("feature-a");
+    repo.create_branch("feature-b");
+    repo.commit_file("b.txt", "b\n", "Add feature b");
+    repo.push("feature-b");
+
+    repo.write_graphql_response(
+        r#"{"data":{"repository":{
+            "h0":{"nodes":[{"number":1,"headRefName":"feature-a","baseRefName":"main","state":"OPEN","isCrossRepository":false}]},
+            "c0":{"nodes":[{"number":2,"headRefName":"feature-b","baseRefName":"feature-a","state":"OPEN","isCrossRepository":false}]},
+            "h1":{"nodes":[{"number":2,"headRefName":"feature-b","baseRefName":"feature-a","state":"OPEN","isCrossRepository":false}]},
+            "c1":{"nodes":[]}
+        }}}"#,
+    );
+
+    repo.stck_cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Stack: main <- feature-a <- feature-b",
+        ))
+        .stdout(predicate::str::contains(
+            "* feature-b PR #2 OPEN base=feature-a",
+        ));
+
+    let gh_log = repo.gh_log();
+    assert_eq!(gh_log.matches("api graphql").count(), 1);
+    assert!(gh_log.contains("-f b0=feature-a -f b1=feature-b"));
+    assert!(
+        !gh_log.contains("pr list"),
+        "prefetched branches must not be looked up one by one"
+    );
+}
+
+#[test]
+fn status_discovers_a_multi_level_stack_with_only_batched_graphql_queries() {
+    let repo = RealGitRepo::new();
+    for (branch, file) in [
+        ("feature-a", "a.txt"),
+        ("feature-b", "b.txt"),
+        ("feature-c", "c.txt"),
+    ] {
+        repo.create_branch(branch);
+        repo.commit_file(file, "content\n", &format!("Add {branch}"));
+        repo.push(branch);
+    }
+
+    repo.write_graphql_response(
+        r#"{"data":{"repository":{
+            "h0":{"nodes":[{"number":1,"headRefName":"feature-a","baseRefName":"main","state":"OPEN","isCrossRepository":false}]},
+            "c0":{"nodes":[{"number":2,"headRefName":"feature-b","baseRefName":"feature-a","state":"OPEN","isCrossRepository":false}]},
+            "h1":{"nodes":[{"number":2,"headRefName":"feature-b","baseRefName":"feature-a","state":"OPEN","isCrossRepository":false}]},
+            "c1":{"nodes":[{"number":3,"headRefName":"feature-c","baseRefName":"feature-b","state":"OPEN","isCrossRepository":false}]},
+            "h2":{"nodes":[{"number":3,"headRefName":"feature-c","baseRefName":"feature-b","state":"OPEN","isCrossRepository":false}]},
+            "c2":{"nodes":[]}
+        }}}"#,
+    );
+
+    repo.stck_cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Stack: main <- feature-a <- feature-b <- feature-c",
+        ));
+
+    let gh_log = repo.gh_log();
+    assert_eq!(gh_log.matches("api graphql").count(), 1, "{gh_log}");
+    assert!(gh_log.contains("-f b0=feature-a -f b1=feature-b -f b2=feature-c"));
+    assert!(
+        !gh_log.contains("pr list") && !gh_log.contains("pr view"),
+        "every lookup must be answered by the batched query: {gh_log}"
+    );
+}
+
+#[test]
+fn status_reports_graphql_failures_other_than_query_rejections() {
+    let repo = RealGitRepo::new();
+    repo.create_branch("feature-a");
+    repo.commit_file("a.txt", "a\n", "Add feature a");
+    repo.push("feature-a");
+    repo.fail_graphql("error connecting to api.github.com");
+
+    repo.stck_cmd()
+        .arg("status")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "failed to look up stack PRs via GitHub GraphQL",
+        ))
+        .stderr(predicate::str::contains(
+            "error connecting to api.github.com",
+        ));
+    assert!(
+        !repo.gh_log().contains("pr list"),
+        "a transport failure must not fall back to per-branch lookups"
+    );
+}
+
+#[test]
+fn status_reports_graphql_errors_that_are_not_schema_rejections() {
+    let repo = RealGitRepo::new();
+    repo.create_branch("feature-a");
+    repo.commit_file("a.txt", "a\n", "Add feature a");
+    repo.push("feature-a");
+    repo.write_graphql_response(
+        r#"{"data":{"repository":null},"errors":[{"type":"NOT_FOUND","message":"Could not resolve to a Repository with the name 'example/stck'."}]}"#,
+    );
+
+    repo.stck_cmd()
+        .arg("status")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "failed to look up stack PRs via GitHub GraphQL: Could not resolve to a Repository",
+        ));
+    assert!(!repo.gh_log().contains("pr list"));
+}
+
+#[test]
+fn nav_rewrites_the_stack_block_and_keeps_hand_written_text() {
+    let repo = RealGitRepo::new();
+    repo.create_branch("feature-a");
+    repo.commit_file("a.txt", "a\n", "Add feature a");
+    repo.push("feature-a");
+    repo.create_branch("feature-b");
+    repo.commit_file("b.txt", "b\n", "Add feature b");
+    repo.push("feature-b");
+
+    repo.write_graphql_response(
+        r#"{"data":{"repository":{
+            "h0":{"nodes":[{"number":1,"headRefName":"feature-a","baseRefName":"main","state":"OPEN","isCrossRepository":false}]},
+            "c0":{"nodes":[{"number":2,"headRefName":"feature-b","baseRefName":"feature-a","state":"OPEN","isCrossRepository":false}]},
+            "h1":{"nodes":[{"number":2,"headRefName":"feature-b","baseRefName":"feature-a","state":"OPEN","isCrossRepository":false}]},
+            "c1":{"nodes":[]}
+        }}}"#,
+    );
+    repo.write_pr_body(
+        "feature-a",
+        "Reviewer notes.\n\n<!-- stck:stack:start -->\nstale\n<!-- stck:stack:end -->",
+    );
+    repo.write_pr_body("feature-b", "Adds feature b.");
+
+    repo.stck_cmd()
+        .arg("nav")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "$ gh pr edit feature-a --body \"<stack navigation>\"",
+        ))
+        .stdout(predicate::str::contains(
+            "Updated stack navigation in 2 PR description(s).",
+        ));
+
+    let body_a = repo
+        .pr_body("feature-a")
+        .expect("feature-a body should exist");
+    assert!(body_a.starts_with("Reviewer notes.\n\n<!-- stck:stack:start -->"));
+    assert!(body_a.contains("| → | **#1** | **`feature-a`** | `main` | open |"));
+    assert!(body_a.contains("|  | #2 | `feature-b` | `feature-a` | open |"));
+    assert!(!body_a.contains("stale"));
+
+    let body_b = repo
+        .pr_body("feature-b")
+        .expect("feature-b body should exist");
+    assert!(body_b.starts_with("Adds feature b.\n\n<!-- stck:stack:start -->"));
+    assert!(body_b.contains("| → | **#2** | **`feature-b`** | `feature-a` | open |"));
+
+    repo.stck_cmd()
+        .arg("nav")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Stack navigation is already up to date.",
+        ));
+}
+
+#[test]
+fn config_resolves_user_file_then_repo_file_then_git_config() {
+    let repo = RealGitRepo::new();
+
+    repo.stck_cmd()
+        .args(["config", "get", "draft"])
+        .assert()
+        .success()
+        .stdout("false\n");
+
+    repo.stck_cmd()
+        .args(["config", "set", "draft", "true", "--user"])
+        .assert()
+        .success();
+    let user_file = repo.user_config_dir().join("stck").join("config.toml");
+    assert_eq!(
+        std::fs::read_to_string(&user_file).expect("user config should be written"),
+        "draft = true\n"
+    );
+    repo.stck_cmd()
+        .args(["config", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("draft = true (user config)"))
+        .stdout(predicate::str::contains("remote = origin (default)"))
+        .stdout(predicate::str::contains("trunk = <unset> (default)"));
+
+    repo.write_repo_file(".stck.toml", "draft = false # repo policy\n");
+    repo.stck_cmd()
+        .args(["config", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("draft = false (.stck.toml)"));
+
+    repo.stck_cmd()
+        .args(["config", "set", "draft", "yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Set draft = yes in git config stck.draft.",
+        ));
+    assert_eq!(repo.git_config("stck.draft"), "yes");
+    repo.stck_cmd()
+        .args(["config", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("draft = yes (git config)"));
+
+    repo.stck_cmd()
+        .args(["config", "set", "draft", "maybe"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "error: config key `draft` expects true or false, found `maybe`",
+        ));
+    repo.stck_cmd()
+        .args(["config", "get", "colour"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "error: unknown config key `colour`",
+        ));
+}
+
+#[test]
+fn config_defaults_new_branches_to_a_prefix_and_prs_to_drafts() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_forge_fixture(
+        r#"{"repository":"example/fixture","default_branch":"main","pull_requests":[]}"#,
+    );
+    repo.commit_file(
+        ".stck.toml",
+        "draft = true\nbranch-prefix = \"me/\"\n",
+        "Configure stck",
+    );
+    repo.push("main");
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["new", "feature-a", "--local"])
+        .assert()
+        .success();
+    assert_eq!(repo.current_branch(), "me/feature-a");
+    repo.commit_file("a.txt", "a\n", "Add feature a");
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["submit", "--base", "main"])
+        .assert()
+        .success();
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["new", "me/feature-b", "--local"])
+        .assert()
+        .success();
+    assert_eq!(repo.current_branch(), "me/feature-b");
+    repo.commit_file("b.txt", "b\n", "Add feature b");
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["submit", "--no-draft"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Created PR for me/feature-b targeting me/feature-a.",
+        ));
+
+    let fixture = repo.read_forge_fixture();
+    assert_eq!(fixture["pull_requests"][0]["title"], "Add feature a");
+    assert_eq!(fixture["pull_requests"][0]["draft"], true);
+    assert_eq!(fixture["pull_requests"][1]["draft"], false);
+}
+
+#[test]
+fn fork_workflow_pushes_to_the_fork_and_opens_prs_against_the_base_repository() {
+    let repo = RealGitRepo::new();
+    let fork = repo.add_fork_remote("fork", "me");
+    repo.git_config_set("stck.push-remote", "fork");
+
+    repo.create_branch("feature-a");
+    repo.commit_file("a.txt", "a\n", "Add feature a");
+    repo.stck_cmd()
+        .args(["submit", "--base", "main"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("$ git push -u fork feature-a"))
+        .stdout(predicate::str::contains(
+            "$ gh pr create --base main --head me:feature-a --title 'Add feature a'",
+        ));
+    assert!(repo.remote_branch_exists_in(&fork, "feature-a"));
+    assert!(!repo.remote_branch_exists("feature-a"));
+    assert!(repo
+        .gh_log()
+        .contains("pr create --base main --head me:feature-a"));
+
+    // Same-named heads from the base repository or other forks are not ours.
+    repo.write_all_pr_head_response(
+        "feature-a",
+        r#"[
+            {"number":5,"headRefName":"feature-a","baseRefName":"main","state":"OPEN","isCrossRepository":false},
+            {"number":6,"headRefName":"feature-a","baseRefName":"main","state":"OPEN","isCrossRepository":true,"headRepositoryOwner":{"login":"someone"}},
+            {"number":7,"headRefName":"feature-a","baseRefName":"main","state":"OPEN","isCrossRepository":true,"headRepositoryOwner":{"login":"me"}}
+        ]"#,
+    );
+    repo.write_children_response("feature-a", "[]");
+
+    repo.stck_cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("* feature-a PR #7 OPEN base=main"))
+        .stdout(predicate::str::contains(
+            "Summary: 0 needs_sync, 0 needs_push, 0 base_mismatch",
+        ));
+}
+
+#[test]
+fn insert_adds_a_branch_below_the_child_and_retargets_its_pr() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_fixture_stack(&["feature-a", "feature-b"]);
+    repo.checkout("feature-a");
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["insert", "feature-fix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Inserting feature-fix between feature-a and feature-b.",
+        ))
+        .stdout(predicate::str::contains("$ git push -u origin feature-fix"))
+        .stdout(predicate::str::contains(
+            "fixture: retarget PR feature-b -> feature-fix",
+        ))
+        .stdout(predicate::str::contains(
+            "stck insert finished; feature-fix is checked out.",
+        ));
+
+    assert_eq!(repo.current_branch(), "feature-fix");
+    assert_eq!(repo.local_sha("feature-fix"), repo.local_sha("feature-a"));
+    assert!(repo.remote_branch_exists("feature-fix"));
+    assert_eq!(
+        repo.git_config("branch.feature-fix.stck-parent"),
+        "feature-a"
+    );
+    assert_eq!(
+        repo.git_config("branch.feature-b.stck-parent"),
+        "feature-fix"
+    );
+    assert_eq!(
+        repo.read_forge_fixture()["pull_requests"][1]["baseRefName"],
+        "feature-fix"
+    );
+    assert!(!repo.sync_state_exists());
+
+    // Commits on the inserted branch flow into the child with a restack.
+    repo.commit_file("fix.txt", "fix\n", "Fix feature a");
+    repo.checkout("feature-b");
+    repo.disconnect_origin();
+    repo.stck_cmd()
+        .args(["restack"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "restacking feature-b onto feature-fix",
+        ));
+    assert!(repo.is_ancestor("feature-fix", "feature-b"));
+}
+
+#[test]
+fn insert_resumes_with_continue_after_a_failed_push() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_fixture_stack(&["feature-a", "feature-b"]);
+    repo.checkout("feature-a");
+    repo.git_config_set("stck.auto-fetch", "false");
+    repo.disconnect_origin();
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["insert", "feature-fix"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "error: failed to push branch feature-fix with upstream",
+        ))
+        .stderr(predicate::str::contains(
+            "Fix the error and rerun `stck insert --continue` to resume.",
+        ));
+    assert!(repo.sync_state_exists());
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["insert", "feature-other"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "error: a `stck insert` is in progress; run `stck insert --continue` to finish it or `stck insert --reset` to discard it",
+        ));
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .arg("sync")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("a `stck insert` is in progress"));
+
+    repo.reconnect_origin();
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["insert", "--continue"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Resuming previous insert from saved state.",
+        ))
+        .stdout(predicate::str::contains("$ git branch feature-fix").not())
+        .stdout(predicate::str::contains(
+            "fixture: retarget PR feature-b -> feature-fix",
+        ));
+
+    assert_eq!(repo.current_branch(), "feature-fix");
+    assert!(repo.remote_branch_exists("feature-fix"));
+    assert!(!repo.sync_state_exists());
+}
+
+#[test]
+fn move_reorders_a_branch_and_retargets_the_affected_prs() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_fixture_stack(&["feature-a", "feature-b", "feature-c"]);
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["move", "feature-c", "--before", "feature-a"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Moving feature-c before feature-a.",
+        ))
+        .stdout(predicate::str::contains(
+            "Rebasing feature-c onto main (from feature-b)",
+        ))
+        .stdout(predicate::str::contains(
+            "Rebasing feature-a onto feature-c (from main)",
+        ))
+        .stdout(predicate::str::contains(
+            "fixture: retarget PR feature-c -> main",
+        ))
+        .stdout(predicate::str::contains(
+            "fixture: retarget PR feature-a -> feature-c",
+        ));
+
+    assert_eq!(repo.current_branch(), "feature-c");
+    assert_eq!(repo.commit_count("main..feature-c"), 1);
+    assert!(repo.is_ancestor("feature-c", "feature-a"));
+    assert!(repo.is_ancestor("feature-a", "feature-b"));
+    assert_eq!(repo.commit_count("main..feature-b"), 3);
+    for branch in ["feature-a", "feature-b", "feature-c"] {
+        assert_eq!(repo.remote_sha(branch), repo.local_sha(branch));
+    }
+    let fixture = repo.read_forge_fixture();
+    assert_eq!(fixture["pull_requests"][0]["baseRefName"], "feature-c");
+    assert_eq!(fixture["pull_requests"][1]["baseRefName"], "feature-a");
+    assert_eq!(fixture["pull_requests"][2]["baseRefName"], "main");
+}
+
+#[test]
+fn move_continues_after_a_rebase_conflict_is_resolved() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_fixture_stack(&["feature-a", "feature-b"]);
+    repo.commit_file("feature-a.txt", "changed by b\n", "Change a from b");
+    repo.push("feature-b");
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["move", "feature-b", "--onto", "main"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "error: rebase failed for branch feature-b",
+        ))
+        .stderr(predicate::str::contains(
+            "Then run `stck move --continue` to resume.",
+        ));
+
+    repo.resolve_rebase_conflict("feature-a.txt", "changed by b\n");
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["move", "--continue"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Resuming previous move from saved state.",
+        ))
+        .stdout(predicate::str::contains(
+            "fixture: retarget PR feature-b -> main",
+        ));
+
+    assert_eq!(repo.current_branch(), "feature-b");
+    assert!(!repo.is_ancestor("feature-a", "feature-b"));
+    assert_eq!(repo.remote_sha("feature-b"), repo.local_sha("feature-b"));
+    assert_eq!(
+        repo.read_forge_fixture()["pull_requests"][1]["baseRefName"],
+        "main"
+    );
+    assert!(!repo.sync_state_exists());
+}
+
+#[test]
+fn split_creates_stacked_branches_with_prs_and_moves_children_onto_the_last() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_fixture_stack(&["feature-a", "feature-b"]);
+    repo.checkout("feature-a");
+    repo.commit_file("parser.txt", "parser\n", "Add parser");
+    repo.commit_file("lexer.txt", "lexer\n", "Add lexer");
+    repo.push("feature-a");
+    let tip = repo.local_sha("feature-a");
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args([
+            "split",
+            "feature-parser=feature-a~1",
+            "feature-lexer=feature-a",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Splitting feature-a into feature-a, feature-parser, feature-lexer.",
+        ))
+        .stdout(predicate::str::contains(
+            "fixture: retarget PR feature-b -> feature-lexer",
+        ))
+        .stdout(predicate::str::contains(
+            "fixture: create PR feature-parser -> feature-a",
+        ))
+        .stdout(predicate::str::contains(
+            "fixture: create PR feature-lexer -> feature-parser",
+        ))
+        .stdout(predicate::str::contains(
+            "stck split finished; feature-a is checked out.",
+        ));
+
+    assert_eq!(repo.current_branch(), "feature-a");
+    assert_eq!(repo.local_sha("feature-lexer"), tip);
+    assert_eq!(repo.commit_count("main..feature-a"), 1);
+    assert_eq!(repo.commit_count("feature-a..feature-parser"), 1);
+    assert_eq!(repo.commit_count("feature-parser..feature-lexer"), 1);
+    assert!(repo.is_ancestor("feature-lexer", "feature-b"));
+    assert_eq!(repo.remote_sha("feature-a"), repo.local_sha("feature-a"));
+    assert_eq!(repo.remote_sha("feature-lexer"), tip);
+    assert_eq!(
+        repo.git_config("branch.feature-lexer.stck-parent"),
+        "feature-parser"
+    );
+
+    let forge = repo.read_forge_fixture();
+    let prs = forge["pull_requests"].as_array().expect("pull requests");
+    assert_eq!(prs[1]["baseRefName"], "feature-lexer");
+    assert_eq!(prs[2]["headRefName"], "feature-parser");
+    assert_eq!(prs[2]["title"], "Add parser");
+    assert_eq!(prs[3]["headRefName"], "feature-lexer");
+    assert_eq!(prs[3]["baseRefName"], "feature-parser");
+    assert!(!repo.sync_state_exists());
+}
+
+#[test]
+fn split_reads_split_points_from_stdin_when_none_are_given() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_fixture_stack(&["feature-a"]);
+    repo.commit_file("parser.txt", "parser\n", "Add parser");
+    repo.push("feature-a");
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .arg("split")
+        .write_stdin("2 feature-parser\n\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1. "))
+        .stdout(predicate::str::contains(" Add feature-a"))
+        .stdout(predicate::str::contains(" Add parser"))
+        .stdout(predicate::str::contains(
+            "Splitting feature-a into feature-a, feature-parser.",
+        ));
+
+    assert_eq!(repo.commit_count("main..feature-a"), 1);
+    assert_eq!(repo.commit_count("feature-a..feature-parser"), 1);
+    assert_eq!(
+        repo.read_forge_fixture()["pull_requests"][1]["baseRefName"],
+        "feature-a"
+    );
+}
+
+#[test]
+fn split_rejects_a_commit_outside_the_branch() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_fixture_stack(&["feature-a"]);
+    repo.commit_file("parser.txt", "parser\n", "Add parser");
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["split", "feature-parser=main"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "error: main is not one of the commits feature-a adds on top of main",
+        ));
+    assert!(!repo.sync_state_exists());
+}
+
+#[test]
+fn fold_moves_the_branch_into_its_parent_and_closes_its_pr() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_fixture_stack(&["feature-a", "feature-b", "feature-c"]);
+    repo.checkout("feature-b");
+    let folded_tip = repo.local_sha("feature-b");
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .arg("fold")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Folding feature-b into feature-a.",
+        ))
+        .stdout(predicate::str::contains(
+            "$ git branch --force feature-a feature-b",
+        ))
+        .stdout(predicate::str::contains(
+            "fixture: retarget PR feature-c -> feature-a",
+        ))
+        .stdout(predicate::str::contains("fixture: close PR feature-b"))
+        .stdout(predicate::str::contains("--delete feature-b"))
+        .stdout(predicate::str::contains(
+            "stck fold finished; feature-a is checked out.",
+        ));
+
+    assert_eq!(repo.current_branch(), "feature-a");
+    assert_eq!(repo.local_sha("feature-a"), folded_tip);
+    assert_eq!(repo.remote_sha("feature-a"), folded_tip);
+    assert!(!repo.remote_branch_exists("feature-b"));
+    assert!(!repo.local_branch_exists("feature-b"));
+    assert_eq!(repo.git_config("branch.feature-c.stck-parent"), "feature-a");
+
+    let forge = repo.read_forge_fixture();
+    let prs = forge["pull_requests"].as_array().expect("pull requests");
+    assert_eq!(prs[1]["state"], "CLOSED");
+    assert_eq!(
+        prs[1]["comments"][0],
+        "Folded into #1 (`feature-a`) with `stck fold`; these commits continue there."
+    );
+    assert_eq!(prs[2]["baseRefName"], "feature-a");
+    assert!(!repo.sync_state_exists());
+}
+
+#[test]
+fn fold_rebases_onto_a_parent_that_moved_on() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_fixture_stack(&["feature-a", "feature-b"]);
+    repo.checkout("feature-a");
+    repo.commit_file("review.txt", "review\n", "Address review");
+    repo.push("feature-a");
+    repo.checkout("feature-b");
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .arg("fold")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Rebasing feature-b onto feature-a",
+        ));
+
+    assert_eq!(repo.current_branch(), "feature-a");
+    assert_eq!(repo.commit_count("main..feature-a"), 3);
+    assert_eq!(repo.remote_sha("feature-a"), repo.local_sha("feature-a"));
+    assert_eq!(
+        repo.read_forge_fixture()["pull_requests"][1]["state"],
+        "CLOSED"
+    );
+}
+
+#[test]
+fn fold_rejects_a_branch_on_the_default_branch() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_fixture_stack(&["feature-a"]);
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .arg("fold")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "error: feature-a is stacked directly on main; use `stck land` to merge it instead",
+        ));
+}
+
+#[test]
+fn remove_drops_a_middle_branch_and_restacks_its_children_onto_its_parent() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_fixture_stack(&["feature-a", "feature-b", "feature-c"]);
+    repo.checkout("feature-c");
+    let removed_tip = repo.local_sha("feature-b");
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["remove", "feature-b"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Removing feature-b from the stack; moving feature-c onto feature-a.",
+        ))
+        .stdout(predicate::str::contains(
+            "fixture: retarget PR feature-c -> feature-a",
+        ))
+        .stdout(predicate::str::contains("fixture: close PR feature-b"))
+        .stdout(predicate::str::contains(
+            "stck remove finished; feature-c is checked out.",
+        ));
+
+    assert_eq!(repo.current_branch(), "feature-c");
+    assert!(repo.is_ancestor("feature-a", "feature-c"));
+    assert!(!repo.is_ancestor(&removed_tip, "feature-c"));
+    assert_eq!(repo.commit_count("feature-a..feature-c"), 1);
+    assert_eq!(repo.remote_sha("feature-c"), repo.local_sha("feature-c"));
+    assert_eq!(repo.git_config("branch.feature-c.stck-parent"), "feature-a");
+    // Without --delete the branch is kept as is.
+    assert_eq!(repo.local_sha("feature-b"), removed_tip);
+    assert!(repo.remote_branch_exists("feature-b"));
+
+    let forge = repo.read_forge_fixture();
+    let prs = forge["pull_requests"].as_array().expect("pull requests");
+    assert_eq!(prs[1]["state"], "CLOSED");
+    assert_eq!(
+        prs[1]["comments"][0],
+        "Removed from the stack with `stck remove`."
+    );
+    assert_eq!(prs[2]["baseRefName"], "feature-a");
+    assert!(!repo.sync_state_exists());
+}
+
+#[test]
+fn remove_with_delete_deletes_the_current_root_branch() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_fixture_stack(&["feature-a", "feature-b"]);
+    repo.checkout("feature-a");
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["remove", "--delete"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "fixture: retarget PR feature-b -> main",
+        ))
+        .stdout(predicate::str::contains("$ git branch -D feature-a"))
+        .stdout(predicate::str::contains(
+            "stck remove finished; feature-b is checked out.",
+        ));
+
+    assert_eq!(repo.current_branch(), "feature-b");
+    assert!(!repo.local_branch_exists("feature-a"));
+    assert!(!repo.remote_branch_exists("feature-a"));
+    assert_eq!(repo.commit_count("main..feature-b"), 1);
+    assert_eq!(
+        repo.read_forge_fixture()["pull_requests"][1]["baseRefName"],
+        "main"
+    );
+}
+
+#[test]
+fn rename_recreates_the_pr_and_retargets_children_to_the_new_branch() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_fixture_stack(&["feature-a", "feature-b", "feature-c"]);
+    let mut forge = repo.read_forge_fixture();
+    forge["pull_requests"][1]["draft"] = true.into();
+    forge["pull_requests"][1]["reviewers"] = serde_json::json!(["octocat"]);
+    forge["pull_requests"][1]["labels"] = serde_json::json!(["stacked"]);
+    repo.write_forge_fixture(&forge.to_string());
+    repo.git_config_set("branch.feature-c.stck-parent", "feature-b");
+    repo.checkout("feature-b");
+    let tip = repo.local_sha("feature-b");
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["rename", "feature-login"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Renaming feature-b to feature-login.",
+        ))
+        .stdout(predicate::str::contains(
+            "$ git branch -m feature-b feature-login",
+        ))
+        .stdout(predicate::str::contains(
+            "fixture: create PR feature-login -> feature-a (draft)",
+        ))
+        .stdout(predicate::str::contains(
+            "fixture: retarget PR feature-c -> feature-login",
+        ))
+        .stdout(predicate::str::contains("fixture: close PR feature-b"))
+        .stdout(predicate::str::contains(
+            "stck rename finished; feature-login is checked out.",
+        ));
+
+    assert_eq!(repo.current_branch(), "feature-login");
+    assert_eq!(repo.local_sha("feature-login"), tip);
+    assert!(!repo.local_branch_exists("feature-b"));
+    assert_eq!(repo.remote_sha("feature-login"), tip);
+    assert!(!repo.remote_branch_exists("feature-b"));
+    assert_eq!(
+        repo.git_config("branch.feature-c.stck-parent"),
+        "feature-login"
+    );
+
+    let forge = repo.read_forge_fixture();
+    let prs = forge["pull_requests"].as_array().expect("pull requests");
+    assert_eq!(prs[1]["state"], "CLOSED");
+    assert_eq!(
+        prs[1]["comments"][0],
+        "Renamed to `feature-login` with `stck rename`; review continues in the PR for that branch."
+    );
+    assert_eq!(prs[2]["baseRefName"], "feature-login");
+    assert_eq!(prs[3]["headRefName"], "feature-login");
+    assert_eq!(prs[3]["baseRefName"], "feature-a");
+    assert_eq!(prs[3]["title"], "Add feature-b");
+    assert!(prs[3]["body"]
+        .as_str()
+        .expect("body")
+        .starts_with("Body of feature-b"));
+    assert_eq!(prs[3]["draft"], true);
+    assert_eq!(prs[3]["reviewers"], serde_json::json!(["octocat"]));
+    assert_eq!(prs[3]["labels"], serde_json::json!(["stacked"]));
+    assert!(!repo.sync_state_exists());
+}
+
+#[test]
+fn rename_resumes_with_continue_after_a_failed_push() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_fixture_stack(&["feature-a"]);
+    repo.git_config_set("stck.auto-fetch", "false");
+    repo.disconnect_origin();
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["rename", "feature-login"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "Fix the error and rerun `stck rename --continue` to resume.",
+        ));
+    assert_eq!(repo.current_branch(), "feature-login");
+
+    repo.reconnect_origin();
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["rename", "--continue"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Resuming previous rename from saved state.",
+        ))
+        .stdout(predicate::str::contains("fixture: close PR feature-a"));
+
+    assert!(repo.remote_branch_exists("feature-login"));
+    assert!(!repo.remote_branch_exists("feature-a"));
+    assert_eq!(
+        repo.read_forge_fixture()["pull_requests"][1]["headRefName"],
+        "feature-login"
+    );
+}
+
+#[test]
+fn sync_dry_run_prints_the_plan_without_rebasing_or_saving_state() {
+    let repo = RealGitRepo::new();
+    let fixture = repo.write_fixture_stack(&["feature-a", "feature-b"]);
+    let (a_sha, b_sha) = (repo.local_sha("feature-a"), repo.local_sha("feature-b"));
+    repo.checkout("main");
+    repo.commit_file("main.txt", "main advanced\n", "Advance main");
+    repo.push("main");
+    let main_sha = repo.local_sha("main");
+    repo.checkout("feature-b");
+
+    repo.stck_cmd()
+        .env("STCK_FORGE_FIXTURE", &fixture)
+        .args(["sync", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Step 1/2: rebase feature-a onto main (dropping already-upstream commits)",
+        ))
+        .stdout(predicate::str::contains(format!(
+            "  onto refs/remotes/origin/main = {main_sha}"
+        )))
+        .stdout(predicate::str::contains(" Add feature-a"))
+        .stdout(predicate::str::contains(
+            "Step 2/2: rebase feature-b onto feature-a (dropping already-upstream commits)",
+        ))
+        .stdout(predicate::str::contains(format!(
+            "$ git rebase --onto refs/heads/feature-a {a_sha} feature-b"
+        )))
+        .stdout(predicate::str::contains(
+            "  onto refs/heads/feature-a = its tip after step 1",
+        ))
+        .stdout(predicate::str::contains("  carries 1 commit(s):"))
+        .stdout(predicate::str::contains(" Add feature-b"))
+        .stdout(predicate::str::contains(format!(
+            "  origin/feature-b expected at {b_sha}"
+        )))
+        .stdout(predicate::str::contains(
+            "Dry run: nothing was changed. Run `stck sync` to apply this plan.",
+        ));
+
+    assert_eq!(repo.current_branch(), "feature-b");
+    assert_eq!(repo.local_sha("feature-a"), a_sha);
+    assert_eq!(repo.local_sha("feature-b"), b_sha);
+    assert!(!repo.sync_state_exists());
+}
+
+#[test]
+fn push_dry_run_previews_leased_pushes_and_fails_on_a_stale_lease() {
+    let repo = RealGitRepo::new();
+
+    repo.create_branch("feature-base");
+    repo.commit_file("base.txt", "base\n", "Add base feature");
+    repo.push("feature-base");
+    let old_base_sha = repo.remote_sha("feature-base");
+
+    repo.create_branch("feature-child");
+    repo.commit_file("child.txt", "child\n", "Add child feature");
+    repo.push("feature-child");
+    let old_child_sha = repo.remote_sha("feature-child");
+
+    repo.checkout("main");
+    repo.commit_file("main.txt", "main advanced\n", "Advance main");
+    repo.push("main");
+    repo.checkout("feature-base");
+
+    repo.write_pr_response(
+        "feature-base",
+        r#"{"number":101,"headRefName":"feature-base","baseRefName":"main","state":"OPEN"}"#,
+    );
+    repo.write_pr_response(
+        "feature-child",
+        r#"{"number":102,"headRefName":"feature-child","baseRefName":"feature-base","state":"OPEN"}"#,
+    );
+    repo.write_children_response(
+        "feature-base",
+        r#"[{"number":102,"headRefName":"feature-child","baseRefName":"feature-base","state":"OPEN"}]"#,
+    );
+    repo.write_children_response("feature-child", "[]");
+
+    let mut sync = repo.stck_cmd();
+    sync.arg("sync");
+    sync.assert().success();
+
+    let mut dry_run = repo.stck_cmd();
+    dry_run.args(["push", "--dry-run"]);
+    dry_run
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Push 1/2: feature-base (force-with-lease, expected origin/feature-base at {old_base_sha})"
+        )))
+        .stdout(predicate::str::contains(format!(
+            "$ git push --force-with-lease=refs/heads/feature-child:{old_child_sha} origin feature-child"
+        )))
+        .stdout(predicate::str::contains(
+            "Dry run: nothing was changed. Run `stck push` to apply this plan.",
+        ));
+
+    assert_eq!(repo.remote_sha("feature-base"), old_base_sha);
+    assert_eq!(repo.remote_sha("feature-child"), old_child_sha);
+    assert!(!repo.sync_state_exists());
+
+    repo.force_push_remote("main", "feature-child");
+    let main_sha = repo.remote_sha("main");
+
+    let mut stale = repo.stck_cmd();
+    stale.args(["push", "--dry-run"]);
+    stale
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(format!(
+            "Push 2/2: feature-child (stale lease: sync expected origin/feature-child at {old_child_sha}, found {main_sha})"
+        )))
+        .stderr(predicate::str::contains(
+            "error: 1 push(es) would be refused; integrate the remote changes locally, then rerun `stck sync` before pushing",
+        ));
+
+    assert_eq!(repo.remote_sha("feature-base"), old_base_sha);
+    assert!(!repo.sync_state_exists());
+}
+
+#[test]
+fn undo_restores_every_branch_after_a_completed_sync() {
+    let repo = RealGitRepo::new();
+
+    repo.create_branch("feature-base");
+    repo.commit_file("base.txt", "base\n", "Add base feature");
+    repo.push("feature-base");
+    let old_base_sha = repo.local_sha("refs/heads/feature-base");
+
+    repo.create_branch("feature-child");
+    repo.commit_file("child.txt", "child\n", "Add child feature");
+    repo.push("feature-child");
+    let old_child_sha = repo.local_sha("refs/heads/feature-child");
+
+    repo.checkout("main");
+    repo.commit_file("main.txt", "main advanced\n", "Advance main");
+    repo.push("main");
+    repo.checkout("feature-child");
+
+    repo.write_pr_response(
+        "feature-base",
+        r#"{"number":101,"headRefName":"feature-base","baseRefName":"main","state":"OPEN"}"#,
+    );
+    repo.write_pr_response(
+        "feature-child",
+        r#"{"number":102,"headRefName":"feature-child","baseRefName":"feature-base","state":"OPEN"}"#,
+    );
+    repo.write_children_response(
+        "feature-base",
+        r#"[{"number":102,"headRefName":"feature-child","baseRefName":"feature-base","state":"OPEN"}]"#,
+    );
+    repo.write_children_response("feature-child", "[]");
+
+    let mut sync = repo.stck_cmd();
+    sync.arg("sync");
+    sync.assert().success();
+    assert_ne!(repo.local_sha("refs/heads/feature-base"), old_base_sha);
+    assert_ne!(repo.local_sha("refs/heads/feature-child"), old_child_sha);
+
+    let mut undo = repo.stck_cmd();
+    undo.arg("undo");
+    undo.assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Restored feature-base to {}",
+            &old_base_sha[..7]
+        )))
+        .stdout(predicate::str::contains(
+            "Undo restored 2 branch(es) to their heads before the last sync.",
+        ));
+
+    assert_eq!(repo.local_sha("refs/heads/feature-base"), old_base_sha);
+    assert_eq!(repo.local_sha("refs/heads/feature-child"), old_child_sha);
+    assert_eq!(repo.current_branch(), "feature-child");
+    assert!(!repo.is_ancestor("refs/remotes/origin/main", "refs/heads/feature-base"));
+
+    // The checked-out branch's working tree follows its restored head.
+    let mut status = repo.stck_cmd();
+    status.arg("status");
+    status
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("working tree is not clean").not());
+
+    let mut again = repo.stck_cmd();
+    again.arg("undo");
+    again.assert().code(1).stderr(predicate::str::contains(
+        "error: no branch backup to restore",
+    ));
+}
+
+#[test]
+fn undo_aborts_a_conflicted_sync_and_restores_the_rebased_parent() {
+    let repo = RealGitRepo::new();
+    repo.commit_file("shared.txt", "shared\n", "Add shared file");
+    repo.push("main");
+
+    repo.create_branch("feature-base");
+    repo.commit_file("base.txt", "base\n", "Add base feature");
+    repo.push("feature-base");
+    let old_base_sha = repo.local_sha("refs/heads/feature-base");
+
+    repo.create_branch("feature-child");
+    repo.commit_file("shared.txt", "child\n", "Change shared file on child");
+    repo.push("feature-child");
+    let old_child_sha = repo.local_sha("refs/heads/feature-child");
+
+    repo.checkout("main");
+    repo.commit_file("shared.txt", "main\n", "Change shared file on main");
+    repo.push("main");
+    repo.checkout("feature-base");
+
+    repo.write_pr_response(
+        "feature-base",
+        r#"{"number":101,"headRefName":"feature-base","baseRefName":"main","state":"OPEN"}"#,
+    );
+    repo.write_pr_response(
+        "feature-child",
+        r#"{"number":102,"headRefName":"feature-child","baseRefName":"feature-base","state":"OPEN"}"#,
+    );
+    repo.write_children_response(
+        "feature-base",
+        r#"[{"number":102,"headRefName":"feature-child","baseRefName":"feature-base","state":"OPEN"}]"#,
+    );
+    repo.write_children_response("feature-child", "[]");
+
+    let mut sync = repo.stck_cmd();
+    sync.arg("sync");
+    sync.assert()
+        .code(1)
+        .stdout(predicate::str::contains("CONFLICT"));
+    assert_ne!(repo.local_sha("refs/heads/feature-base"), old_base_sha);
+    assert!(repo.sync_state_exists());
+
+    let mut undo = repo.stck_cmd();
+    undo.arg("undo");
+    undo.assert()
+        .success()
+        .stdout(predicate::str::contains("$ git rebase --abort"))
+        .stdout(predicate::str::contains(
+            "Undo restored 2 branch(es) to their heads before the last sync.",
+        ));
+
+    assert_eq!(repo.local_sha("refs/heads/feature-base"), old_base_sha);
+    assert_eq!(repo.local_sha("refs/heads/feature-child"), old_child_sha);
+    assert!(!repo.sync_state_exists());
+
+    // With the state cleared, a plain sync plans from scratch again.
+    let mut retry = repo.stck_cmd();
+    retry.arg("sync");
+    retry
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("CONFLICT"));
+}
+
+#[test]
+fn log_lists_journaled_sync_and_push_operations_newest_first() {
+    let repo = RealGitRepo::new();
+
+    repo.create_branch("feature-base");
+    repo.commit_file("base.txt", "base\n", "Add base feature");
+    repo.push("feature-base");
+    let old_base_sha = repo.local_sha("refs/heads/feature-base");
+
+    repo.checkout("main");
+    repo.commit_file("main.txt", "main advanced\n", "Advance main");
+    repo.push("main");
+    repo.checkout("feature-base");
+
+    repo.write_pr_response(
+        "feature-base",
+        r#"{"number":101,"headRefName":"feature-base","baseRefName":"main","state":"OPEN"}"#,
+    );
+    repo.write_children_response("feature-base", "[]");
+
+    let mut empty = repo.stck_cmd();
+    empty.arg("log");
+    empty
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No stck operations recorded yet."));
+
+    let mut sync = repo.stck_cmd();
+    sync.arg("sync");
+    sync.assert().success();
+    let new_base_sha = repo.local_sha("refs/heads/feature-base");
+
+    let mut dry_run = repo.stck_cmd();
+    dry_run.args(["push", "--dry-run"]);
+    dry_run.assert().success();
+
+    let mut push = repo.stck_cmd();
+    push.arg("push");
+    push.assert().success();
+
+    let mut log = repo.stck_cmd();
+    log.arg("log");
+    log.assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(r"(?s)stck push  succeeded.*stck sync  succeeded").unwrap(),
+        )
+        .stdout(predicate::str::contains(
+            "  plan: rebase feature-base onto main",
+        ))
+        .stdout(predicate::str::contains(format!(
+            "  feature-base: {} -> {}",
+            &old_base_sha[..7],
+            &new_base_sha[..7]
+        )))
+        .stdout(predicate::str::contains("  plan: push feature-base"))
+        .stdout(predicate::str::contains("--dry-run").not());
+
+    let mut json = repo.stck_cmd();
+    json.args(["log", "--json", "--limit", "1"]);
+    let output = json.assert().success().get_output().stdout.clone();
+    let entries: serde_json::Value =
+        serde_json::from_slice(&output).expect("log --json should print JSON");
+    let entries = entries
+        .as_array()
+        .expect("log --json should print an array");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["command"], "push");
+    assert_eq!(entries[0]["outcome"], "succeeded");
+    assert_eq!(entries[0]["plan"]["kind"], "push");
+}
+
+#[test]
+fn undo_restores_recorded_fork_points_after_a_restack() {
+    let repo = RealGitRepo::new();
+    for (branch, file) in [("feature-a", "a.txt"), ("feature-b", "b.txt")] {
+        repo.stck_cmd()
+            .args(["new", branch, "--local"])
+            .assert()
+            .success();
+        repo.commit_file(file, "content\n", &format!("Add {branch}"));
+    }
+    let old_fork_point = repo.git_config("branch.feature-b.stck-fork-point");
+    let old_b_sha = repo.local_sha("refs/heads/feature-b");
+    repo.checkout("feature-a");
+    repo.amend_file("a.txt", "amended\n");
+    repo.disconnect_origin();
+
+    let mut restack = repo.stck_cmd();
+    restack.arg("restack");
+    restack.assert().success();
+    assert_ne!(
+        repo.git_config("branch.feature-b.stck-fork-point"),
+        old_fork_point
+    );
+
+    let mut undo = repo.stck_cmd();
+    undo.arg("undo");
+    undo.assert().success();
+
+    assert_eq!(repo.local_sha("refs/heads/feature-b"), old_b_sha);
+    assert_eq!(
+        repo.git_config("branch.feature-b.stck-fork-point"),
+        old_fork_point
+    );
+    assert_eq!(repo.git_config("branch.feature-b.stck-parent"), "feature-a");
+}
+
+#[test]
+fn undo_refuses_when_a_branch_gained_a_commit_after_the_sync() {
+    let repo = RealGitRepo::new();
+
+    repo.create_branch("feature-base");
+    repo.commit_file("base.txt", "base\n", "Add base feature");
+    repo.push("feature-base");
+
+    repo.create_branch("feature-child");
+    repo.commit_file("child.txt", "child\n", "Add child feature");
+    repo.push("feature-child");
+
+    repo.checkout("main");
+    repo.commit_file("main.txt", "main advanced\n", "Advance main");
+    repo.push("main");
+    repo.checkout("feature-base");
+
+    repo.write_pr_response(
+        "feature-base",
+        r#"{"number":101,"headRefName":"feature-base","baseRefName":"main","state":"OPEN"}"#,
+    );
+    repo.write_pr_response(
+        "feature-child",
+        r#"{"number":102,"headRefName":"feature-child","baseRefName":"feature-base","state":"OPEN"}"#,
+    );
+    repo.write_children_response(
+        "feature-base",
+        r#"[{"number":102,"headRefName":"feature-child","baseRefName":"feature-base","state":"OPEN"}]"#,
+    );
+    repo.write_children_response("feature-child", "[]");
+
+    let mut sync = repo.stck_cmd();
+    sync.arg("sync");
+    sync.assert().success();
+    let synced_base_sha = repo.local_sha("refs/heads/feature-base");
+    let synced_child_sha = repo.local_sha("refs/heads/feature-child");
+
+    repo.checkout("feature-child");
+    repo.commit_file("later.txt", "later\n", "Add later work");
+    let later_sha = repo.local_sha("refs/heads/feature-child");
+
+    let mut undo = repo.stck_cmd();
+    undo.arg("undo");
+    undo.assert().code(1).stderr(predicate::str::contains(format!(
+        "error: branch feature-child moved from {} to {} after the sync; undo would discard those commits, so nothing was restored",
+        &synced_child_sha[..7],
+        &later_sha[..7]
+    )));
+
+    assert_eq!(repo.local_sha("refs/heads/feature-base"), synced_base_sha);
+    assert_eq!(repo.local_sha("refs/heads/feature-child"), later_sha);
+}
@@ -61,14 +61,29 @@ fn status_shows_missing_pr_remediation() {
 }
 
 #[test]
-fn status_fails_on_non_linear_stack() {
+fn status_renders_branching_stack_as_tree() {
     let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
     cmd.env("STCK_TEST_NON_LINEAR", "1");
     cmd.arg("status");
 
-    cmd.assert().code(1).stderr(predicate::str::contains(
-        "error: non-linear stack detected at feature-branch; child candidates: feature-child-a, feature-child-b",
-    ));
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Stack: main (tree)\n"))
+        .stdout(predicate::str::contains(
+            "  feature-base PR #100 MERGED base=main\n",
+        ))
+        .stdout(predicate::str::contains(
+            "*   feature-branch PR #101 OPEN base=feature-base [needs_sync]\n",
+        ))
+        .stdout(predicate::str::contains(
+            "      feature-child-a PR #102 OPEN base=feature-branch\n",
+        ))
+        .stdout(predicate::str::contains(
+            "      feature-child-b PR #103 OPEN base=feature-branch\n",
+        ))
+        .stdout(predicate::str::contains(
+            "Summary: 1 needs_sync, 0 needs_push, 0 base_mismatch",
+        ));
 }
 
 #[test]
@@ -141,3 +156,45 @@ fn status_shows_fetch_failure_remediation() {
         "error: failed to fetch from `origin`; check remote connectivity and permissions",
     ));
 }
+
+#[test]
+fn status_json_prints_versioned_document() {
+    let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    cmd.env("STCK_TEST_NEEDS_PUSH_BRANCH", "feature-child");
+    cmd.args(["status", "--json"]);
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let document: serde_json::Value =
+        serde_json::from_slice(&output).expect("status --json should print JSON");
+
+    assert_eq!(document["schema_version"], 2);
+    assert_eq!(document["repository"], "example/stck");
+    assert_eq!(document["default_branch"], "main");
+    assert_eq!(document["current_branch"], "feature-branch");
+    let branches = document["branches"]
+        .as_array()
+        .expect("branches should be an array");
+    assert_eq!(branches.len(), 3);
+    assert_eq!(branches[1]["branch"], "feature-branch");
+    assert_eq!(branches[1]["number"], 101);
+    assert_eq!(branches[1]["state"], "OPEN");
+    assert_eq!(branches[1]["expected_base"], "feature-base");
+    assert_eq!(branches[1]["current"], true);
+    assert_eq!(branches[1]["flags"], serde_json::json!(["needs_sync"]));
+    assert_eq!(branches[2]["flags"], serde_json::json!(["needs_push"]));
+    assert_eq!(document["summary"]["needs_push"], 1);
+}
+
+#[test]
+fn status_json_on_default_branch_prints_empty_stack() {
+    let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    cmd.env("STCK_TEST_CURRENT_BRANCH", "main");
+    cmd.args(["status", "--json"]);
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let document: serde_json::Value =
+        serde_json::from_slice(&output).expect("status --json should print JSON");
+
+    assert_eq!(document["current_branch"], "main");
+    assert_eq!(document["branches"], serde_json::json!([]));
+}
@@ -23,7 +23,7 @@ fn submit_creates_pr_with_base_override() {
     let log = fs::read_to_string(&log_path).expect("submit log should exist");
     assert!(log.contains("pr create --base feature-base --head feature-branch"));
     assert!(log.contains(
-        "pr body --head feature-branch\nThis pull request is part of a stack.\n\n- **Position:** Child\n- **Base:** `feature-base`"
+        "pr body --head feature-branch\n<!-- stck:stack:start -->\nThis pull request is part of a stack.\n\n- **Position:** Child\n- **Base:** `feature-base`"
     ));
 }
 
@@ -45,7 +45,7 @@ fn submit_defaults_base_to_default_branch() {
 
     let log = fs::read_to_string(&log_path).expect("submit log should exist");
     assert!(log.contains(
-        "pr body --head feature-branch\nThis pull request is part of a stack.\n\n- **Position:** Root\n- **Base:** `main`"
+        "pr body --head feature-branch\n<!-- stck:stack:start -->\nThis pull request is part of a stack.\n\n- **Position:** Root\n- **Base:** `main`"
     ));
 }
 
@@ -251,3 +251,13 @@ fn submit_rejects_default_branch() {
         "error: cannot submit PR for default branch main; checkout a feature branch and retry",
     ));
 }
+
+#[test]
+fn submit_rejects_stack_with_base_override() {
+    let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    cmd.args(["submit", "--stack", "--base", "main"]);
+
+    cmd.assert().code(2).stderr(predicate::str::contains(
+        "the argument '--stack' cannot be used with '--base <BASE>'",
+    ));
+}
@@ -203,6 +203,37 @@ fn sync_rebases_when_default_branch_has_advanced() {
         ));
 }
 
+#[test]
+fn sync_restacks_every_child_subtree_of_a_branching_stack() {
+    let (temp, mut cmd) = stck_cmd_with_stubbed_tools();
+    let log_path = log_path(&temp, "stck-sync-tree.log");
+    cmd.env("STCK_TEST_LOG", log_path.as_os_str());
+    cmd.env("STCK_TEST_NON_LINEAR", "1");
+    cmd.arg("sync");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Step 1/3: rebasing feature-branch onto main"))
+        .stdout(predicate::str::contains(
+            "$ git rebase --onto refs/heads/feature-branch 2222222222222222222222222222222222222222 feature-child-a",
+        ))
+        .stdout(predicate::str::contains(
+            "$ git rebase --onto refs/heads/feature-branch 2222222222222222222222222222222222222222 feature-child-b",
+        ));
+
+    let log = fs::read_to_string(&log_path).expect("rebase log should exist");
+    let parent_idx = log
+        .find("1111111111111111111111111111111111111111 feature-branch")
+        .expect("parent rebase should be logged");
+    let child_a_idx = log
+        .find("2222222222222222222222222222222222222222 feature-child-a")
+        .expect("first child rebase should be logged");
+    let child_b_idx = log
+        .find("2222222222222222222222222222222222222222 feature-child-b")
+        .expect("second child rebase should be logged");
+    assert!(parent_idx < child_a_idx && child_a_idx < child_b_idx);
+}
+
 #[test]
 fn sync_continue_requires_existing_state() {
     let (_temp, mut cmd) = stck_cmd_with_stubbed_tools();